@@ -1,14 +1,44 @@
-from typing import List
+import contextlib
+from typing import List, Optional, Tuple
 
 import numpy
 import torch
 
-from .autograd import Graph, Tensor
+from . import autograd as _autograd
+from . import forward_ad, functional
+from .autograd import (
+    DType,
+    DTypeError,
+    GradError,
+    Graph,
+    ShapeError,
+    Tensor,
+    binary_cross_entropy_with_logits,
+    cross_entropy,
+    get_num_threads,
+    grad,
+    huber_loss,
+    is_grad_enabled,
+    is_inference_mode_enabled,
+    kl_div,
+    l1_loss,
+    mse_loss,
+    nll_loss,
+    set_num_threads,
+    smooth_l1_loss,
+)
+from .function import Function, FunctionCtx
+from .gradcheck import gradcheck, gradgradcheck
 
 """ Useful methods for the autograd module."""
 
 
-def tensor(shape: List[int], value: float, requires_grad: bool = False) -> Tensor:
+def tensor(
+    shape: List[int],
+    value: float,
+    requires_grad: bool = False,
+    dtype: Optional[DType] = None,
+) -> Tensor:
     """
     Create a Tensor with the given shape and value.
 
@@ -16,6 +46,7 @@ def tensor(shape: List[int], value: float, requires_grad: bool = False) -> Tenso
         shape (List[int]): The shape of the Tensor.
         value (float): The value to fill the Tensor with.
         requires_grad (bool): Whether the Tensor requires gradient computation.
+        dtype (Optional[DType]): The element type of the Tensor, float32 by default.
 
     Returns:
         Tensor: The created Tensor.
@@ -26,41 +57,204 @@ def tensor(shape: List[int], value: float, requires_grad: bool = False) -> Tenso
         requires_grad=requires_grad,
         grad=None,
         graph=None,
+        dtype=dtype,
     )
 
 
+def _dtype_from_numpy(np_dtype: numpy.dtype) -> DType:
+    if np_dtype == numpy.bool_:
+        return DType.Bool
+    if numpy.issubdtype(np_dtype, numpy.integer):
+        return DType.Int64
+    # Like the default dtype of torch, float64 has to be asked for
+    return DType.Float32
+
+
+def _dtype_from_torch(torch_dtype: torch.dtype) -> DType:
+    if torch_dtype == torch.bool:
+        return DType.Bool
+    if not torch_dtype.is_floating_point:
+        return DType.Int64
+    return DType.Float32
+
+
+_NUMPY_DTYPES = {
+    DType.Bool: numpy.bool_,
+    DType.Int64: numpy.int64,
+    DType.Float32: numpy.float32,
+    DType.Float64: numpy.float64,
+}
+
+_TORCH_DTYPES = {
+    DType.Bool: torch.bool,
+    DType.Int64: torch.int64,
+    DType.Float32: torch.float32,
+    DType.Float64: torch.float64,
+}
+
+
 """ We add some python methods to our objects here."""
 
 
-def from_numpy(cls, np_array: numpy.ndarray, requires_grad: bool = False) -> Tensor:
+def from_numpy(
+    cls,
+    np_array: numpy.ndarray,
+    requires_grad: bool = False,
+    dtype: Optional[DType] = None,
+) -> Tensor:
     return cls(
         shape=list(np_array.shape),
         data=np_array.flatten().tolist(),
         requires_grad=requires_grad,
         grad=None,
         graph=None,
+        dtype=dtype if dtype is not None else _dtype_from_numpy(np_array.dtype),
     )
 
 
-def from_torch(cls, torch_tensor: torch.Tensor, requires_grad: bool = False) -> Tensor:
+def from_torch(
+    cls,
+    torch_tensor: torch.Tensor,
+    requires_grad: bool = False,
+    dtype: Optional[DType] = None,
+) -> Tensor:
     return cls(
         shape=list(torch_tensor.shape),
         data=torch_tensor.flatten().tolist(),
         requires_grad=requires_grad,
         grad=None,
         graph=None,
+        dtype=dtype if dtype is not None else _dtype_from_torch(torch_tensor.dtype),
     )
 
 
 def to_numpy(self: Tensor) -> numpy.ndarray:
-    return numpy.array(self.get_data()).reshape(self.get_shape())
+    return numpy.array(
+        self.get_data(), dtype=_NUMPY_DTYPES[self.get_dtype()]
+    ).reshape(self.get_shape())
 
 
 def to_torch(self: Tensor) -> torch.Tensor:
-    return torch.tensor(self.get_data()).reshape(self.get_shape())
+    return torch.tensor(
+        self.get_data(), dtype=_TORCH_DTYPES[self.get_dtype()]
+    ).reshape(self.get_shape())
 
 
 Tensor.from_numpy = classmethod(from_numpy)  # type: ignore
 Tensor.from_torch = classmethod(from_torch)  # type: ignore
 Tensor.to_numpy = to_numpy  # type: ignore
 Tensor.to_torch = to_torch  # type: ignore
+
+""" Grad mode context managers, also usable as decorators."""
+
+
+class _GradMode(contextlib.ContextDecorator):
+    """Sets the grad mode of the current thread inside a `with` block."""
+
+    def __init__(
+        self, enabled: Optional[bool] = None, inference: Optional[bool] = None
+    ):
+        self._enabled = enabled
+        self._inference = inference
+        self._previous: List[Tuple[bool, bool]] = []
+
+    def __enter__(self):
+        enabled, inference = _autograd._grad_mode()
+        if self._enabled is not None:
+            enabled = self._enabled
+        if self._inference is not None:
+            inference = self._inference
+        self._previous.append(_autograd._set_grad_mode(enabled, inference))
+        return self
+
+    def __exit__(self, *exc) -> bool:
+        _autograd._set_grad_mode(*self._previous.pop())
+        return False
+
+
+class no_grad(_GradMode):
+    """Operations inside the block are not recorded in the graph."""
+
+    def __init__(self):
+        super().__init__(enabled=False)
+
+
+class enable_grad(_GradMode):
+    """Re-enables gradients inside a `no_grad` block."""
+
+    def __init__(self):
+        super().__init__(enabled=True)
+
+
+class inference_mode(_GradMode):
+    """
+    No graph is built inside the block, even within `enable_grad`. Meant for
+    evaluation loops.
+    """
+
+    def __init__(self, mode: bool = True):
+        super().__init__(inference=mode)
+
+
+class set_grad_enabled(_GradMode):
+    """
+    Enables or disables gradients. Called as a function the mode is set until
+    it is changed again, and used as a context manager it is restored at the
+    end of the block.
+    """
+
+    def __init__(self, mode: bool):
+        super().__init__(enabled=mode)
+        super().__enter__()
+        self._applied = True
+
+    def __enter__(self):
+        if self._applied:
+            self._applied = False
+            return self
+        return super().__enter__()
+
+
+""" Element types, named like their numpy and torch counterparts."""
+
+bool = _autograd.bool
+int64 = _autograd.int64
+float32 = _autograd.float32
+float64 = _autograd.float64
+
+# bool is left out, so that star imports don't shadow the builtin
+__all__ = [
+    "DType",
+    "DTypeError",
+    "Function",
+    "FunctionCtx",
+    "GradError",
+    "Graph",
+    "ShapeError",
+    "Tensor",
+    "binary_cross_entropy_with_logits",
+    "cross_entropy",
+    "enable_grad",
+    "float32",
+    "float64",
+    "forward_ad",
+    "functional",
+    "get_num_threads",
+    "grad",
+    "gradcheck",
+    "gradgradcheck",
+    "huber_loss",
+    "inference_mode",
+    "int64",
+    "is_grad_enabled",
+    "is_inference_mode_enabled",
+    "kl_div",
+    "l1_loss",
+    "mse_loss",
+    "nll_loss",
+    "no_grad",
+    "set_grad_enabled",
+    "set_num_threads",
+    "smooth_l1_loss",
+    "tensor",
+]
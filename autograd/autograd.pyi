@@ -1,8 +1,24 @@
-from typing import List, Optional
+from typing import Any, Callable, List, Optional, Tuple, Union
 
 import numpy
 import torch
 
+class DType:
+    Bool: DType
+    Int64: DType
+    Float32: DType
+    Float64: DType
+    def is_floating_point(self) -> bool: ...
+
+bool: DType
+int64: DType
+float32: DType
+float64: DType
+
+IndexItem = Union[int, slice, None, "ellipsis", Tensor, List[int], List[bool]]
+Index = Union[IndexItem, Tuple[IndexItem, ...]]
+Operand = Union[Tensor, float, int]
+
 class Tensor:
     # Rust-defined methods
 
@@ -13,37 +29,152 @@ class Tensor:
         requires_grad: bool,
         grad: Optional[List[float]],
         graph: Optional[Graph],
+        dtype: Optional[DType] = None,
     ): ...
     def get_shape(self) -> List[int]: ...
-    def get_data(self) -> List[float]: ...
+    def get_data(self) -> List[Union[float, int, bool]]: ...
+    def get_dtype(self) -> DType: ...
+    def to(self, dtype: DType) -> Tensor: ...
+    def get_strides(self) -> List[int]: ...
+    def get_offset(self) -> int: ...
+    def is_contiguous(self) -> bool: ...
     def get_requires_grad(self) -> bool: ...
     def get_grad(self) -> Optional[Tensor]: ...
     def set_grad(self, grad: Optional[Tensor]) -> None: ...
     def get_graph(self) -> Optional[Graph]: ...
     def set_graph(self, graph: Optional[Graph]) -> None: ...
-    def backward(self, grad: Optional[Tensor]) -> None: ...
-    def __add__(self, other: Tensor) -> Tensor: ...
-    def __sub__(self, other: Tensor) -> Tensor: ...
+    def detach(self) -> Tensor: ...
+    def requires_grad_(self, requires_grad: bool = True) -> Tensor: ...
+    def zero_grad(self, set_to_none: bool = True) -> None: ...
+    @property
+    def is_leaf(self) -> bool: ...
+    @property
+    def grad_fn(self) -> Optional[Graph]: ...
+    @property
+    def requires_grad(self) -> bool: ...
+    @requires_grad.setter
+    def requires_grad(self, requires_grad: bool) -> None: ...
+    @property
+    def grad(self) -> Optional[Tensor]: ...
+    @grad.setter
+    def grad(self, grad: Optional[Tensor]) -> None: ...
+    def register_hook(
+        self, hook: Callable[[Tensor], Optional[Tensor]]
+    ) -> RemovableHandle: ...
+    def retain_grad(self) -> None: ...
+    @property
+    def retains_grad(self) -> bool: ...
+    def backward(
+        self,
+        grad: Optional[Tensor] = None,
+        retain_graph: Optional[bool] = None,
+        create_graph: bool = False,
+    ) -> None: ...
+    def __add__(self, other: Operand) -> Tensor: ...
+    def __radd__(self, other: Operand) -> Tensor: ...
+    def __iadd__(self, other: Operand) -> Tensor: ...
+    def __sub__(self, other: Operand) -> Tensor: ...
+    def __rsub__(self, other: Operand) -> Tensor: ...
+    def __isub__(self, other: Operand) -> Tensor: ...
     def __neg__(self) -> Tensor: ...
-    def __mul__(self, other: Tensor) -> Tensor: ...
+    def __mul__(self, other: Operand) -> Tensor: ...
+    def __rmul__(self, other: Operand) -> Tensor: ...
+    def __imul__(self, other: Operand) -> Tensor: ...
+    def __truediv__(self, other: Operand) -> Tensor: ...
+    def __rtruediv__(self, other: Operand) -> Tensor: ...
+    def __itruediv__(self, other: Operand) -> Tensor: ...
     def __matmul__(self, other: Tensor) -> Tensor: ...
-    def transpose(self) -> Tensor: ...
+    def matmul(self, other: Tensor) -> Tensor: ...
+    def dot(self, other: Tensor) -> Tensor: ...
+    def mv(self, vec: Tensor) -> Tensor: ...
+    def outer(self, other: Tensor) -> Tensor: ...
+    def bmm(self, other: Tensor) -> Tensor: ...
+    def addmm(
+        self, mat1: Tensor, mat2: Tensor, beta: float = 1.0, alpha: float = 1.0
+    ) -> Tensor: ...
+    def transpose(self, dim0: int = -2, dim1: int = -1) -> Tensor: ...
+    def permute(self, dims: List[int]) -> Tensor: ...
+    def view(self, shape: List[int]) -> Tensor: ...
+    def reshape(self, shape: List[int]) -> Tensor: ...
+    def squeeze(self, dim: Optional[int] = None) -> Tensor: ...
+    def unsqueeze(self, dim: int) -> Tensor: ...
+    def slice(
+        self,
+        dim: int,
+        start: Optional[int] = None,
+        end: Optional[int] = None,
+        step: int = 1,
+    ) -> Tensor: ...
+    def contiguous(self) -> Tensor: ...
+    def __getitem__(self, key: Index) -> Tensor: ...
+    def __setitem__(
+        self, key: Index, value: Union[Tensor, float, int, bool]
+    ) -> None: ...
     def reduce_sum(self) -> Tensor: ...
+    def sum(
+        self, dim: Optional[Union[int, Tuple[int, ...]]] = None, keepdim: bool = False
+    ) -> Tensor: ...
+    def mean(
+        self, dim: Optional[Union[int, Tuple[int, ...]]] = None, keepdim: bool = False
+    ) -> Tensor: ...
+    def prod(
+        self, dim: Optional[Union[int, Tuple[int, ...]]] = None, keepdim: bool = False
+    ) -> Tensor: ...
+    def max(
+        self, dim: Optional[Union[int, Tuple[int, ...]]] = None, keepdim: bool = False
+    ) -> Tensor: ...
+    def min(
+        self, dim: Optional[Union[int, Tuple[int, ...]]] = None, keepdim: bool = False
+    ) -> Tensor: ...
+    def argmax(self, dim: Optional[int] = None, keepdim: bool = False) -> Tensor: ...
+    def argmin(self, dim: Optional[int] = None, keepdim: bool = False) -> Tensor: ...
     def relu(self) -> Tensor: ...
-    def softmax(self) -> Tensor: ...
+    def exp(self) -> Tensor: ...
+    def log(self) -> Tensor: ...
+    def log1p(self) -> Tensor: ...
+    def sqrt(self) -> Tensor: ...
+    def rsqrt(self) -> Tensor: ...
+    def sin(self) -> Tensor: ...
+    def cos(self) -> Tensor: ...
+    def tanh(self) -> Tensor: ...
+    def sigmoid(self) -> Tensor: ...
+    def abs(self) -> Tensor: ...
+    def __abs__(self) -> Tensor: ...
+    def sign(self) -> Tensor: ...
+    def gelu(self) -> Tensor: ...
+    def silu(self) -> Tensor: ...
+    def leaky_relu(self, negative_slope: float = 0.01) -> Tensor: ...
+    def elu(self, alpha: float = 1.0) -> Tensor: ...
+    def softplus(self, beta: float = 1.0, threshold: float = 20.0) -> Tensor: ...
+    def clamp(
+        self, min: Optional[float] = None, max: Optional[float] = None
+    ) -> Tensor: ...
+    def pow(self, exponent: Operand) -> Tensor: ...
+    def __pow__(self, exponent: Operand) -> Tensor: ...
+    def __rpow__(self, base: Operand) -> Tensor: ...
+    def __ipow__(self, exponent: Operand) -> Tensor: ...
+    def softmax(self, dim: int = -1) -> Tensor: ...
+    def log_softmax(self, dim: int = -1) -> Tensor: ...
+    def logsumexp(self, dim: int, keepdim: bool = False) -> Tensor: ...
     def broadcast(self, shape: List[int]) -> Tensor: ...
+    def expand(self, sizes: List[int]) -> Tensor: ...
 
     # Python-defined methods
 
     @classmethod
     def from_numpy(
-        cls, np_array: numpy.ndarray, requires_grad: bool = False
+        cls,
+        np_array: numpy.ndarray,
+        requires_grad: bool = False,
+        dtype: Optional[DType] = None,
     ) -> Tensor: ...
     """
     Convert a numpy array to a Tensor. The underlying data will be copied.
 
     Args:
         np_array (numpy.ndarray): The numpy array to convert.
+        dtype (Optional[DType]): The element type of the Tensor. By default
+            bool and integer arrays keep their kind, and floats are float32.
 
     Returns:
         Tensor: The converted Tensor.
@@ -51,13 +182,18 @@ class Tensor:
 
     @classmethod
     def from_torch(
-        cls, torch_tensor: torch.Tensor, requires_grad: bool = False
+        cls,
+        torch_tensor: torch.Tensor,
+        requires_grad: bool = False,
+        dtype: Optional[DType] = None,
     ) -> Tensor: ...
     """
     Convert a torch.Tensor to a Tensor. The underlying data will be copied.
 
     Args:
         torch_tensor (torch.tensor): The torch tensor to convert.
+        dtype (Optional[DType]): The element type of the Tensor. By default
+            bool and integer tensors keep their kind, and floats are float32.
 
     Returns:
         Tensor: The converted Tensor.
@@ -79,4 +215,120 @@ class Tensor:
         torch.Tensor: The torch tensor representation of the Tensor.
     """
 
-class Graph: ...
+class Graph:
+    def name(self) -> str: ...
+    def register_hook(
+        self,
+        hook: Callable[
+            [List[Optional[Tensor]], Tensor], Optional[List[Optional[Tensor]]]
+        ],
+    ) -> RemovableHandle: ...
+
+class RemovableHandle:
+    def remove(self) -> None: ...
+
+def grad(
+    outputs: Union[Tensor, List[Tensor]],
+    inputs: Union[Tensor, List[Tensor]],
+    grad_outputs: Union[Optional[Tensor], List[Optional[Tensor]], None] = None,
+    retain_graph: Optional[bool] = None,
+    create_graph: bool = False,
+    allow_unused: bool = False,
+) -> List[Optional[Tensor]]: ...
+def _vjp(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    v: Optional[List[Tensor]],
+    create_graph: bool,
+    strict: bool,
+) -> Tuple[List[Tensor], List[Tensor]]: ...
+def _jvp(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    v: Optional[List[Tensor]],
+    create_graph: bool,
+    strict: bool,
+) -> Tuple[List[Tensor], List[Tensor]]: ...
+def _jacobian(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> List[List[Tensor]]: ...
+def _hessian(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> List[List[Tensor]]: ...
+def _hvp(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    v: Optional[List[Tensor]],
+    create_graph: bool,
+    strict: bool,
+) -> Tuple[Tensor, List[Tensor]]: ...
+def _apply_function(function: type, ctx: Any, args: Tuple[Any, ...]) -> Any: ...
+def _gradcheck(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    eps: float,
+    atol: float,
+    rtol: float,
+) -> Optional[str]: ...
+def _gradgradcheck(
+    func: Callable[..., List[Tensor]],
+    inputs: List[Tensor],
+    grad_outputs: Optional[List[Tensor]],
+    eps: float,
+    atol: float,
+    rtol: float,
+) -> Optional[str]: ...
+def _enter_dual_level() -> int: ...
+def _exit_dual_level(level: int) -> None: ...
+def _make_dual(tensor: Tensor, tangent: Tensor) -> Tensor: ...
+def _unpack_dual(tensor: Tensor) -> Tuple[Tensor, Optional[Tensor]]: ...
+def is_grad_enabled() -> bool: ...
+def is_inference_mode_enabled() -> bool: ...
+def _grad_mode() -> Tuple[bool, bool]: ...
+def _set_grad_mode(enabled: bool, inference: bool) -> Tuple[bool, bool]: ...
+def set_num_threads(num_threads: int) -> None: ...
+def get_num_threads() -> int: ...
+def mse_loss(input: Tensor, target: Tensor, reduction: str = "mean") -> Tensor: ...
+def l1_loss(input: Tensor, target: Tensor, reduction: str = "mean") -> Tensor: ...
+def huber_loss(
+    input: Tensor, target: Tensor, reduction: str = "mean", delta: float = 1.0
+) -> Tensor: ...
+def smooth_l1_loss(
+    input: Tensor, target: Tensor, reduction: str = "mean", beta: float = 1.0
+) -> Tensor: ...
+def nll_loss(
+    input: Tensor,
+    target: Tensor,
+    weight: Optional[Tensor] = None,
+    ignore_index: int = -100,
+    reduction: str = "mean",
+) -> Tensor: ...
+def cross_entropy(
+    input: Tensor,
+    target: Tensor,
+    weight: Optional[Tensor] = None,
+    ignore_index: int = -100,
+    reduction: str = "mean",
+) -> Tensor: ...
+def binary_cross_entropy_with_logits(
+    input: Tensor,
+    target: Tensor,
+    weight: Optional[Tensor] = None,
+    reduction: str = "mean",
+    pos_weight: Optional[Tensor] = None,
+) -> Tensor: ...
+def kl_div(
+    input: Tensor, target: Tensor, reduction: str = "mean", log_target: bool = False
+) -> Tensor: ...
+
+class ShapeError(ValueError): ...
+class DTypeError(ValueError): ...
+class GradError(RuntimeError): ...
@@ -0,0 +1,71 @@
+"""Forward mode differentiation with dual tensors, like torch.autograd.forward_ad."""
+
+import contextlib
+from typing import NamedTuple, Optional
+
+from . import autograd as _autograd
+from .autograd import Tensor
+
+
+class UnpackedDualTensor(NamedTuple):
+    primal: Tensor
+    tangent: Optional[Tensor]
+
+
+def enter_dual_level() -> int:
+    """
+    Enter a new dual level on the current thread. Dual levels cannot be nested.
+
+    Returns:
+        int: The level, to be passed to `exit_dual_level`.
+    """
+    return _autograd._enter_dual_level()
+
+
+def exit_dual_level(level: int) -> None:
+    """
+    Exit the current dual level, which discards the tangents computed in it.
+
+    Args:
+        level (int): The level returned by `enter_dual_level`.
+    """
+    _autograd._exit_dual_level(level)
+
+
+def make_dual(tensor: Tensor, tangent: Tensor) -> Tensor:
+    """
+    Create a dual tensor, a view of `tensor` carrying `tangent` in the current
+    dual level. Operations on dual tensors compute the tangents of their outputs.
+
+    Args:
+        tensor (Tensor): The primal, a floating point Tensor.
+        tangent (Tensor): The tangent, with the shape and dtype of `tensor`.
+
+    Returns:
+        Tensor: The dual tensor.
+    """
+    return _autograd._make_dual(tensor, tangent)
+
+
+def unpack_dual(tensor: Tensor) -> UnpackedDualTensor:
+    """
+    Split a dual tensor into its primal and its tangent in the current dual
+    level, which is None if it has none.
+    """
+    return UnpackedDualTensor(*_autograd._unpack_dual(tensor))
+
+
+class dual_level(contextlib.ContextDecorator):
+    """Tangents can be attached to tensors and computed inside the block."""
+
+    def __init__(self):
+        self._level: Optional[int] = None
+
+    def __enter__(self) -> int:
+        self._level = enter_dual_level()
+        return self._level
+
+    def __exit__(self, *exc) -> bool:
+        exit_dual_level(self._level)
+        self._level = None
+        return False
@@ -0,0 +1,49 @@
+"""Differentiable functions defined in Python, like torch.autograd.Function."""
+
+from typing import Any, Tuple
+
+from . import autograd as _autograd
+from .autograd import Tensor
+
+
+class FunctionCtx:
+    """
+    Given to `forward` and `backward` to share state between them. Tensors are
+    saved with `save_for_backward`, other values can be set as attributes.
+    """
+
+    def __init__(self, args: Tuple[Any, ...]):
+        self.needs_input_grad = tuple(
+            isinstance(arg, Tensor) and arg.requires_grad for arg in args
+        )
+        self.saved_tensors: Tuple[Tensor, ...] = ()
+
+    def save_for_backward(self, *tensors: Tensor) -> None:
+        """Save tensors for `backward`, in `ctx.saved_tensors`."""
+        self.saved_tensors = tensors
+
+
+class Function:
+    """
+    Base class for operations whose gradients are written in Python. Subclasses
+    define static methods `forward(ctx, *args)`, returning a Tensor or a tuple
+    of Tensors, and `backward(ctx, *grads)`, given the gradient of each output
+    and returning one gradient or None per argument of `forward`. They can also
+    define `jvp(ctx, *tangents)` for forward mode differentiation, otherwise the
+    tangents are those computed by the operations in `forward`.
+
+    Forward runs without recording a graph. The function is called with
+    `apply`, e.g. `Exp.apply(x)`.
+    """
+
+    @staticmethod
+    def forward(ctx: FunctionCtx, *args: Any) -> Any:
+        raise NotImplementedError("Subclasses of Function must implement forward")
+
+    @staticmethod
+    def backward(ctx: FunctionCtx, *grads: Tensor) -> Any:
+        raise NotImplementedError("Subclasses of Function must implement backward")
+
+    @classmethod
+    def apply(cls, *args: Any) -> Any:
+        return _autograd._apply_function(cls, FunctionCtx(args), args)
@@ -0,0 +1,191 @@
+"""Differentiation of functions, like torch.autograd.functional."""
+
+from typing import Any, Callable, List, Optional, Sequence, Tuple, Union
+
+from . import autograd as _autograd
+from .autograd import Tensor
+
+Tensors = Union[Tensor, Sequence[Tensor]]
+
+
+def _as_tuple(tensors: Tensors) -> Tuple[bool, List[Tensor]]:
+    if isinstance(tensors, Tensor):
+        return False, [tensors]
+    return True, list(tensors)
+
+
+def _unpack(is_tuple: bool, tensors: List[Any]) -> Any:
+    return tuple(tensors) if is_tuple else tensors[0]
+
+
+class _Function:
+    """
+    Calls `func` with the inputs as positional arguments and returns its outputs
+    as a list, remembering whether they were a tuple.
+    """
+
+    def __init__(self, func: Callable[..., Tensors]):
+        self._func = func
+        self.is_tuple = False
+
+    def __call__(self, *inputs: Tensor) -> List[Tensor]:
+        self.is_tuple, outputs = _as_tuple(self._func(*inputs))
+        return outputs
+
+
+def vjp(
+    func: Callable[..., Tensors],
+    inputs: Tensors,
+    v: Optional[Tensors] = None,
+    create_graph: bool = False,
+    strict: bool = False,
+) -> Tuple[Tensors, Tensors]:
+    """
+    Compute the product of a vector with the Jacobian of a function.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a Tensor or a tuple of Tensors.
+        inputs (Tensor or tuple of Tensors): The point at which to differentiate.
+        v (Tensor or tuple of Tensors): The vector, shaped like the outputs. Can
+            be None if the output is a scalar.
+        create_graph (bool): Whether the results can be differentiated again.
+        strict (bool): Whether to raise a GradError if an output does not depend
+            on an input, instead of returning zeros.
+
+    Returns:
+        The outputs of the function and the vector-Jacobian product, shaped like
+        the inputs.
+    """
+    f = _Function(func)
+    is_tuple, inputs = _as_tuple(inputs)
+    if v is not None:
+        _, v = _as_tuple(v)
+    outputs, vjp = _autograd._vjp(f, inputs, v, create_graph, strict)
+    return _unpack(f.is_tuple, outputs), _unpack(is_tuple, vjp)
+
+
+def jvp(
+    func: Callable[..., Tensors],
+    inputs: Tensors,
+    v: Optional[Tensors] = None,
+    create_graph: bool = False,
+    strict: bool = False,
+) -> Tuple[Tensors, Tensors]:
+    """
+    Compute the product of the Jacobian of a function with a vector. It is
+    computed with two backward passes, which costs about twice a `vjp`.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a Tensor or a tuple of Tensors.
+        inputs (Tensor or tuple of Tensors): The point at which to differentiate.
+        v (Tensor or tuple of Tensors): The vector, shaped like the inputs. Can be
+            None if the input is a scalar.
+        create_graph (bool): Whether the results can be differentiated again.
+        strict (bool): Whether to raise a GradError if an output does not depend
+            on an input, instead of returning zeros.
+
+    Returns:
+        The outputs of the function and the Jacobian-vector product, shaped like
+        the outputs.
+    """
+    f = _Function(func)
+    _, inputs = _as_tuple(inputs)
+    if v is not None:
+        _, v = _as_tuple(v)
+    outputs, jvp = _autograd._jvp(f, inputs, v, create_graph, strict)
+    return _unpack(f.is_tuple, outputs), _unpack(f.is_tuple, jvp)
+
+
+def jacobian(
+    func: Callable[..., Tensors],
+    inputs: Tensors,
+    create_graph: bool = False,
+    strict: bool = False,
+    vectorize: bool = False,
+) -> Any:
+    """
+    Compute the Jacobian of a function, one backward pass per output element.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a Tensor or a tuple of Tensors.
+        inputs (Tensor or tuple of Tensors): The point at which to differentiate.
+        create_graph (bool): Whether the Jacobian can be differentiated again.
+        strict (bool): Whether to raise a GradError if an output does not depend
+            on an input, instead of returning zeros.
+        vectorize (bool): Whether to compute the backward passes in parallel.
+
+    Returns:
+        The Jacobian of each output with respect to each input, with the shape of
+        the output followed by the shape of the input. It is a Tensor if both the
+        inputs and the outputs are Tensors, otherwise a tuple indexed by output,
+        by input, or by output then input.
+    """
+    f = _Function(func)
+    is_tuple, inputs = _as_tuple(inputs)
+    jacobian = _autograd._jacobian(f, inputs, create_graph, strict, vectorize)
+    return _unpack(f.is_tuple, [_unpack(is_tuple, row) for row in jacobian])
+
+
+def hessian(
+    func: Callable[..., Tensor],
+    inputs: Tensors,
+    create_graph: bool = False,
+    strict: bool = False,
+    vectorize: bool = False,
+) -> Any:
+    """
+    Compute the Hessian of a scalar function, the Jacobian of its gradient.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a scalar Tensor.
+        inputs (Tensor or tuple of Tensors): The point at which to differentiate.
+        create_graph (bool): Whether the Hessian can be differentiated again.
+        strict (bool): Whether to raise a GradError if the gradient does not
+            depend on an input, instead of returning zeros.
+        vectorize (bool): Whether to compute the backward passes in parallel.
+
+    Returns:
+        The Hessian, a Tensor if the inputs are a Tensor, otherwise a tuple of
+        tuples indexed by pairs of inputs.
+    """
+    is_tuple, inputs = _as_tuple(inputs)
+    hessian = _autograd._hessian(
+        _Function(func), inputs, create_graph, strict, vectorize
+    )
+    return _unpack(is_tuple, [_unpack(is_tuple, row) for row in hessian])
+
+
+def hvp(
+    func: Callable[..., Tensor],
+    inputs: Tensors,
+    v: Optional[Tensors] = None,
+    create_graph: bool = False,
+    strict: bool = False,
+) -> Tuple[Tensor, Tensors]:
+    """
+    Compute the product of the Hessian of a scalar function with a vector,
+    without computing the Hessian.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a scalar Tensor.
+        inputs (Tensor or tuple of Tensors): The point at which to differentiate.
+        v (Tensor or tuple of Tensors): The vector, shaped like the inputs. Can be
+            None if the input is a scalar.
+        create_graph (bool): Whether the results can be differentiated again.
+        strict (bool): Whether to raise a GradError if the gradient does not
+            depend on an input, instead of returning zeros.
+
+    Returns:
+        The output of the function and the Hessian-vector product, shaped like
+        the inputs.
+    """
+    is_tuple, inputs = _as_tuple(inputs)
+    if v is not None:
+        _, v = _as_tuple(v)
+    output, hvp = _autograd._hvp(_Function(func), inputs, v, create_graph, strict)
+    return output, _unpack(is_tuple, hvp)
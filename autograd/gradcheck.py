@@ -0,0 +1,81 @@
+"""Finite difference checks of gradients, like torch.autograd.gradcheck."""
+
+from typing import Callable, Optional
+
+from . import autograd as _autograd
+from .autograd import GradError
+from .functional import Tensors, _as_tuple, _Function
+
+
+def gradcheck(
+    func: Callable[..., Tensors],
+    inputs: Tensors,
+    eps: float = 1e-6,
+    atol: float = 1e-5,
+    rtol: float = 1e-3,
+    raise_exception: bool = True,
+) -> bool:
+    """
+    Check the gradients of a function against finite differences, with respect
+    to the inputs that require grad. Use float64 inputs, float32 is not precise
+    enough for the default eps.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a Tensor or a tuple of Tensors.
+        inputs (Tensor or tuple of Tensors): The point at which to check.
+        eps (float): The perturbation of the inputs for finite differences.
+        atol (float): The absolute tolerance.
+        rtol (float): The relative tolerance.
+        raise_exception (bool): Whether to raise a GradError describing the first
+            mismatch, instead of returning False.
+
+    Returns:
+        bool: Whether the gradients match.
+    """
+    _, inputs = _as_tuple(inputs)
+    mismatch = _autograd._gradcheck(_Function(func), inputs, eps, atol, rtol)
+    return _check(mismatch, raise_exception)
+
+
+def gradgradcheck(
+    func: Callable[..., Tensors],
+    inputs: Tensors,
+    grad_outputs: Optional[Tensors] = None,
+    eps: float = 1e-6,
+    atol: float = 1e-5,
+    rtol: float = 1e-3,
+    raise_exception: bool = True,
+) -> bool:
+    """
+    Check the second order gradients of a function against finite differences
+    of its gradients.
+
+    Args:
+        func (Callable): The function, taking the inputs as arguments and returning
+            a Tensor or a tuple of Tensors.
+        inputs (Tensor or tuple of Tensors): The point at which to check.
+        grad_outputs (Tensor or tuple of Tensors): The gradients of the outputs
+            that require grad. A fixed tensor is used for each by default.
+        eps (float): The perturbation of the inputs for finite differences.
+        atol (float): The absolute tolerance.
+        rtol (float): The relative tolerance.
+        raise_exception (bool): Whether to raise a GradError describing the first
+            mismatch, instead of returning False.
+
+    Returns:
+        bool: Whether the second order gradients match.
+    """
+    _, inputs = _as_tuple(inputs)
+    if grad_outputs is not None:
+        _, grad_outputs = _as_tuple(grad_outputs)
+    mismatch = _autograd._gradgradcheck(
+        _Function(func), inputs, grad_outputs, eps, atol, rtol
+    )
+    return _check(mismatch, raise_exception)
+
+
+def _check(mismatch: Optional[str], raise_exception: bool) -> bool:
+    if mismatch is not None and raise_exception:
+        raise GradError(mismatch)
+    return mismatch is None
@@ -0,0 +1,54 @@
+"""
+Times pointwise operations on contiguous operands, whose inner loop the
+compiler vectorises (with AVX2 when the CPU has it), against the same
+operations on transposed views, which take the strided scalar loop. Part of
+the gap comes from the cache misses of reading the transposed views.
+"""
+
+import time
+
+import autograd
+from autograd import Tensor
+
+N = 1024
+K = 50
+OPS = [
+    ("add", lambda a, b: a + b),
+    ("mul", lambda a, b: a * b),
+    ("relu", lambda a, b: a.relu()),
+    ("exp", lambda a, b: a.exp()),
+]
+
+
+def timed(op, a, b):
+    op(a, b)
+    t = time.perf_counter()
+    for _ in range(K):
+        op(a, b)
+    return (time.perf_counter() - t) / K
+
+
+def main():
+    # A single thread, so that the gap only comes from the inner loops
+    autograd.set_num_threads(1)
+    with open("/proc/cpuinfo") as cpuinfo:
+        flags = cpuinfo.read()
+    print(f"avx2: {'avx2' in flags}, fma: {' fma' in flags}")
+
+    a = autograd.tensor([N, N], 0.5)
+    b = autograd.tensor([N, N], 1.5)
+    a_t, b_t = a.permute([1, 0]), b.permute([1, 0])
+    assert isinstance(a_t, Tensor) and a_t.get_strides() == [1, N]
+
+    for name, op in OPS:
+        contiguous = timed(op, a, b)
+        strided = timed(op, a_t, b_t)
+        print(
+            f"{name:>5}: contiguous {contiguous * 1e3:6.2f} ms, "
+            f"transposed {strided * 1e3:6.2f} ms, "
+            f"speedup {strided / contiguous:4.1f}x"
+        )
+
+
+if __name__ == "__main__":
+    main()
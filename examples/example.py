@@ -43,8 +43,7 @@ class Network(torch.nn.Module):
     def forward(self, x: torch.Tensor) -> torch.Tensor:
         x = self.fc1(x)
         x = torch.relu(x)
-        x = self.fc2(x)
-        return torch.softmax(x, dim=1)
+        return self.fc2(x)
 
 
 def network(
@@ -56,8 +55,7 @@ def network(
 ) -> autograd.Tensor:
     x = x @ w1.transpose() + b1
     x = x.relu()
-    x = x @ w2.transpose() + b2
-    return x.softmax()
+    return x @ w2.transpose() + b2
 
 
 def unwrap(t: autograd.Tensor | None) -> autograd.Tensor:
@@ -87,12 +85,10 @@ def main(hidden_size: int = 100):
     for k, (x, y) in tqdm(enumerate(train_loader), total=len(train_loader)):
         batch_size = x.shape[0]
         x = x.view(batch_size, input_size)
-        y_true = torch.zeros(batch_size, output_size)
-        y_true[torch.arange(batch_size), y] = 1.0
 
         # Torch model
         y_pred = torch_net(x)
-        loss = ((y_pred - y_true) ** 2).sum()
+        loss = torch.nn.functional.cross_entropy(y_pred, y)
         torch_loss.append(loss.item())
         loss.backward()
         optimizer.step()
@@ -100,18 +96,15 @@ def main(hidden_size: int = 100):
 
         # Autograd model
         x = autograd.Tensor.from_torch(x, requires_grad=False)
-        y_true = autograd.Tensor.from_torch(y_true, requires_grad=False)
+        y_true = autograd.Tensor.from_torch(y)
         y_pred = network(x, w1, w2, b1, b2)
-        loss = ((y_pred - y_true) * (y_pred - y_true)).reduce_sum()
+        loss = autograd.cross_entropy(y_pred, y_true)
         loss.backward(None)
         autograd_loss.append(loss.get_data()[0])
-        w1 = w1 - lr * unwrap(w1.get_grad())
-        w2 = w2 - lr * unwrap(w2.get_grad())
-        b1 = b1 - lr * unwrap(b1.get_grad())
-        b2 = b2 - lr * unwrap(b2.get_grad())
-        for t in [w1, w2, b1, b2]:
-            t.set_grad(None)
-            t.set_graph(None)
+        with autograd.no_grad():
+            for t in [w1, w2, b1, b2]:
+                t -= lr * unwrap(t.get_grad())
+                t.zero_grad()
 
     torch_right, torch_wrong, autograd_right, autograd_wrong = 0, 0, 0, 0
     for x, y in test_loader:
@@ -119,13 +112,16 @@ def main(hidden_size: int = 100):
         x = x.view(batch_size, input_size)
 
         # Torch model
-        y_pred = torch_net(x)
+        with torch.inference_mode():
+            y_pred = torch_net(x)
         y_pred = torch.max(y_pred, dim=1).indices
         torch_right += (y == y_pred).sum()
         torch_wrong += (y != y_pred).sum()
 
         # Autograd model
-        y_pred = network(autograd.Tensor.from_torch(x), w1, w2, b1, b2).to_torch()
+        with autograd.inference_mode():
+            y_pred = network(autograd.Tensor.from_torch(x), w1, w2, b1, b2)
+        y_pred = y_pred.to_torch()
         y_pred = torch.max(y_pred, dim=1).indices
         autograd_right += (y == y_pred).sum()
         autograd_wrong += (y != y_pred).sum()
@@ -1,62 +1,203 @@
-use crate::{objects::Tensor, utils::new_tensor_simple};
+use crate::{
+    dispatch_float,
+    dtype::Numeric,
+    engine::run_backward,
+    errors::{AutogradError, Result},
+    objects::Tensor,
+};
 use pyo3::prelude::*;
 
+/* A node of the computation graph */
 pub trait Backward {
-    fn do_backward(&mut self, grad: Option<Tensor>, input: Option<Tensor>);
+    /// The tensors the operation was computed from.
+    fn inputs(&self) -> Vec<Tensor>;
+
+    /// Computes the gradients of the inputs given the gradient of the output.
+    /// `output` is the tensor produced by the operation.
+    /// The returned gradients follow the order of `inputs`, and can be None
+    /// for inputs that do not require a gradient.
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>>;
+
+    /// Like `do_backward`, for operations that can fail, e.g. because they run
+    /// code written in Python. This is what the engine calls.
+    fn try_backward(&mut self, grad: Tensor, output: Tensor) -> Result<Vec<Option<Tensor>>> {
+        Ok(self.do_backward(grad, output))
+    }
+
+    /// Whether the engine can run the operation on the thread pool, alongside
+    /// other branches of the graph. Operations calling into Python run on the
+    /// thread that started the backward pass, since a worker waiting for the
+    /// GIL could hold up work the GIL holder is waiting for.
+    fn parallel(&self) -> bool {
+        true
+    }
+
+    /// The name of the operation, e.g. `AddOperation`.
+    fn name(&self) -> String {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
 }
 
-impl Backward for Tensor {
-    /// Backward pass for the tensor.
-    /// If the tensor is a scalar, it will create a gradient of 1.
-    /// Otherwise, it will panic if no gradient is provided.
-    fn do_backward(&mut self, grad: Option<Tensor>, input: Option<Tensor>) {
-        if !self.get_requires_grad() {
-            return;
-        }
-        if self.get_grad().is_none() {
-            // TODO: without this we can deadlock.
-            // self.core.write().unwrap().grad should be hidden within the tensor API
-            let length = self.get_data_ref().len();
-            /* Initializing the gradient to zero if it is None */
-            self.core.write().unwrap().grad =
-                Some(new_tensor_simple(self.get_shape(), vec![0.0; length]));
+/// The gradient backpropagated from `t`: `grad` after checking it matches
+/// the tensor, or 1 if the tensor is a scalar.
+fn initial_grad(t: &Tensor, grad: Option<Tensor>) -> Result<Tensor> {
+    if !t.get_requires_grad() {
+        return Err(AutogradError::MissingGrad(
+            "Backward called on a tensor that does not require grad".to_string(),
+        ));
+    }
+    match grad {
+        Some(grad) => {
+            t.check_grad(&grad)?;
+            Ok(grad)
         }
-        if grad.is_none() {
+        None => {
             /* grad can be None if the tensor is a scalar */
-            if self.get_shape().len() > 1 || self.get_shape()[0] != 1 {
-                panic!("Backward requires grad to be provided for non-scalar tensors");
-            } else {
-                self.core.write().unwrap().grad =
-                    Some(Tensor::new(vec![1], vec![1.0], false, None, None));
+            if t.get_shape() != vec![1] {
+                return Err(AutogradError::NonScalarBackward(t.get_shape()));
             }
-        } else {
-            /* Accumulating the gradient */
-            let current_grad = self.core.read().unwrap().grad.clone().unwrap();
-            self.core.write().unwrap().grad = Some(grad.unwrap() + current_grad);
+            dispatch_float!(t.dtype, T => Tensor::new(vec![1], vec![T::ONE], false, None, None))
         }
-        if !input.is_none() {
-            panic!("Expected input to be None for Tensor backward");
+    }
+}
+
+/// Backward pass for the tensor.
+/// If the tensor is a scalar, it will create a gradient of 1.
+/// Otherwise, the gradient must be provided and match the tensor shape and dtype.
+/// The graph is released afterwards, unless `retain_graph` is set. With
+/// `create_graph`, the gradients have their own graph and can be differentiated.
+pub fn backward(
+    t: &Tensor,
+    grad: Option<Tensor>,
+    retain_graph: bool,
+    create_graph: bool,
+) -> Result<()> {
+    let grad = initial_grad(t, grad)?;
+    run_backward(vec![(t.clone(), grad)], None, retain_graph, create_graph)?;
+    Ok(())
+}
+
+/// The gradients of `outputs` with respect to each of `inputs`, weighted by
+/// `grad_outputs` like in `backward`. The inputs can be any tensors requiring
+/// grad, not only leaves, and the `grad` of the tensors is left untouched.
+/// Inputs the outputs don't depend on are an error, or get None with
+/// `allow_unused`.
+pub fn grad(
+    outputs: Vec<Tensor>,
+    inputs: Vec<Tensor>,
+    grad_outputs: Vec<Option<Tensor>>,
+    retain_graph: bool,
+    create_graph: bool,
+    allow_unused: bool,
+) -> Result<Vec<Option<Tensor>>> {
+    if inputs.iter().any(|input| !input.get_requires_grad()) {
+        return Err(AutogradError::MissingGrad(
+            "One of the differentiated tensors does not require grad".to_string(),
+        ));
+    }
+    if grad_outputs.len() != outputs.len() {
+        return Err(AutogradError::InvalidArgument(format!(
+            "Expected {} grad_outputs, got {}",
+            outputs.len(),
+            grad_outputs.len()
+        )));
+    }
+    let roots = outputs
+        .into_iter()
+        .zip(grad_outputs)
+        .map(|(output, grad)| Ok((output.clone(), initial_grad(&output, grad)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let grads = run_backward(roots, Some(&inputs), retain_graph, create_graph)?;
+    if !allow_unused && grads.iter().any(|grad| grad.is_none()) {
+        return Err(AutogradError::MissingGrad(
+            "One of the differentiated tensors was not used to compute the outputs, \
+             set allow_unused=True to get None as its gradient"
+                .to_string(),
+        ));
+    }
+    Ok(grads)
+}
+
+/// One tensor or a sequence of tensors, as accepted from Python.
+#[derive(FromPyObject)]
+pub enum Tensors {
+    Single(Tensor),
+    Multiple(Vec<Tensor>),
+}
+
+impl From<Tensors> for Vec<Tensor> {
+    fn from(tensors: Tensors) -> Self {
+        match tensors {
+            Tensors::Single(tensor) => vec![tensor],
+            Tensors::Multiple(tensors) => tensors,
         }
+    }
+}
 
-        /* Go up the graph */
-        match self.get_graph() {
-            None => {
-                return;
-            }
-            Some(ref mut graph) => {
-                graph
-                    .0
-                    .write()
-                    .unwrap()
-                    .do_backward(self.get_grad(), Some(self.clone()));
-            }
+#[derive(FromPyObject)]
+pub enum OptionalTensors {
+    Single(Option<Tensor>),
+    Multiple(Vec<Option<Tensor>>),
+}
+
+impl From<OptionalTensors> for Vec<Option<Tensor>> {
+    fn from(tensors: OptionalTensors) -> Self {
+        match tensors {
+            OptionalTensors::Single(tensor) => vec![tensor],
+            OptionalTensors::Multiple(tensors) => tensors,
         }
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn backward(&mut self, grad: Option<Tensor>) {
-        self.do_backward(grad, None);
+    /// `retain_graph` defaults to `create_graph`, since the graph of the
+    /// gradients refers to the graph being differentiated.
+    #[pyo3(name = "backward", signature = (grad=None, retain_graph=None, create_graph=false))]
+    pub fn py_backward(
+        &self,
+        py: Python<'_>,
+        grad: Option<Tensor>,
+        retain_graph: Option<bool>,
+        create_graph: bool,
+    ) -> PyResult<()> {
+        let retain_graph = retain_graph.unwrap_or(create_graph);
+        Ok(py.allow_threads(|| backward(self, grad, retain_graph, create_graph))?)
     }
 }
+
+#[pyfunction(name = "grad")]
+#[pyo3(signature = (outputs, inputs, grad_outputs=None, retain_graph=None, create_graph=false, allow_unused=false))]
+pub fn py_grad(
+    py: Python<'_>,
+    outputs: Tensors,
+    inputs: Tensors,
+    grad_outputs: Option<OptionalTensors>,
+    retain_graph: Option<bool>,
+    create_graph: bool,
+    allow_unused: bool,
+) -> PyResult<Vec<Option<Tensor>>> {
+    let outputs: Vec<Tensor> = outputs.into();
+    let grad_outputs = match grad_outputs {
+        None => vec![None; outputs.len()],
+        Some(grad_outputs) => grad_outputs.into(),
+    };
+    let retain_graph = retain_graph.unwrap_or(create_graph);
+    let inputs = inputs.into();
+    Ok(py.allow_threads(|| {
+        grad(
+            outputs,
+            inputs,
+            grad_outputs,
+            retain_graph,
+            create_graph,
+            allow_unused,
+        )
+    })?)
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_grad, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use pyo3::prelude::*;
+use std::{
+    fmt::Debug,
+    iter::{Product, Sum},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub},
+};
+
+/* Element types of tensors. They are ordered for type promotion: the result of
+ * a binary operation has the largest type of its operands. */
+
+#[pyclass(eq, eq_int, hash, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DType {
+    Bool,
+    Int64,
+    Float32,
+    Float64,
+}
+
+impl DType {
+    pub fn promote(self, other: DType) -> DType {
+        self.max(other)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DType::Bool => "bool",
+            DType::Int64 => "int64",
+            DType::Float32 => "float32",
+            DType::Float64 => "float64",
+        }
+    }
+}
+
+#[pymethods]
+impl DType {
+    pub fn is_floating_point(&self) -> bool {
+        matches!(self, DType::Float32 | DType::Float64)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("autograd.{}", self.name())
+    }
+}
+
+/* The storage of a tensor holds elements of a single type */
+pub enum Buffer {
+    Bool(Vec<bool>),
+    Int64(Vec<i64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+}
+
+pub trait Element: Copy + Default + PartialEq + PartialOrd + Send + Sync + Debug + 'static {
+    const DTYPE: DType;
+
+    fn into_buffer(data: Vec<Self>) -> Buffer;
+    /// Panics if the buffer holds another type.
+    fn from_buffer(buffer: &Buffer) -> &[Self];
+    /// Panics if the buffer holds another type.
+    fn from_buffer_mut(buffer: &mut Buffer) -> &mut [Self];
+
+    /* Conversions between element types go through i64 from integer types,
+     * which f64 can't hold exactly above 2^53, and through f64 otherwise */
+    fn to_f64(self) -> f64;
+    fn from_f64(x: f64) -> Self;
+    fn to_i64(self) -> i64;
+    fn from_i64(x: i64) -> Self;
+
+    fn cast<U: Element>(self) -> U {
+        if Self::DTYPE.is_floating_point() {
+            U::from_f64(self.to_f64())
+        } else {
+            U::from_i64(self.to_i64())
+        }
+    }
+}
+
+/// Element types supporting arithmetic.
+pub trait Numeric:
+    Element
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + MulAssign
+    + Sum
+    + Product
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+/// Element types supporting differentiation.
+pub trait Float: Numeric + Div<Output = Self> + DivAssign {
+    const NEG_INFINITY: Self;
+
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn ln_1p(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powf(self, exponent: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tanh(self) -> Self;
+    fn erf(self) -> Self;
+    fn abs(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+}
+
+macro_rules! impl_element {
+    ($T:ty, $variant:ident) => {
+        impl Element for $T {
+            const DTYPE: DType = DType::$variant;
+
+            fn into_buffer(data: Vec<Self>) -> Buffer {
+                Buffer::$variant(data)
+            }
+            fn from_buffer(buffer: &Buffer) -> &[Self] {
+                match buffer {
+                    Buffer::$variant(data) => data,
+                    _ => panic!("Buffer does not hold {} elements", DType::$variant.name()),
+                }
+            }
+            fn from_buffer_mut(buffer: &mut Buffer) -> &mut [Self] {
+                match buffer {
+                    Buffer::$variant(data) => data,
+                    _ => panic!("Buffer does not hold {} elements", DType::$variant.name()),
+                }
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(x: f64) -> Self {
+                x as $T
+            }
+            fn to_i64(self) -> i64 {
+                self as i64
+            }
+            fn from_i64(x: i64) -> Self {
+                x as $T
+            }
+        }
+    };
+}
+
+impl_element!(i64, Int64);
+impl_element!(f32, Float32);
+impl_element!(f64, Float64);
+
+impl Element for bool {
+    const DTYPE: DType = DType::Bool;
+
+    fn into_buffer(data: Vec<Self>) -> Buffer {
+        Buffer::Bool(data)
+    }
+    fn from_buffer(buffer: &Buffer) -> &[Self] {
+        match buffer {
+            Buffer::Bool(data) => data,
+            _ => panic!("Buffer does not hold bool elements"),
+        }
+    }
+    fn from_buffer_mut(buffer: &mut Buffer) -> &mut [Self] {
+        match buffer {
+            Buffer::Bool(data) => data,
+            _ => panic!("Buffer does not hold bool elements"),
+        }
+    }
+    fn to_f64(self) -> f64 {
+        if self {
+            1.0
+        } else {
+            0.0
+        }
+    }
+    fn from_f64(x: f64) -> Self {
+        x != 0.0
+    }
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+    fn from_i64(x: i64) -> Self {
+        x != 0
+    }
+}
+
+impl Numeric for i64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+}
+
+macro_rules! impl_float {
+    ($T:ident) => {
+        impl Numeric for $T {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+        }
+
+        impl Float for $T {
+            const NEG_INFINITY: Self = $T::NEG_INFINITY;
+
+            fn exp(self) -> Self {
+                $T::exp(self)
+            }
+            fn ln(self) -> Self {
+                $T::ln(self)
+            }
+            fn ln_1p(self) -> Self {
+                $T::ln_1p(self)
+            }
+            fn sqrt(self) -> Self {
+                $T::sqrt(self)
+            }
+            fn powf(self, exponent: Self) -> Self {
+                $T::powf(self, exponent)
+            }
+            fn sin(self) -> Self {
+                $T::sin(self)
+            }
+            fn cos(self) -> Self {
+                $T::cos(self)
+            }
+            fn tanh(self) -> Self {
+                $T::tanh(self)
+            }
+            fn erf(self) -> Self {
+                $T::erf(self)
+            }
+            fn abs(self) -> Self {
+                $T::abs(self)
+            }
+            fn max(self, other: Self) -> Self {
+                $T::max(self, other)
+            }
+            fn min(self, other: Self) -> Self {
+                $T::min(self, other)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+/* Dispatch a generic expression on the runtime type of a tensor. Within
+ * `$body`, `$T` is the element type. `dispatch_all` covers every type and
+ * evaluates to the expression, the others evaluate to a `Result`. */
+
+#[macro_export]
+macro_rules! dispatch_all {
+    ($dtype:expr, $T:ident => $body:expr) => {
+        match $dtype {
+            $crate::dtype::DType::Bool => {
+                type $T = bool;
+                $body
+            }
+            $crate::dtype::DType::Int64 => {
+                type $T = i64;
+                $body
+            }
+            $crate::dtype::DType::Float32 => {
+                type $T = f32;
+                $body
+            }
+            $crate::dtype::DType::Float64 => {
+                type $T = f64;
+                $body
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! dispatch_numeric {
+    ($dtype:expr, $T:ident => $body:expr) => {
+        match $dtype {
+            $crate::dtype::DType::Int64 => {
+                type $T = i64;
+                Ok($body)
+            }
+            $crate::dtype::DType::Float32 => {
+                type $T = f32;
+                Ok($body)
+            }
+            $crate::dtype::DType::Float64 => {
+                type $T = f64;
+                Ok($body)
+            }
+            dtype => Err($crate::errors::AutogradError::DTypeMismatch(format!(
+                "Expected a numeric tensor, got {}",
+                dtype.name()
+            ))),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! dispatch_float {
+    ($dtype:expr, $T:ident => $body:expr) => {
+        match $dtype {
+            $crate::dtype::DType::Float32 => {
+                type $T = f32;
+                Ok($body)
+            }
+            $crate::dtype::DType::Float64 => {
+                type $T = f64;
+                Ok($body)
+            }
+            dtype => Err($crate::errors::AutogradError::DTypeMismatch(format!(
+                "Expected a floating point tensor, got {}",
+                dtype.name()
+            ))),
+        }
+    };
+}
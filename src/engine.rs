@@ -0,0 +1,288 @@
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    errors::{AutogradError, Result},
+    grad_mode::{grad_mode, GradMode, GradModeGuard},
+    objects::Tensor,
+    operations::add::add,
+    threads,
+};
+
+/* The backward engine walks the graph in topological order, so that every node
+ * receives the sum of the gradients of all its consumers before being run, and
+ * every operation is run exactly once. The hooks of a tensor run on its summed
+ * gradient, and the hooks of an operation on the gradients it computed.
+ * Operations are released once they ran, unless the graph is retained. The
+ * tensors whose gradients are complete at the same time are independent
+ * branches of the graph, and are run together on the thread pool. */
+
+/// Counts, for every tensor reachable from `roots`, the number of graph edges
+/// that will send it a gradient. Fails if part of the graph was released, before
+/// any gradient is computed.
+fn compute_dependencies(roots: &[Tensor]) -> Result<HashMap<usize, usize>> {
+    let mut dependencies: HashMap<usize, usize> = HashMap::new();
+    let mut seen: HashSet<usize> = roots.iter().map(|root| root.id()).collect();
+    let mut stack = roots.to_vec();
+
+    while let Some(tensor) = stack.pop() {
+        let graph = match tensor.get_graph() {
+            None => continue,
+            Some(graph) => graph,
+        };
+        let node = graph.0.read().unwrap();
+        let operation = match node.operation {
+            None => return Err(AutogradError::GraphFreed(node.name.clone())),
+            Some(ref operation) => operation,
+        };
+        for input in operation.inputs() {
+            if !input.get_requires_grad() {
+                continue;
+            }
+            *dependencies.entry(input.id()).or_insert(0) += 1;
+            if seen.insert(input.id()) {
+                stack.push(input);
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+/// Adds `grad` to the gradient flowing into the tensor `id`.
+fn add_grad(grads: &mut HashMap<usize, Tensor>, id: usize, grad: Tensor) -> Result<()> {
+    let accumulated = match grads.remove(&id) {
+        None => grad,
+        Some(current) => add(current, grad)?,
+    };
+    grads.insert(id, accumulated);
+    Ok(())
+}
+
+/// Counts one of the edges into `input` as done. Once all of them are, the
+/// tensor is ready if it received a gradient. Otherwise its operation has
+/// nothing to backpropagate, and the edges to its own inputs are done too, so
+/// that they don't wait forever for it.
+fn release(
+    input: Tensor,
+    dependencies: &mut HashMap<usize, usize>,
+    grads: &HashMap<usize, Tensor>,
+    ready: &mut Vec<Tensor>,
+) {
+    let mut stack = vec![input];
+    while let Some(tensor) = stack.pop() {
+        let remaining = dependencies.get_mut(&tensor.id()).unwrap();
+        *remaining -= 1;
+        if *remaining > 0 {
+            continue;
+        }
+        if grads.contains_key(&tensor.id()) {
+            ready.push(tensor);
+            continue;
+        }
+        if let Some(graph) = tensor.get_graph() {
+            let node = graph.0.read().unwrap();
+            if let Some(ref operation) = node.operation {
+                stack.extend(
+                    operation
+                        .inputs()
+                        .into_iter()
+                        .filter(|input| input.get_requires_grad()),
+                );
+            }
+        }
+    }
+}
+
+/// What running a tensor of the graph produced: the gradient it received
+/// after its hooks, and the gradients of the inputs of its operation.
+struct Step {
+    tensor: Tensor,
+    grad: Tensor,
+    inputs: Vec<Tensor>,
+    input_grads: Vec<Option<Tensor>>,
+}
+
+/// Runs the hooks of `tensor` on its gradient, accumulates it, and runs the
+/// operation that created the tensor.
+fn run_step(
+    mut tensor: Tensor,
+    grad: Tensor,
+    accumulate: bool,
+    retain_graph: bool,
+) -> Result<Step> {
+    let grad = tensor.run_hooks(grad)?;
+    if accumulate && tensor.keeps_grad() {
+        tensor.accumulate_grad(grad.clone())?;
+    }
+    let graph = match tensor.get_graph() {
+        None => {
+            return Ok(Step {
+                tensor,
+                grad,
+                inputs: vec![],
+                input_grads: vec![],
+            })
+        }
+        Some(graph) => graph,
+    };
+    let (inputs, input_grads) = {
+        let mut node = graph.0.write().unwrap();
+        node.check_versions()?;
+        let operation = node.operation.as_mut().unwrap();
+        let inputs = operation.inputs();
+        let input_grads = operation.try_backward(grad.clone(), tensor.clone())?;
+        if !retain_graph {
+            node.operation = None;
+        }
+        (inputs, input_grads)
+    };
+    let input_grads = graph.run_hooks(&inputs, input_grads, &grad)?;
+    Ok(Step {
+        tensor,
+        grad,
+        inputs,
+        input_grads,
+    })
+}
+
+/// Whether the step of `tensor` can run on the thread pool: hooks and
+/// operations may call into Python, which is left to the calling thread.
+fn runs_in_pool(tensor: &Tensor) -> bool {
+    if tensor.has_hooks() {
+        return false;
+    }
+    match tensor.get_graph() {
+        None => true,
+        Some(graph) => {
+            let node = graph.0.read().unwrap();
+            node.hooks.is_empty() && node.operation.as_ref().is_some_and(|op| op.parallel())
+        }
+    }
+}
+
+/// Whether backpropagating from `roots` may call into Python, through hooks or
+/// operations written in Python.
+pub fn reaches_python(roots: &[Tensor]) -> bool {
+    let mut seen: HashSet<usize> = roots.iter().map(|root| root.id()).collect();
+    let mut stack = roots.to_vec();
+    while let Some(tensor) = stack.pop() {
+        if !runs_in_pool(&tensor) {
+            return true;
+        }
+        let graph = match tensor.get_graph() {
+            None => continue,
+            Some(graph) => graph,
+        };
+        let node = graph.0.read().unwrap();
+        for input in node
+            .operation
+            .iter()
+            .flat_map(|operation| operation.inputs())
+        {
+            if input.get_requires_grad() && seen.insert(input.id()) {
+                stack.push(input);
+            }
+        }
+    }
+    false
+}
+
+/// Runs tensors whose gradients are complete. They don't depend on each other,
+/// so they are run in parallel on the thread pool, except those that may call
+/// into Python. The workers take the grad mode of the calling thread, so that
+/// create_graph records the backward pass.
+fn run_wave(
+    wave: Vec<(Tensor, Tensor)>,
+    accumulate: bool,
+    retain_graph: bool,
+) -> Result<Vec<Step>> {
+    let (pooled, local): (Vec<_>, Vec<_>) = wave
+        .into_iter()
+        .partition(|(tensor, _)| runs_in_pool(tensor));
+    let mut steps = match pooled.len() {
+        0 | 1 => pooled
+            .into_iter()
+            .map(|(tensor, grad)| run_step(tensor, grad, accumulate, retain_graph))
+            .collect::<Result<Vec<_>>>()?,
+        _ => {
+            let mode = grad_mode();
+            threads::install(|| {
+                pooled
+                    .into_par_iter()
+                    .map(|(tensor, grad)| {
+                        let _guard = GradModeGuard::new(mode);
+                        run_step(tensor, grad, accumulate, retain_graph)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })?
+        }
+    };
+    for (tensor, grad) in local {
+        steps.push(run_step(tensor, grad, accumulate, retain_graph)?);
+    }
+    Ok(steps)
+}
+
+/// Backpropagates the gradient of each root through the graph.
+///
+/// Without `inputs`, the gradients are accumulated in the leaves that require
+/// them and in the tensors that retain their gradient. Otherwise they are
+/// returned for each of the `inputs`, None for those the roots don't depend
+/// on, and no tensor is modified.
+///
+/// The operations and the tensors they saved are released unless `retain_graph`
+/// is set. With `create_graph`, the backward pass is itself recorded in the
+/// graph, so that the gradients can be differentiated again.
+pub fn run_backward(
+    roots: Vec<(Tensor, Tensor)>,
+    inputs: Option<&[Tensor]>,
+    retain_graph: bool,
+    create_graph: bool,
+) -> Result<Vec<Option<Tensor>>> {
+    let _guard = GradModeGuard::new(GradMode {
+        enabled: create_graph,
+        ..grad_mode()
+    });
+    let (roots, root_grads): (Vec<Tensor>, Vec<Tensor>) = roots.into_iter().unzip();
+    let mut dependencies = compute_dependencies(&roots)?;
+    let mut grads: HashMap<usize, Tensor> = HashMap::new();
+    let mut ready = vec![];
+    for (root, grad) in roots.into_iter().zip(root_grads) {
+        if !grads.contains_key(&root.id()) && !dependencies.contains_key(&root.id()) {
+            ready.push(root.clone());
+        }
+        add_grad(&mut grads, root.id(), grad)?;
+    }
+
+    let mut captured: Vec<Option<Tensor>> = vec![None; inputs.map_or(0, |inputs| inputs.len())];
+    let mut positions: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, input) in inputs.unwrap_or_default().iter().enumerate() {
+        positions.entry(input.id()).or_default().push(i);
+    }
+
+    let accumulate = inputs.is_none();
+    while !ready.is_empty() {
+        let wave: Vec<(Tensor, Tensor)> = ready
+            .drain(..)
+            .map(|tensor| {
+                let grad = grads.remove(&tensor.id()).unwrap();
+                (tensor, grad)
+            })
+            .collect();
+        for step in run_wave(wave, accumulate, retain_graph)? {
+            for &i in positions.get(&step.tensor.id()).into_iter().flatten() {
+                captured[i] = Some(step.grad.clone());
+            }
+            for (input, input_grad) in step.inputs.into_iter().zip(step.input_grads) {
+                if !input.get_requires_grad() {
+                    continue;
+                }
+                if let Some(input_grad) = input_grad {
+                    add_grad(&mut grads, input.id(), input_grad)?;
+                }
+                release(input, &mut dependencies, &grads, &mut ready);
+            }
+        }
+    }
+    Ok(captured)
+}
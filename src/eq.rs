@@ -1,7 +1,9 @@
-use crate::objects::Tensor;
+use crate::{dispatch_all, objects::Tensor};
 
 impl PartialEq for Tensor {
     fn eq(&self, other: &Self) -> bool {
-        self.get_shape() == other.get_shape() && *self.get_data_ref() == *other.get_data_ref()
+        self.dtype == other.dtype
+            && self.get_shape() == other.get_shape()
+            && dispatch_all!(self.dtype, T => *self.get_data_ref::<T>() == *other.get_data_ref::<T>())
     }
 }
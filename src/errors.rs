@@ -0,0 +1,152 @@
+use pyo3::{
+    create_exception,
+    exceptions::{PyIndexError, PyRuntimeError, PyValueError},
+    prelude::*,
+};
+use std::{fmt, sync::Arc};
+
+/* Errors raised by tensor operations. They are returned by the Rust API and
+ * converted to Python exceptions at the boundary, so that invalid inputs never
+ * panic while a tensor lock is held. */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutogradError {
+    /// The shapes of the operands are not compatible.
+    ShapeMismatch(String),
+    /// A dimension does not exist in a tensor of the given shape.
+    DimensionOutOfRange { dim: isize, shape: Vec<usize> },
+    /// An index does not exist in a dimension of the given size.
+    IndexOutOfRange { index: isize, size: usize },
+    /// The index itself is malformed, e.g. it has too many dimensions.
+    InvalidIndex(String),
+    /// An argument has a value the operation does not support.
+    InvalidArgument(String),
+    /// The element types of the operands are not compatible.
+    DTypeMismatch(String),
+    /// A gradient was needed but the tensor does not require one.
+    MissingGrad(String),
+    /// Backward was called without a gradient on a tensor that is not a scalar.
+    NonScalarBackward(Vec<usize>),
+    /// An in-place operation would modify a tensor needed for gradients.
+    InPlaceOnGrad(String),
+    /// The operation only applies to tensors that were not computed by the graph.
+    NotLeaf(String),
+    /// Backward went through an operation whose graph was already released.
+    GraphFreed(String),
+    /// A dual level was entered, exited or needed at the wrong time.
+    DualLevel(String),
+    /// A hook returned the wrong number of gradients.
+    Hook(String),
+    /// A function defined in Python returned the wrong number of gradients.
+    Function(String),
+    /// Python code run by the library, e.g. a hook, raised an exception.
+    Python { origin: String, error: PythonError },
+    /// The gradients of a function do not match their finite differences.
+    GradCheck(String),
+}
+
+pub type Result<T> = std::result::Result<T, AutogradError>;
+
+/// An exception raised by Python code, to be raised again as it is. Errors
+/// are cloned, so the exception is shared, and two errors are equal if they
+/// share it.
+#[derive(Debug, Clone)]
+pub struct PythonError(Arc<PyErr>);
+
+impl From<PyErr> for PythonError {
+    fn from(error: PyErr) -> Self {
+        PythonError(Arc::new(error))
+    }
+}
+
+impl PartialEq for PythonError {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for AutogradError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutogradError::ShapeMismatch(message) => write!(f, "{}", message),
+            AutogradError::DimensionOutOfRange { dim, shape } => write!(
+                f,
+                "Dimension {} is out of range for tensor with shape {:?}",
+                dim, shape
+            ),
+            AutogradError::IndexOutOfRange { index, size } => write!(
+                f,
+                "Index {} is out of range for dimension of size {}",
+                index, size
+            ),
+            AutogradError::InvalidIndex(message) => write!(f, "{}", message),
+            AutogradError::InvalidArgument(message) => write!(f, "{}", message),
+            AutogradError::DTypeMismatch(message) => write!(f, "{}", message),
+            AutogradError::MissingGrad(message) => write!(f, "{}", message),
+            AutogradError::NonScalarBackward(shape) => write!(
+                f,
+                "Backward requires grad to be provided for non-scalar tensors, got shape {:?}",
+                shape
+            ),
+            AutogradError::InPlaceOnGrad(message) => write!(f, "{}", message),
+            AutogradError::NotLeaf(message) => write!(f, "{}", message),
+            AutogradError::GraphFreed(name) => write!(
+                f,
+                "Trying to backward through {} a second time, its saved tensors were \
+                 released after the first backward. Use retain_graph=True to backward \
+                 through the graph several times",
+                name
+            ),
+            AutogradError::DualLevel(message) => write!(f, "{}", message),
+            AutogradError::Hook(message) => write!(f, "{}", message),
+            AutogradError::Function(message) => write!(f, "{}", message),
+            AutogradError::Python { origin, error } => write!(f, "{} raised {}", origin, error.0),
+            AutogradError::GradCheck(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for AutogradError {}
+
+create_exception!(autograd, ShapeError, PyValueError);
+create_exception!(autograd, DTypeError, PyValueError);
+create_exception!(autograd, GradError, PyRuntimeError);
+
+impl From<AutogradError> for PyErr {
+    fn from(error: AutogradError) -> PyErr {
+        let message = error.to_string();
+        match error {
+            AutogradError::ShapeMismatch(_) | AutogradError::DimensionOutOfRange { .. } => {
+                ShapeError::new_err(message)
+            }
+            AutogradError::IndexOutOfRange { .. } | AutogradError::InvalidIndex(_) => {
+                PyIndexError::new_err(message)
+            }
+            AutogradError::InvalidArgument(_) => PyValueError::new_err(message),
+            AutogradError::DTypeMismatch(_) => DTypeError::new_err(message),
+            AutogradError::MissingGrad(_)
+            | AutogradError::NonScalarBackward(_)
+            | AutogradError::InPlaceOnGrad(_)
+            | AutogradError::NotLeaf(_)
+            | AutogradError::GraphFreed(_)
+            | AutogradError::DualLevel(_)
+            | AutogradError::Hook(_)
+            | AutogradError::Function(_)
+            | AutogradError::GradCheck(_) => GradError::new_err(message),
+            AutogradError::Python { origin, error } => Python::with_gil(|py| {
+                let error = error.0.clone_ref(py);
+                /* Since Python 3.11, notes are shown under the exception */
+                let note = format!("{} raised this error during the backward pass", origin);
+                let _ = error.value(py).call_method1("add_note", (note,));
+                error
+            }),
+        }
+    }
+}
+
+pub fn register_exceptions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ShapeError", m.py().get_type::<ShapeError>())?;
+    m.add("DTypeError", m.py().get_type::<DTypeError>())?;
+    m.add("GradError", m.py().get_type::<GradError>())?;
+    Ok(())
+}
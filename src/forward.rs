@@ -0,0 +1,218 @@
+use pyo3::prelude::*;
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    backward::Backward,
+    errors::{AutogradError, Result},
+    objects::Tensor,
+    operations::view::reshape,
+};
+
+/* Forward mode differentiation. Inside a dual level, a tensor can carry a
+ * tangent: its derivative along one direction of the inputs. Every operation
+ * computes the tangent of its output from the tangents of its inputs as it
+ * runs, so a Jacobian-vector product costs one evaluation of the function
+ * however many outputs it has, and no graph is kept. A dual level is local to
+ * the thread that entered it, and its tangents vanish when it exits. */
+
+pub trait Forward: Backward {
+    /// Computes the tangent of the output given the tangents of the inputs,
+    /// in the order of `inputs`. Inputs without a tangent get None, and at
+    /// least one input has one. `output` is the tensor produced by the operation.
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor>;
+}
+
+/* Levels are numbered globally, so that a tangent never outlives its level */
+static LEVELS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LEVEL: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// The dual level of the current thread, if any.
+pub fn dual_level() -> Option<usize> {
+    LEVEL.with(|level| level.get())
+}
+
+/// Enters a new dual level and returns it. Levels cannot be nested.
+pub fn enter_dual_level() -> Result<usize> {
+    if dual_level().is_some() {
+        return Err(AutogradError::DualLevel(
+            "Nested dual levels are not supported".to_string(),
+        ));
+    }
+    let level = LEVELS.fetch_add(1, Ordering::Relaxed);
+    LEVEL.with(|current| current.set(Some(level)));
+    Ok(level)
+}
+
+/// Exits `level`, which must be the current one, discarding its tangents.
+pub fn exit_dual_level(level: usize) -> Result<()> {
+    if dual_level() != Some(level) {
+        return Err(AutogradError::DualLevel(format!(
+            "Trying to exit dual level {} which is not the current one",
+            level
+        )));
+    }
+    LEVEL.with(|current| current.set(None));
+    Ok(())
+}
+
+/// Stays in a dual level until dropped.
+pub struct DualLevelGuard {
+    level: usize,
+}
+
+impl DualLevelGuard {
+    pub fn new() -> Result<Self> {
+        Ok(DualLevelGuard {
+            level: enter_dual_level()?,
+        })
+    }
+}
+
+impl Drop for DualLevelGuard {
+    fn drop(&mut self) {
+        let _ = exit_dual_level(self.level);
+    }
+}
+
+/// Leaves the dual level until dropped, so that the operations computing a
+/// tangent don't compute tangents of their own.
+struct SuspendGuard {
+    level: Option<usize>,
+}
+
+impl SuspendGuard {
+    fn new() -> Self {
+        SuspendGuard {
+            level: LEVEL.with(|level| level.replace(None)),
+        }
+    }
+}
+
+impl Drop for SuspendGuard {
+    fn drop(&mut self) {
+        LEVEL.with(|level| level.set(self.level));
+    }
+}
+
+impl Tensor {
+    /// The tangent of the tensor in the current dual level.
+    pub fn get_tangent(&self) -> Option<Tensor> {
+        let level = dual_level()?;
+        match self.core.read().unwrap().tangent {
+            Some((tangent_level, ref tangent)) if tangent_level == level => Some(tangent.clone()),
+            _ => None,
+        }
+    }
+
+    /// Sets the tangent of the tensor in the current dual level. Outside of a
+    /// dual level there is nothing to set.
+    pub fn set_tangent(&self, tangent: Option<Tensor>) {
+        if let Some(level) = dual_level() {
+            self.core.write().unwrap().tangent = tangent.map(|tangent| (level, tangent));
+        }
+    }
+}
+
+/// Computes the tangent of `output`, produced by `operation`, if one of the
+/// inputs of the operation has a tangent.
+pub fn forward_tangent<T: Forward>(operation: &T, output: &Tensor) {
+    if dual_level().is_none() {
+        return;
+    }
+    let tangents: Vec<Option<Tensor>> = operation
+        .inputs()
+        .iter()
+        .map(|input| input.get_tangent())
+        .collect();
+    if tangents.iter().all(|tangent| tangent.is_none()) {
+        return;
+    }
+    let tangent = {
+        let _guard = SuspendGuard::new();
+        operation.do_forward(&tangents, output)
+    };
+    output.set_tangent(tangent);
+}
+
+/// Adds the tangents that are not None.
+pub fn sum_tangents(tangents: Vec<Option<Tensor>>) -> Option<Tensor> {
+    tangents.into_iter().flatten().reduce(|lhs, rhs| lhs + rhs)
+}
+
+/// A view of `t` whose tangent is `tangent` in the current dual level.
+pub fn make_dual(t: &Tensor, tangent: Tensor) -> Result<Tensor> {
+    if dual_level().is_none() {
+        return Err(AutogradError::DualLevel(
+            "make_dual can only be used inside a dual level".to_string(),
+        ));
+    }
+    if !t.dtype.is_floating_point() {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "Only floating point tensors can have a tangent, got {}",
+            t.dtype.name()
+        )));
+    }
+    if tangent.get_shape() != t.get_shape() {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Tangent shape {:?} does not match tensor shape {:?}",
+            tangent.get_shape(),
+            t.get_shape()
+        )));
+    }
+    if tangent.dtype != t.dtype {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "Tangent dtype {} does not match tensor dtype {}",
+            tangent.dtype.name(),
+            t.dtype.name()
+        )));
+    }
+    let dual = primal(t)?;
+    dual.set_tangent(Some(tangent));
+    Ok(dual)
+}
+
+/// A view of `t` without its tangent, still part of the graph of `t`.
+fn primal(t: &Tensor) -> Result<Tensor> {
+    let _guard = SuspendGuard::new();
+    let shape = t.get_shape().iter().map(|&d| d as isize).collect();
+    reshape(t.clone(), shape)
+}
+
+/// The primal of `t` and its tangent in the current dual level.
+pub fn unpack_dual(t: &Tensor) -> Result<(Tensor, Option<Tensor>)> {
+    Ok((primal(t)?, t.get_tangent()))
+}
+
+#[pyfunction(name = "_enter_dual_level")]
+pub fn py_enter_dual_level() -> PyResult<usize> {
+    Ok(enter_dual_level()?)
+}
+
+#[pyfunction(name = "_exit_dual_level")]
+pub fn py_exit_dual_level(level: usize) -> PyResult<()> {
+    Ok(exit_dual_level(level)?)
+}
+
+#[pyfunction(name = "_make_dual")]
+pub fn py_make_dual(tensor: Tensor, tangent: Tensor) -> PyResult<Tensor> {
+    Ok(make_dual(&tensor, tangent)?)
+}
+
+#[pyfunction(name = "_unpack_dual")]
+pub fn py_unpack_dual(tensor: Tensor) -> PyResult<(Tensor, Option<Tensor>)> {
+    Ok(unpack_dual(&tensor)?)
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_enter_dual_level, m)?)?;
+    m.add_function(wrap_pyfunction!(py_exit_dual_level, m)?)?;
+    m.add_function(wrap_pyfunction!(py_make_dual, m)?)?;
+    m.add_function(wrap_pyfunction!(py_unpack_dual, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,450 @@
+use pyo3::{prelude::*, types::PyTuple, IntoPyObjectExt};
+
+use crate::{
+    backward::Backward,
+    dispatch_all,
+    dtype::{DType, Element},
+    errors::{AutogradError, Result},
+    forward::{forward_tangent, Forward},
+    grad_mode::{is_grad_enabled, GradModeGuard},
+    objects::Tensor,
+    operations::{
+        cast::to,
+        reduce::{scatter, take},
+    },
+    utils::{new_graph, new_tensor_simple},
+};
+
+/* Differentiable functions defined in Python, by subclassing autograd.Function
+ * with `forward(ctx, *args)` and `backward(ctx, *grads)` static methods.
+ * Forward runs without recording a graph, and the tensors it returns are
+ * connected to a node calling back into Python for their gradients. When
+ * several of them are differentiable, they share the node through a packed
+ * tensor: each output sends its gradient to its own range of the packed
+ * tensor, so that the engine sums them and backward is called once, with
+ * zeros as the gradients of the outputs that received none. */
+
+pub struct FunctionOperation {
+    /// The subclass of autograd.Function.
+    function: PyObject,
+    name: String,
+    /// The context given to forward, holding what it saved for backward.
+    ctx: PyObject,
+    /// The tensors among the arguments, and their positions.
+    inputs: Vec<Tensor>,
+    positions: Vec<usize>,
+    arguments: usize,
+    /// The outputs of forward. They are not kept themselves, they hold the node.
+    outputs: Vec<Output>,
+    /// Whether the node belongs to a packed tensor rather than to an output.
+    packed: bool,
+    /// The output whose tangent `do_forward` computes.
+    index: usize,
+}
+
+struct Output {
+    shape: Vec<usize>,
+    dtype: DType,
+    /// The id of the tensor forward returned.
+    result: usize,
+    /// Where the output is in the packed tensor, if it is differentiable. Without
+    /// packing, the only differentiable output is at 0.
+    offset: Option<usize>,
+}
+
+impl Output {
+    fn range(&self) -> Vec<usize> {
+        let offset = self.offset.unwrap();
+        (offset..offset + self.shape.iter().product::<usize>()).collect()
+    }
+}
+
+/// Sends the gradient of an output of a function to the packed tensor holding
+/// its node, at the position of the output.
+struct FunctionOutput {
+    packed: Tensor,
+    name: String,
+    range: Vec<usize>,
+}
+
+impl Backward for FunctionOutput {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.packed.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        let grad = to(grad, self.packed.dtype);
+        vec![Some(
+            scatter(&grad, self.packed.get_shape(), self.range.clone()).unwrap(),
+        )]
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// `result` connected to the graph through `packed`, as the output of the
+/// function at `range`.
+fn unpack(result: &Tensor, packed: &Tensor, name: &str, range: Vec<usize>) -> Tensor {
+    let mut output = {
+        let core = result.core.read().unwrap();
+        result.new_view(
+            core.shape.clone(),
+            core.strides.clone(),
+            core.offset,
+            true,
+            None,
+        )
+    };
+    let node = FunctionOutput {
+        packed: packed.clone(),
+        name: name.to_string(),
+        range,
+    };
+    let graph = new_graph(node, &output);
+    output.set_graph(Some(graph));
+    output
+}
+
+impl FunctionOperation {
+    fn error(&self, method: &str, error: PyErr) -> AutogradError {
+        AutogradError::Python {
+            origin: format!("{}.{}", self.name, method),
+            error: error.into(),
+        }
+    }
+
+    /// The differentiable output forward returned as `result`, connected to the
+    /// graph through `output`, the tensor the node belongs to.
+    fn connect(&self, result: &Tensor, output: &Tensor) -> Option<Tensor> {
+        let found = self
+            .outputs
+            .iter()
+            .find(|o| o.result == result.id() && o.offset.is_some())?;
+        match self.packed {
+            true => Some(unpack(result, output, &self.name, found.range())),
+            false => Some(output.clone()),
+        }
+    }
+
+    /// Calls backward with the differentiable outputs in place of the tensors
+    /// forward returned in the saved tensors, so that backward can be
+    /// differentiated again through them. The outputs are only saved during
+    /// the call, as they hold the node.
+    fn call_backward(
+        &self,
+        py: Python<'_>,
+        args: Bound<'_, PyTuple>,
+        output: Tensor,
+    ) -> PyResult<PyObject> {
+        let ctx = self.ctx.bind(py);
+        let saved = ctx.getattr("saved_tensors")?;
+        let connected = saved
+            .try_iter()?
+            .map(|item| {
+                let item = item?;
+                let tensor = item.extract::<Tensor>().ok();
+                match tensor.and_then(|tensor| self.connect(&tensor, &output)) {
+                    Some(output) => output.into_py_any(py),
+                    None => Ok(item.unbind()),
+                }
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        ctx.setattr("saved_tensors", PyTuple::new(py, connected)?)?;
+        let result = self.function.call_method1(py, "backward", args);
+        ctx.setattr("saved_tensors", saved)?;
+        result
+    }
+
+    /// The gradients returned by backward, one per argument of forward, as a
+    /// tuple or a single value.
+    fn results(&self, py: Python<'_>, result: PyObject, expected: usize) -> Result<Vec<PyObject>> {
+        let result = result.into_bound(py);
+        let results: Vec<PyObject> = match result.downcast::<PyTuple>() {
+            Ok(results) => results.iter().map(|r| r.unbind()).collect(),
+            Err(_) => vec![result.unbind()],
+        };
+        if results.len() != expected {
+            return Err(AutogradError::Function(format!(
+                "{}.backward returned {} gradients, expected {}, one per argument of forward",
+                self.name,
+                results.len(),
+                expected
+            )));
+        }
+        Ok(results)
+    }
+}
+
+/// The arguments of a method of the function, after the context.
+fn with_ctx<'py>(
+    py: Python<'py>,
+    ctx: &PyObject,
+    mut args: Vec<PyObject>,
+) -> PyResult<Bound<'py, PyTuple>> {
+    args.insert(0, ctx.clone_ref(py));
+    PyTuple::new(py, args)
+}
+
+fn zeros(shape: &[usize], dtype: DType) -> Tensor {
+    let size = shape.iter().product();
+    dispatch_all!(dtype, T => new_tensor_simple(shape.to_vec(), vec![T::from_f64(0.0); size]))
+}
+
+impl Backward for FunctionOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        self.inputs.clone()
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        self.try_backward(grad, output).unwrap()
+    }
+
+    fn try_backward(&mut self, grad: Tensor, output: Tensor) -> Result<Vec<Option<Tensor>>> {
+        /* A packed gradient holds those of the outputs in its ranges */
+        let grads: Vec<Tensor> = self
+            .outputs
+            .iter()
+            .map(|o| match (o.offset, self.packed) {
+                (None, _) => zeros(&o.shape, o.dtype),
+                (Some(_), false) => grad.clone(),
+                (Some(_), true) => to(take(&grad, o.shape.clone(), o.range()), o.dtype),
+            })
+            .collect();
+        let grads = Python::with_gil(|py| -> Result<Vec<Option<Tensor>>> {
+            let grads = grads
+                .into_iter()
+                .map(|grad| grad.into_py_any(py))
+                .collect::<PyResult<Vec<_>>>();
+            let args = grads
+                .and_then(|grads| with_ctx(py, &self.ctx, grads))
+                .map_err(|error| self.error("backward", error))?;
+            let result = self
+                .call_backward(py, args, output)
+                .map_err(|error| self.error("backward", error))?;
+            let results = self.results(py, result, self.arguments)?;
+            self.positions
+                .iter()
+                .map(|&position| {
+                    results[position]
+                        .extract::<Option<Tensor>>(py)
+                        .map_err(|_| {
+                            AutogradError::Function(format!(
+                                "{}.backward must return Tensors or None, one per argument",
+                                self.name
+                            ))
+                        })
+                })
+                .collect()
+        })?;
+        for (input, grad) in self.inputs.iter().zip(&grads) {
+            if let Some(grad) = grad {
+                input.check_grad(grad)?;
+            }
+        }
+        Ok(grads)
+    }
+
+    fn parallel(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Forward for FunctionOperation {
+    /// Calls `jvp(ctx, *tangents)` if the function defines it, with a tangent
+    /// or None per argument. Errors can't be raised from here, they are
+    /// reported as unraisable.
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        Python::with_gil(|py| {
+            if !self.function.bind(py).hasattr("jvp").unwrap_or(false) {
+                return None;
+            }
+            let mut args: Vec<PyObject> = (0..self.arguments).map(|_| py.None()).collect();
+            for (&position, tangent) in self.positions.iter().zip(tangents) {
+                if let Some(tangent) = tangent {
+                    args[position] = tangent.clone().into_py_any(py).unwrap();
+                }
+            }
+            let tangent = with_ctx(py, &self.ctx, args)
+                .and_then(|args| self.function.call_method1(py, "jvp", args))
+                .and_then(|result| {
+                    let result = result.into_bound(py);
+                    match result.downcast::<PyTuple>() {
+                        Ok(results) => results.get_item(self.index)?.extract::<Option<Tensor>>(),
+                        Err(_) => result.extract::<Option<Tensor>>(),
+                    }
+                })
+                .and_then(|tangent| {
+                    if let Some(ref tangent) = tangent {
+                        output.check_grad(tangent)?;
+                    }
+                    Ok(tangent)
+                });
+            match tangent {
+                Ok(tangent) => tangent,
+                Err(error) => {
+                    error.write_unraisable(py, Some(self.function.bind(py)));
+                    None
+                }
+            }
+        })
+    }
+}
+
+/// Runs `function.forward(ctx, *args)` without recording a graph, and connects
+/// the tensors it returns to the graph. Functions without `jvp` keep the
+/// tangents computed by the operations of their forward.
+#[pyfunction(name = "_apply_function")]
+pub fn py_apply_function(
+    py: Python<'_>,
+    function: PyObject,
+    ctx: PyObject,
+    args: Bound<'_, PyTuple>,
+) -> PyResult<PyObject> {
+    let name: String = function.getattr(py, "__name__")?.extract(py)?;
+    let (positions, inputs): (Vec<usize>, Vec<Tensor>) = args
+        .iter()
+        .enumerate()
+        .filter_map(|(i, arg)| arg.extract::<Tensor>().ok().map(|t| (i, t)))
+        .unzip();
+
+    let result = {
+        let _guard = GradModeGuard::no_grad();
+        let args = args.iter().map(|arg| arg.unbind()).collect();
+        let forward_args = with_ctx(py, &ctx, args)?;
+        function
+            .call_method1(py, "forward", forward_args)?
+            .into_bound(py)
+    };
+    let (is_tuple, results) = match result.extract::<Tensor>() {
+        Ok(result) => (false, vec![result]),
+        Err(_) => (
+            true,
+            result.extract::<Vec<Tensor>>().map_err(|_| {
+                AutogradError::Function(format!(
+                    "{}.forward must return a Tensor or a tuple of Tensors",
+                    name
+                ))
+            })?,
+        ),
+    };
+
+    let requires_grad = is_grad_enabled() && inputs.iter().any(|t| t.get_requires_grad());
+    let differentiable: Vec<bool> = results
+        .iter()
+        .map(|t| requires_grad && t.dtype.is_floating_point())
+        .collect();
+    let mut size = 0;
+    let outputs = results
+        .iter()
+        .zip(&differentiable)
+        .map(|(result, &differentiable)| {
+            let offset = differentiable.then_some(size);
+            if differentiable {
+                size += result.get_shape().iter().product::<usize>();
+            }
+            Output {
+                shape: result.get_shape(),
+                dtype: result.dtype,
+                result: result.id(),
+                offset,
+            }
+        })
+        .collect::<Vec<_>>();
+    let packed = differentiable.iter().filter(|&&d| d).count() > 1;
+    let ranges: Vec<Option<Vec<usize>>> = outputs
+        .iter()
+        .map(|o| o.offset.map(|_| o.range()))
+        .collect();
+    let mut node = FunctionOperation {
+        function: function.clone_ref(py),
+        name: name.clone(),
+        ctx: ctx.clone_ref(py),
+        inputs,
+        positions,
+        arguments: args.len(),
+        outputs,
+        packed,
+        index: 0,
+    };
+
+    let outputs: Vec<Tensor> = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let core = result.core.read().unwrap();
+            result.new_view(
+                core.shape.clone(),
+                core.strides.clone(),
+                core.offset,
+                differentiable[index],
+                None,
+            )
+        })
+        .collect();
+    for (index, (output, result)) in outputs.iter().zip(&results).enumerate() {
+        node.index = index;
+        forward_tangent(&node, output);
+        if output.get_tangent().is_none() {
+            output.set_tangent(result.get_tangent());
+        }
+    }
+
+    /* The packed tensor only has a shape, its gradients are never read from
+     * its storage */
+    let outputs: Vec<Tensor> = match packed {
+        true => {
+            let dtype = results
+                .iter()
+                .zip(&differentiable)
+                .filter(|(_, &d)| d)
+                .map(|(t, _)| t.dtype)
+                .reduce(DType::promote)
+                .unwrap();
+            let mut packed = zeros(&[1], dtype).new_view(vec![size], vec![0], 0, true, None);
+            let graph = new_graph(node, &packed);
+            packed.set_graph(Some(graph));
+            outputs
+                .into_iter()
+                .zip(results.iter().zip(ranges))
+                .map(|(output, (result, range))| match range {
+                    Some(range) => {
+                        let connected = unpack(result, &packed, &name, range);
+                        connected.set_tangent(output.get_tangent());
+                        connected
+                    }
+                    None => output,
+                })
+                .collect()
+        }
+        false => {
+            let mut node = Some(node);
+            outputs
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut output)| {
+                    if differentiable[index] {
+                        let graph = new_graph(node.take().unwrap(), &output);
+                        output.set_graph(Some(graph));
+                    }
+                    output
+                })
+                .collect()
+        }
+    };
+
+    match is_tuple {
+        true => PyTuple::new(py, outputs)?.into_py_any(py),
+        false => outputs.into_iter().next().unwrap().into_py_any(py),
+    }
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_apply_function, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,507 @@
+use pyo3::{prelude::*, types::PyTuple};
+use rayon::prelude::*;
+
+use crate::{
+    backward::{grad, Backward},
+    dispatch_all,
+    dtype::Element,
+    engine::reaches_python,
+    errors::{AutogradError, Result},
+    forward::Forward,
+    grad_mode::{grad_mode, GradModeGuard},
+    objects::Tensor,
+    operations::{reduce::take, view::reshape},
+    threads,
+    utils::{new_tensor_simple, new_tensor_with_graph},
+};
+
+/* Differentiation of functions rather than of tensors, like
+ * torch.autograd.functional. The function is evaluated on copies of the inputs
+ * that require grad, and the derivatives are computed with `grad`, so that they
+ * never touch the `grad` of the inputs. The functions can fail with any error
+ * an `AutogradError` converts to, Python functions fail with a `PyErr`. */
+
+/// Copies of `inputs` that require grad, so that the function can be
+/// differentiated with respect to them. With `create_graph`, inputs that
+/// already require grad stay connected to their graph so that the result can
+/// be differentiated again.
+fn prepare_inputs(inputs: &[Tensor], create_graph: bool) -> Result<Vec<Tensor>> {
+    inputs
+        .iter()
+        .map(|input| {
+            if create_graph && input.get_requires_grad() {
+                let shape = input.get_shape().iter().map(|&d| d as isize).collect();
+                return reshape(input.clone(), shape);
+            }
+            let input = input.detach();
+            input.set_requires_grad(true)?;
+            Ok(input)
+        })
+        .collect()
+}
+
+/// Evaluates `f` with gradients enabled, whatever the current mode.
+fn evaluate<F, E>(f: &mut F, inputs: &[Tensor]) -> std::result::Result<Vec<Tensor>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+{
+    let _guard = GradModeGuard::enable_grad();
+    f(inputs)
+}
+
+/// Cuts the results from the graph unless it was requested.
+fn postprocess(tensors: Vec<Tensor>, create_graph: bool) -> Vec<Tensor> {
+    if create_graph {
+        return tensors;
+    }
+    tensors.iter().map(|t| t.detach()).collect()
+}
+
+pub fn zeros_like(t: &Tensor) -> Tensor {
+    let size = t.get_shape().iter().product();
+    dispatch_all!(t.dtype, T => new_tensor_simple(t.get_shape(), vec![T::from_f64(0.0); size]))
+}
+
+/// A tensor of zeros with the shape of `t`, except for a one at the row-major
+/// index `i`.
+fn one_hot(t: &Tensor, i: usize) -> Tensor {
+    let size = t.get_shape().iter().product();
+    dispatch_all!(t.dtype, T => {
+        let mut data = vec![T::from_f64(0.0); size];
+        data[i] = T::from_f64(1.0);
+        new_tensor_simple(t.get_shape(), data)
+    })
+}
+
+fn single_output(outputs: Vec<Tensor>, function: &str) -> Result<Tensor> {
+    match <[Tensor; 1]>::try_from(outputs) {
+        Ok([output]) if output.get_shape() == vec![1] => Ok(output),
+        _ => Err(AutogradError::InvalidArgument(format!(
+            "The function given to {} must return a single scalar tensor",
+            function
+        ))),
+    }
+}
+
+/// The vector `v` to multiply the derivative of `tensors` by, which can only be
+/// omitted if the tensors are scalars.
+fn direction(v: Option<Vec<Tensor>>, tensors: &[Tensor]) -> Result<Vec<Tensor>> {
+    let v = match v {
+        Some(v) => v,
+        None if tensors.iter().all(|t| t.get_shape() == vec![1]) => {
+            tensors.iter().map(|t| one_hot(t, 0)).collect()
+        }
+        None => {
+            return Err(AutogradError::InvalidArgument(
+                "v can only be omitted if the tensors it multiplies are scalars".to_string(),
+            ))
+        }
+    };
+    if v.len() != tensors.len() {
+        return Err(AutogradError::InvalidArgument(format!(
+            "Expected {} tensors in v, got {}",
+            tensors.len(),
+            v.len()
+        )));
+    }
+    for (t, v) in tensors.iter().zip(&v) {
+        t.check_grad(v)?;
+    }
+    Ok(v)
+}
+
+/// The gradients of `outputs` with respect to `inputs`. Outputs that do not
+/// require grad and inputs they don't depend on get zeros, or are an error if
+/// `strict`.
+fn connected_grad(
+    outputs: &[Tensor],
+    inputs: &[Tensor],
+    grad_outputs: Vec<Option<Tensor>>,
+    retain_graph: bool,
+    create_graph: bool,
+    strict: bool,
+) -> Result<Vec<Tensor>> {
+    let (connected, grad_outputs): (Vec<Tensor>, Vec<Option<Tensor>>) = outputs
+        .iter()
+        .cloned()
+        .zip(grad_outputs)
+        .filter(|(output, _)| output.get_requires_grad())
+        .unzip();
+    if strict && connected.len() != outputs.len() {
+        return Err(AutogradError::MissingGrad(
+            "An output of the function does not require grad, it must be computed in a \
+             differentiable manner from the inputs in strict mode"
+                .to_string(),
+        ));
+    }
+    let grads = match connected.is_empty() {
+        true => vec![None; inputs.len()],
+        false => grad(
+            connected,
+            inputs.to_vec(),
+            grad_outputs,
+            retain_graph,
+            create_graph,
+            true,
+        )?,
+    };
+    if strict && grads.iter().any(|grad| grad.is_none()) {
+        return Err(AutogradError::MissingGrad(
+            "An output of the function does not depend on an input, which is not allowed \
+             in strict mode"
+                .to_string(),
+        ));
+    }
+    Ok(grads
+        .into_iter()
+        .zip(inputs)
+        .map(|(grad, input)| grad.unwrap_or_else(|| zeros_like(input)))
+        .collect())
+}
+
+/// The outputs of `f` and the product of the vector `v` with its Jacobian. `v`
+/// has one tensor per output and can be omitted if the outputs are scalars.
+pub fn vjp<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> std::result::Result<(Vec<Tensor>, Vec<Tensor>), E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let inputs = prepare_inputs(inputs, create_graph)?;
+    let outputs = evaluate(&mut f, &inputs)?;
+    let v = direction(v, &outputs)?;
+
+    let grad_outputs = v.into_iter().map(Some).collect();
+    let vjp = connected_grad(
+        &outputs,
+        &inputs,
+        grad_outputs,
+        create_graph,
+        create_graph,
+        strict,
+    )?;
+    Ok((postprocess(outputs, create_graph), vjp))
+}
+
+/// The outputs of `f` and the product of its Jacobian with the vector `v`. `v`
+/// has one tensor per input and can be omitted if the inputs are scalars.
+pub fn jvp<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> std::result::Result<(Vec<Tensor>, Vec<Tensor>), E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let inputs = prepare_inputs(inputs, create_graph)?;
+    let outputs = evaluate(&mut f, &inputs)?;
+    let v = direction(v, &inputs)?;
+
+    /* The vector-Jacobian product u J is linear in u, so differentiating it
+     * with respect to u in the direction v gives J v */
+    let u = outputs
+        .iter()
+        .map(|output| {
+            let u = zeros_like(output);
+            u.set_requires_grad(true)?;
+            Ok(u)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let grad_outputs = u.iter().cloned().map(Some).collect();
+    let vjp = connected_grad(&outputs, &inputs, grad_outputs, true, true, strict)?;
+
+    let grad_outputs = v.into_iter().map(Some).collect();
+    let jvp = connected_grad(&vjp, &u, grad_outputs, create_graph, create_graph, strict)?;
+    Ok((postprocess(outputs, create_graph), jvp))
+}
+
+/// Computes `row` for every index in `0..size` on the thread pool, whose
+/// workers inherit the grad mode of the caller. Callers run the rows serially
+/// when they backpropagate into Python, whose GIL the workers would contend for.
+fn parallel_rows<R, F>(size: usize, row: F) -> Result<Vec<R>>
+where
+    R: Send,
+    F: Fn(usize) -> Result<R> + Sync,
+{
+    let mode = grad_mode();
+    threads::install(|| {
+        (0..size)
+            .into_par_iter()
+            .map(|i| {
+                let _guard = GradModeGuard::new(mode);
+                row(i)
+            })
+            .collect()
+    })
+}
+
+/// The Jacobian of `output` with respect to each of `inputs`, with the shape of
+/// the output followed by the shape of the input. Each row is the gradient of
+/// one element of the output.
+fn output_jacobian(
+    output: &Tensor,
+    inputs: &[Tensor],
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> Result<Vec<Tensor>> {
+    let size = output.get_shape().iter().product();
+    let row = |i: usize| {
+        let grad_outputs = vec![Some(one_hot(output, i))];
+        connected_grad(
+            std::slice::from_ref(output),
+            inputs,
+            grad_outputs,
+            true,
+            create_graph,
+            strict,
+        )
+    };
+    let parallel = vectorize && !reaches_python(std::slice::from_ref(output));
+    let rows = match parallel {
+        true => parallel_rows(size, row)?,
+        false => (0..size).map(row).collect::<Result<Vec<_>>>()?,
+    };
+
+    Ok(inputs
+        .iter()
+        .enumerate()
+        .map(|(j, input)| {
+            let shape = [output.get_shape(), input.get_shape()].concat();
+            stack(
+                rows.iter().map(|row| row[j].clone()).collect(),
+                input,
+                shape,
+            )
+        })
+        .collect())
+}
+
+/// The Jacobian of each output of `f` with respect to each input, indexed by
+/// output then input. With `vectorize`, its rows are computed in parallel.
+pub fn jacobian<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> std::result::Result<Vec<Vec<Tensor>>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let inputs = prepare_inputs(inputs, create_graph)?;
+    let outputs = evaluate(&mut f, &inputs)?;
+    Ok(outputs
+        .iter()
+        .map(|output| output_jacobian(output, &inputs, create_graph, strict, vectorize))
+        .collect::<Result<Vec<_>>>()?)
+}
+
+/// The Hessian of the scalar function `f`, indexed by pairs of inputs.
+pub fn hessian<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> std::result::Result<Vec<Vec<Tensor>>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    /* The Hessian is the Jacobian of the gradient */
+    let gradient = |inputs: &[Tensor]| -> std::result::Result<Vec<Tensor>, E> {
+        let output = single_output(f(inputs)?, "hessian")?;
+        Ok(connected_grad(
+            &[output],
+            inputs,
+            vec![None],
+            true,
+            true,
+            strict,
+        )?)
+    };
+    jacobian(gradient, inputs, create_graph, strict, vectorize)
+}
+
+/// The output of the scalar function `f` and the product of its Hessian with
+/// the vector `v`, which has one tensor per input.
+pub fn hvp<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> std::result::Result<(Tensor, Vec<Tensor>), E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let inputs = prepare_inputs(inputs, create_graph)?;
+    let output = single_output(evaluate(&mut f, &inputs)?, "hvp")?;
+    let v = direction(v, &inputs)?;
+
+    let gradient = connected_grad(
+        std::slice::from_ref(&output),
+        &inputs,
+        vec![None],
+        true,
+        true,
+        strict,
+    )?;
+    let grad_outputs = v.into_iter().map(Some).collect();
+    let hvp = connected_grad(
+        &gradient,
+        &inputs,
+        grad_outputs,
+        create_graph,
+        create_graph,
+        strict,
+    )?;
+    let output = postprocess(vec![output], create_graph).remove(0);
+    Ok((output, hvp))
+}
+
+/* Stacking the rows of a Jacobian */
+
+/// Stacks tensors of the same shape into a tensor of shape `shape`, whose
+/// leading dimensions enumerate them. `like` gives the element type if there
+/// are no tensors.
+fn stack(tensors: Vec<Tensor>, like: &Tensor, shape: Vec<usize>) -> Tensor {
+    dispatch_all!(like.dtype, T => {
+        let mut data: Vec<T> = Vec::with_capacity(shape.iter().product());
+        for t in &tensors {
+            data.extend(t.get_data_ref::<T>().iter());
+        }
+
+        new_tensor_with_graph(
+            shape,
+            data,
+            tensors.iter().any(|t| t.get_requires_grad()),
+            StackOperation { tensors },
+        )
+    })
+}
+
+pub struct StackOperation {
+    tensors: Vec<Tensor>,
+}
+
+impl Backward for StackOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        self.tensors.clone()
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        let mut start = 0;
+        self.tensors
+            .iter()
+            .map(|t| {
+                let size: usize = t.get_shape().iter().product();
+                let args = (start..start + size).collect();
+                start += size;
+                Some(take(&grad, t.get_shape(), args))
+            })
+            .collect()
+    }
+}
+
+impl Forward for StackOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let tangents = tangents
+            .iter()
+            .zip(&self.tensors)
+            .map(|(tangent, t)| tangent.clone().unwrap_or_else(|| zeros_like(t)))
+            .collect();
+        Some(stack(tangents, output, output.get_shape()))
+    }
+}
+
+/* Python functions, wrapped by autograd/functional.py. The function is called
+ * with the inputs as positional arguments and returns a list of tensors. The
+ * GIL is released while differentiating and taken back to call it. */
+
+pub fn call(f: &PyObject, inputs: &[Tensor]) -> PyResult<Vec<Tensor>> {
+    Python::with_gil(|py| {
+        let args = PyTuple::new(py, inputs.iter().cloned())?;
+        f.call1(py, args)?.extract(py)
+    })
+}
+
+#[pyfunction(name = "_vjp")]
+pub fn py_vjp(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> PyResult<(Vec<Tensor>, Vec<Tensor>)> {
+    py.allow_threads(|| vjp(|x| call(&func, x), &inputs, v, create_graph, strict))
+}
+
+#[pyfunction(name = "_jvp")]
+pub fn py_jvp(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> PyResult<(Vec<Tensor>, Vec<Tensor>)> {
+    py.allow_threads(|| jvp(|x| call(&func, x), &inputs, v, create_graph, strict))
+}
+
+#[pyfunction(name = "_jacobian")]
+pub fn py_jacobian(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> PyResult<Vec<Vec<Tensor>>> {
+    py.allow_threads(|| jacobian(|x| call(&func, x), &inputs, create_graph, strict, vectorize))
+}
+
+#[pyfunction(name = "_hessian")]
+pub fn py_hessian(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    create_graph: bool,
+    strict: bool,
+    vectorize: bool,
+) -> PyResult<Vec<Vec<Tensor>>> {
+    py.allow_threads(|| hessian(|x| call(&func, x), &inputs, create_graph, strict, vectorize))
+}
+
+#[pyfunction(name = "_hvp")]
+pub fn py_hvp(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    v: Option<Vec<Tensor>>,
+    create_graph: bool,
+    strict: bool,
+) -> PyResult<(Tensor, Vec<Tensor>)> {
+    py.allow_threads(|| hvp(|x| call(&func, x), &inputs, v, create_graph, strict))
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_vjp, m)?)?;
+    m.add_function(wrap_pyfunction!(py_jvp, m)?)?;
+    m.add_function(wrap_pyfunction!(py_jacobian, m)?)?;
+    m.add_function(wrap_pyfunction!(py_hessian, m)?)?;
+    m.add_function(wrap_pyfunction!(py_hvp, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use pyo3::prelude::*;
+use std::cell::Cell;
+
+/* Whether operations record the graph needed for backward. The mode is local to
+ * each thread, like in PyTorch, so that evaluating a model on one thread does
+ * not stop another one from training. */
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradMode {
+    /// Whether gradients are enabled, as set by `no_grad` and `enable_grad`.
+    pub enabled: bool,
+    /// Inference mode disables gradients regardless of `enabled`.
+    pub inference: bool,
+}
+
+thread_local! {
+    static GRAD_MODE: Cell<GradMode> = const {
+        Cell::new(GradMode {
+            enabled: true,
+            inference: false,
+        })
+    };
+}
+
+pub fn grad_mode() -> GradMode {
+    GRAD_MODE.with(|mode| mode.get())
+}
+
+/// Sets the mode of the current thread and returns the previous one.
+pub fn set_grad_mode(mode: GradMode) -> GradMode {
+    GRAD_MODE.with(|current| current.replace(mode))
+}
+
+/// Whether new operations are added to the graph.
+pub fn is_grad_enabled() -> bool {
+    let mode = grad_mode();
+    mode.enabled && !mode.inference
+}
+
+pub fn is_inference_mode_enabled() -> bool {
+    grad_mode().inference
+}
+
+/// Restores the previous grad mode when dropped.
+pub struct GradModeGuard {
+    previous: GradMode,
+}
+
+impl GradModeGuard {
+    pub fn new(mode: GradMode) -> Self {
+        GradModeGuard {
+            previous: set_grad_mode(mode),
+        }
+    }
+
+    /// Disables gradients until the guard is dropped.
+    pub fn no_grad() -> Self {
+        GradModeGuard::new(GradMode {
+            enabled: false,
+            ..grad_mode()
+        })
+    }
+
+    /// Enables gradients until the guard is dropped, unless in inference mode.
+    pub fn enable_grad() -> Self {
+        GradModeGuard::new(GradMode {
+            enabled: true,
+            ..grad_mode()
+        })
+    }
+
+    /// Enters inference mode until the guard is dropped: no graph is built,
+    /// even inside `enable_grad`.
+    pub fn inference_mode() -> Self {
+        GradModeGuard::new(GradMode {
+            inference: true,
+            ..grad_mode()
+        })
+    }
+}
+
+impl Drop for GradModeGuard {
+    fn drop(&mut self) {
+        set_grad_mode(self.previous);
+    }
+}
+
+#[pyfunction(name = "is_grad_enabled")]
+pub fn py_is_grad_enabled() -> bool {
+    is_grad_enabled()
+}
+
+#[pyfunction(name = "is_inference_mode_enabled")]
+pub fn py_is_inference_mode_enabled() -> bool {
+    is_inference_mode_enabled()
+}
+
+/// The mode of the current thread as a pair `(enabled, inference)`.
+#[pyfunction(name = "_grad_mode")]
+pub fn py_grad_mode() -> (bool, bool) {
+    let mode = grad_mode();
+    (mode.enabled, mode.inference)
+}
+
+/// Sets the mode of the current thread and returns the previous one. The
+/// Python context managers are built on it.
+#[pyfunction(name = "_set_grad_mode")]
+pub fn py_set_grad_mode(enabled: bool, inference: bool) -> (bool, bool) {
+    let previous = set_grad_mode(GradMode { enabled, inference });
+    (previous.enabled, previous.inference)
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_is_grad_enabled, m)?)?;
+    m.add_function(wrap_pyfunction!(py_is_inference_mode_enabled, m)?)?;
+    m.add_function(wrap_pyfunction!(py_grad_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_grad_mode, m)?)?;
+    Ok(())
+}
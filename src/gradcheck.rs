@@ -0,0 +1,335 @@
+use pyo3::prelude::*;
+
+use crate::{
+    backward::grad,
+    dispatch_float,
+    dtype::Element,
+    errors::{AutogradError, Result},
+    functional::{call, jacobian, zeros_like},
+    grad_mode::GradModeGuard,
+    objects::Tensor,
+    operations::view::reshape,
+    utils::new_tensor_simple,
+};
+
+/* Checks the hand-written backward of operations against finite differences.
+ * The analytical Jacobian is computed by backpropagating one element of the
+ * outputs at a time, the numerical one by moving one element of the inputs at
+ * a time by +/- eps. Only inputs that require grad are checked, and float64
+ * inputs should be used since eps is small. */
+
+/// The indices of the inputs that require grad, at least one must.
+fn checked_inputs(inputs: &[Tensor]) -> Result<Vec<usize>> {
+    let checked: Vec<usize> = (0..inputs.len())
+        .filter(|&i| inputs[i].get_requires_grad())
+        .collect();
+    if checked.is_empty() {
+        return Err(AutogradError::MissingGrad(
+            "gradcheck expects at least one input that requires grad".to_string(),
+        ));
+    }
+    Ok(checked)
+}
+
+/// `inputs` with the inputs at the indices `checked` replaced by `values`.
+fn substitute(inputs: &[Tensor], checked: &[usize], values: &[Tensor]) -> Vec<Tensor> {
+    let mut inputs = inputs.to_vec();
+    for (&i, value) in checked.iter().zip(values) {
+        inputs[i] = value.clone();
+    }
+    inputs
+}
+
+/// The elements of a floating point tensor, as f64.
+fn to_f64(t: &Tensor) -> Vec<f64> {
+    dispatch_float!(t.dtype, T => t.get_data_ref::<T>().iter().map(|x| x.to_f64()).collect())
+        .unwrap()
+}
+
+/// A copy of `t` with `delta` added to its row-major element `i`.
+fn perturb(t: &Tensor, i: usize, delta: f64) -> Tensor {
+    dispatch_float!(t.dtype, T => {
+        let mut data = t.get_data_ref::<T>().to_vec();
+        data[i] = T::from_f64(data[i].to_f64() + delta);
+        new_tensor_simple(t.get_shape(), data)
+    })
+    .unwrap()
+}
+
+/// The Jacobian of each output of `f` with respect to each input, estimated by
+/// central differences, flattened row-major like the analytical one.
+fn numerical_jacobian<F, E>(
+    f: &mut F,
+    inputs: &[Tensor],
+    eps: f64,
+) -> std::result::Result<Vec<Vec<Vec<f64>>>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+{
+    let _guard = GradModeGuard::no_grad();
+    let outputs = f(inputs)?;
+    let mut jacobian: Vec<Vec<Vec<f64>>> = outputs
+        .iter()
+        .map(|output| {
+            let size: usize = output.get_shape().iter().product();
+            inputs
+                .iter()
+                .map(|input| vec![0.0; size * input.get_shape().iter().product::<usize>()])
+                .collect()
+        })
+        .collect();
+
+    for (j, input) in inputs.iter().enumerate() {
+        let input_size: usize = input.get_shape().iter().product();
+        for k in 0..input_size {
+            let mut shifted = inputs.to_vec();
+            shifted[j] = perturb(input, k, eps);
+            let plus = f(&shifted)?;
+            shifted[j] = perturb(input, k, -eps);
+            let minus = f(&shifted)?;
+            for (o, (plus, minus)) in plus.iter().zip(&minus).enumerate() {
+                if !plus.dtype.is_floating_point() {
+                    continue;
+                }
+                let (plus, minus) = (to_f64(plus), to_f64(minus));
+                for i in 0..plus.len() {
+                    jacobian[o][j][i * input_size + k] = (plus[i] - minus[i]) / (2.0 * eps);
+                }
+            }
+        }
+    }
+    Ok(jacobian)
+}
+
+/// Compares the analytical and numerical Jacobians of `f`, returning a
+/// description of the first element that differs, if any.
+fn compare<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> std::result::Result<Option<String>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let checked = checked_inputs(inputs)?;
+    let values: Vec<Tensor> = checked.iter().map(|&i| inputs[i].detach()).collect();
+    let mut checked_f = |values: &[Tensor]| f(&substitute(inputs, &checked, values));
+
+    let analytical = jacobian(&mut checked_f, &values, false, false, false)?;
+    let numerical = numerical_jacobian(&mut checked_f, &values, eps)?;
+    for (o, (analytical, numerical)) in analytical.iter().zip(&numerical).enumerate() {
+        for (j, (analytical, numerical)) in analytical.iter().zip(numerical).enumerate() {
+            let input_size: usize = values[j].get_shape().iter().product();
+            for (k, (&a, &n)) in to_f64(analytical).iter().zip(numerical).enumerate() {
+                if (a - n).abs() > atol + rtol * n.abs() {
+                    return Ok(Some(format!(
+                        "Jacobian mismatch for output {} with respect to input {}, at \
+                         element {} of the output and {} of the input: numerical {} but \
+                         analytical {}",
+                        o,
+                        checked[j],
+                        k / input_size,
+                        k % input_size,
+                        n,
+                        a
+                    )));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Checks the gradients of `f` at `inputs` against finite differences, with
+/// respect to the inputs that require grad. A mismatch is a `GradCheck` error.
+pub fn gradcheck<F, E>(
+    f: F,
+    inputs: &[Tensor],
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> std::result::Result<(), E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    match compare(f, inputs, eps, atol, rtol)? {
+        None => Ok(()),
+        Some(message) => Err(AutogradError::GradCheck(message).into()),
+    }
+}
+
+/// A fixed tensor shaped like `t` whose elements are spread in [-1, 1), so
+/// that no two elements of a gradient are weighted the same.
+fn spread_like(t: &Tensor) -> Tensor {
+    dispatch_float!(t.dtype, T => {
+        let size = t.get_shape().iter().product();
+        let data = (0..size)
+            .map(|i| T::from_f64(((i + 1) as f64 * 0.618_033_988_749_895).fract() * 2.0 - 1.0))
+            .collect();
+        new_tensor_simple(t.get_shape(), data)
+    })
+    .unwrap()
+}
+
+/// The function whose gradients gradgradcheck checks: the gradients of `f`
+/// with respect to the `checked` inputs, weighted by grad outputs given after
+/// the inputs, one per output in `differentiable`.
+fn gradients<F, E>(
+    f: &mut F,
+    inputs: &[Tensor],
+    checked: &[usize],
+    differentiable: &[usize],
+) -> std::result::Result<Vec<Tensor>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let _guard = GradModeGuard::enable_grad();
+    let (inputs, grad_outputs) = inputs.split_at(inputs.len() - differentiable.len());
+    let values = checked
+        .iter()
+        .map(|&i| {
+            let input = &inputs[i];
+            if input.get_requires_grad() {
+                let shape = input.get_shape().iter().map(|&d| d as isize).collect();
+                return reshape(input.clone(), shape);
+            }
+            let input = input.detach();
+            input.set_requires_grad(true)?;
+            Ok(input)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let outputs = f(&substitute(inputs, checked, &values))?;
+    let grads = grad(
+        differentiable.iter().map(|&o| outputs[o].clone()).collect(),
+        values.clone(),
+        grad_outputs.iter().cloned().map(Some).collect(),
+        true,
+        true,
+        true,
+    )?;
+    Ok(grads
+        .into_iter()
+        .zip(&values)
+        .map(|(grad, value)| grad.unwrap_or_else(|| zeros_like(value)))
+        .collect())
+}
+
+/// Checks the second order gradients of `f` at `inputs` against finite
+/// differences of its gradients, weighted by `grad_outputs`. They default to a
+/// fixed tensor for every floating point output that requires grad.
+pub fn gradgradcheck<F, E>(
+    mut f: F,
+    inputs: &[Tensor],
+    grad_outputs: Option<Vec<Tensor>>,
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> std::result::Result<(), E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    match compare_gradients(&mut f, inputs, grad_outputs, eps, atol, rtol)? {
+        None => Ok(()),
+        Some(message) => Err(AutogradError::GradCheck(message).into()),
+    }
+}
+
+fn compare_gradients<F, E>(
+    f: &mut F,
+    inputs: &[Tensor],
+    grad_outputs: Option<Vec<Tensor>>,
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> std::result::Result<Option<String>, E>
+where
+    F: FnMut(&[Tensor]) -> std::result::Result<Vec<Tensor>, E>,
+    E: From<AutogradError>,
+{
+    let checked = checked_inputs(inputs)?;
+    let outputs = {
+        let _guard = GradModeGuard::enable_grad();
+        f(inputs)?
+    };
+    let differentiable: Vec<usize> = (0..outputs.len())
+        .filter(|&o| outputs[o].dtype.is_floating_point() && outputs[o].get_requires_grad())
+        .collect();
+    let grad_outputs = match grad_outputs {
+        None => differentiable
+            .iter()
+            .map(|&o| spread_like(&outputs[o]))
+            .collect(),
+        Some(grad_outputs) if grad_outputs.len() == differentiable.len() => grad_outputs,
+        Some(grad_outputs) => {
+            return Err(AutogradError::InvalidArgument(format!(
+                "Expected {} grad_outputs, got {}",
+                differentiable.len(),
+                grad_outputs.len()
+            ))
+            .into())
+        }
+    };
+    for (&o, grad_output) in differentiable.iter().zip(&grad_outputs) {
+        outputs[o].check_grad(grad_output)?;
+    }
+
+    let mut all_inputs = inputs.to_vec();
+    for grad_output in grad_outputs {
+        let grad_output = grad_output.detach();
+        grad_output.set_requires_grad(true)?;
+        all_inputs.push(grad_output);
+    }
+    compare(
+        |x: &[Tensor]| gradients(f, x, &checked, &differentiable),
+        &all_inputs,
+        eps,
+        atol,
+        rtol,
+    )
+}
+
+#[pyfunction(name = "_gradcheck")]
+pub fn py_gradcheck(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> PyResult<Option<String>> {
+    py.allow_threads(|| compare(|x| call(&func, x), &inputs, eps, atol, rtol))
+}
+
+#[pyfunction(name = "_gradgradcheck")]
+pub fn py_gradgradcheck(
+    py: Python<'_>,
+    func: PyObject,
+    inputs: Vec<Tensor>,
+    grad_outputs: Option<Vec<Tensor>>,
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> PyResult<Option<String>> {
+    py.allow_threads(|| {
+        compare_gradients(
+            &mut |x: &[Tensor]| call(&func, x),
+            &inputs,
+            grad_outputs,
+            eps,
+            atol,
+            rtol,
+        )
+    })
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_gradcheck, m)?)?;
+    m.add_function(wrap_pyfunction!(py_gradgradcheck, m)?)?;
+    Ok(())
+}
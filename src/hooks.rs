@@ -0,0 +1,218 @@
+use pyo3::prelude::*;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, RwLock, Weak,
+};
+
+use crate::{
+    errors::{AutogradError, Result},
+    objects::{CoreTensor, Graph, Node, Tensor},
+};
+
+/* Hooks let the user observe or replace gradients during the backward pass,
+ * e.g. to clip the gradients of a layer or to look at the gradient of an
+ * intermediate tensor. A tensor hook runs on the gradient flowing into the
+ * tensor, before it is accumulated or sent through the operation that created
+ * the tensor. A node hook runs once the operation computed the gradients of
+ * its inputs. Hooks run in the order they were registered, each on the result
+ * of the previous one. */
+
+/// Called with the gradient of a tensor, returns a gradient to replace it or
+/// None to keep it.
+pub type TensorHook = Arc<dyn Fn(&Tensor) -> Result<Option<Tensor>> + Send + Sync>;
+
+/// Called with the gradients of the inputs of an operation and the gradient
+/// of its output, returns gradients to replace those of the inputs or None to
+/// keep them.
+pub type NodeHook =
+    Arc<dyn Fn(&[Option<Tensor>], &Tensor) -> Result<Option<Vec<Option<Tensor>>>> + Send + Sync>;
+
+/* Identifies hooks, so that a handle removes the hook it registered */
+static HOOKS: AtomicUsize = AtomicUsize::new(0);
+
+enum HookTarget {
+    Tensor(Weak<RwLock<CoreTensor>>),
+    Node(Weak<RwLock<Node>>),
+}
+
+/// Removes the hook it was returned for. It doesn't keep the tensor or the
+/// operation alive.
+#[pyclass]
+pub struct RemovableHandle {
+    target: HookTarget,
+    id: usize,
+}
+
+#[pymethods]
+impl RemovableHandle {
+    /// Removes the hook. Does nothing if it was already removed.
+    pub fn remove(&self) {
+        match self.target {
+            HookTarget::Tensor(ref core) => {
+                if let Some(core) = core.upgrade() {
+                    core.write().unwrap().hooks.retain(|(id, _)| *id != self.id);
+                }
+            }
+            HookTarget::Node(ref node) => {
+                if let Some(node) = node.upgrade() {
+                    node.write().unwrap().hooks.retain(|(id, _)| *id != self.id);
+                }
+            }
+        }
+    }
+}
+
+impl Tensor {
+    /// Registers a hook on the gradient of the tensor, which must require grad.
+    pub fn register_hook(&self, hook: TensorHook) -> Result<RemovableHandle> {
+        if !self.get_requires_grad() {
+            return Err(AutogradError::MissingGrad(
+                "Cannot register a hook on a tensor that does not require grad".to_string(),
+            ));
+        }
+        let id = HOOKS.fetch_add(1, Ordering::Relaxed);
+        self.core.write().unwrap().hooks.push((id, hook));
+        Ok(RemovableHandle {
+            target: HookTarget::Tensor(Arc::downgrade(&self.core)),
+            id,
+        })
+    }
+
+    /// Runs the hooks of the tensor on `grad`.
+    pub fn run_hooks(&self, mut grad: Tensor) -> Result<Tensor> {
+        /* The lock is released before running them, hooks may use the tensor */
+        let hooks: Vec<TensorHook> = {
+            let core = self.core.read().unwrap();
+            core.hooks.iter().map(|(_, hook)| hook.clone()).collect()
+        };
+        for hook in hooks {
+            if let Some(new_grad) = hook(&grad)? {
+                self.check_grad(&new_grad)?;
+                grad = new_grad;
+            }
+        }
+        Ok(grad)
+    }
+
+    pub fn has_hooks(&self) -> bool {
+        !self.core.read().unwrap().hooks.is_empty()
+    }
+
+    /// Makes the backward pass set the gradient of the tensor even if it is
+    /// not a leaf. Leaves always keep their gradient.
+    pub fn retain_grad(&self) -> Result<()> {
+        if !self.get_requires_grad() {
+            return Err(AutogradError::MissingGrad(
+                "Cannot retain the gradient of a tensor that does not require grad".to_string(),
+            ));
+        }
+        if !self.is_leaf() {
+            self.core.write().unwrap().retains_grad = true;
+        }
+        Ok(())
+    }
+
+    /// Whether the backward pass sets the gradient of the tensor.
+    pub fn keeps_grad(&self) -> bool {
+        self.is_leaf() || self.core.read().unwrap().retains_grad
+    }
+}
+
+impl Graph {
+    /// Registers a hook on the gradients computed by the operation.
+    pub fn register_hook(&self, hook: NodeHook) -> RemovableHandle {
+        let id = HOOKS.fetch_add(1, Ordering::Relaxed);
+        self.0.write().unwrap().hooks.push((id, hook));
+        RemovableHandle {
+            target: HookTarget::Node(Arc::downgrade(&self.0)),
+            id,
+        }
+    }
+
+    /// Runs the hooks of the operation on the gradients of its `inputs`.
+    pub fn run_hooks(
+        &self,
+        inputs: &[Tensor],
+        mut grads: Vec<Option<Tensor>>,
+        grad_output: &Tensor,
+    ) -> Result<Vec<Option<Tensor>>> {
+        let hooks: Vec<NodeHook> = {
+            let node = self.0.read().unwrap();
+            node.hooks.iter().map(|(_, hook)| hook.clone()).collect()
+        };
+        for hook in hooks {
+            let new_grads = match hook(&grads, grad_output)? {
+                None => continue,
+                Some(new_grads) => new_grads,
+            };
+            if new_grads.len() != inputs.len() {
+                return Err(AutogradError::Hook(format!(
+                    "A hook of {} returned {} gradients for {} inputs",
+                    self.name(),
+                    new_grads.len(),
+                    inputs.len()
+                )));
+            }
+            for (input, grad) in inputs.iter().zip(new_grads.iter()) {
+                if let Some(grad) = grad {
+                    input.check_grad(grad)?;
+                }
+            }
+            grads = new_grads;
+        }
+        Ok(grads)
+    }
+}
+
+/// Wraps the error raised by a Python hook.
+fn hook_error(error: PyErr) -> AutogradError {
+    AutogradError::Python {
+        origin: "A hook".to_string(),
+        error: error.into(),
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    /// Registers `hook`, called as `hook(grad)` with the gradient of the tensor
+    /// during the backward pass. It can return a tensor to replace the gradient.
+    #[pyo3(name = "register_hook")]
+    pub fn py_register_hook(&self, hook: PyObject) -> PyResult<RemovableHandle> {
+        Ok(self.register_hook(Arc::new(move |grad: &Tensor| {
+            Python::with_gil(|py| {
+                hook.call1(py, (grad.clone(),))
+                    .and_then(|result| result.extract::<Option<Tensor>>(py))
+                    .map_err(hook_error)
+            })
+        }))?)
+    }
+
+    #[pyo3(name = "retain_grad")]
+    pub fn py_retain_grad(&self) -> PyResult<()> {
+        Ok(self.retain_grad()?)
+    }
+
+    #[getter]
+    pub fn retains_grad(&self) -> bool {
+        self.core.read().unwrap().retains_grad
+    }
+}
+
+#[pymethods]
+impl Graph {
+    /// Registers `hook`, called as `hook(grad_inputs, grad_output)` once the
+    /// operation computed the gradients of its inputs. It can return a list of
+    /// gradients to replace them.
+    #[pyo3(name = "register_hook")]
+    pub fn py_register_hook(&self, hook: PyObject) -> RemovableHandle {
+        self.register_hook(Arc::new(
+            move |grads: &[Option<Tensor>], grad_output: &Tensor| {
+                Python::with_gil(|py| {
+                    hook.call1(py, (grads.to_vec(), grad_output.clone()))
+                        .and_then(|result| result.extract::<Option<Vec<Option<Tensor>>>>(py))
+                        .map_err(hook_error)
+                })
+            },
+        ))
+    }
+}
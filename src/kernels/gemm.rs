@@ -0,0 +1,347 @@
+use rayon::prelude::*;
+
+use crate::{dtype::Numeric, threads};
+
+/* General matrix multiplication, c += a @ b, in the style of BLIS. The columns
+ * of b are split in blocks of NC, the inner dimension in blocks of KC and the
+ * rows of a in blocks of MC. Each block of a and b is packed into panels of MR
+ * rows and NR columns, laid out in the order the micro-kernel reads them, and
+ * the micro-kernel computes a MR x NR tile of c from a pair of panels. Packing
+ * reads the operands through their strides, so transposed and broadcast views
+ * are multiplied without being copied first. */
+
+const KC: usize = 256;
+const NC: usize = 4096;
+/// MC is a number of panels of MR rows.
+const MC_PANELS: usize = 16;
+
+/// A strided matrix in a buffer: element (i, j) is
+/// `data[offset + i * row_stride + j * col_stride]`.
+#[derive(Clone, Copy)]
+pub struct MatRef<'a, T> {
+    pub data: &'a [T],
+    pub offset: usize,
+    pub rows: usize,
+    pub cols: usize,
+    pub row_stride: usize,
+    pub col_stride: usize,
+}
+
+impl<'a, T: Copy> MatRef<'a, T> {
+    /// A contiguous row-major matrix.
+    pub fn new(data: &'a [T], rows: usize, cols: usize) -> Self {
+        MatRef {
+            data,
+            offset: 0,
+            rows,
+            cols,
+            row_stride: cols,
+            col_stride: 1,
+        }
+    }
+
+    /// The transposed matrix, without copying.
+    pub fn t(self) -> Self {
+        MatRef {
+            rows: self.cols,
+            cols: self.rows,
+            row_stride: self.col_stride,
+            col_stride: self.row_stride,
+            ..self
+        }
+    }
+
+    fn get(&self, i: usize, j: usize) -> T {
+        self.data[self.offset + i * self.row_stride + j * self.col_stride]
+    }
+}
+
+/// Computes `tile = a @ b` for a panel `a` of MR rows and a panel `b` of NR
+/// columns, both packed k-major over `kc` steps. The tile is row-major.
+pub type MicroKernel<T> = fn(kc: usize, a: &[T], b: &[T], tile: &mut [T]);
+
+/// Element types with a GEMM micro-kernel.
+pub trait Gemm: Numeric {
+    const MR: usize;
+    const NR: usize;
+
+    /// The fastest micro-kernel supported by the CPU.
+    fn microkernel() -> MicroKernel<Self>;
+}
+
+/// The portable micro-kernel, which the compiler vectorises as it can.
+fn microkernel_generic<T: Numeric, const MR: usize, const NR: usize>(
+    kc: usize,
+    a: &[T],
+    b: &[T],
+    tile: &mut [T],
+) {
+    let mut acc = [[T::ZERO; NR]; MR];
+    for (a, b) in a.chunks_exact(MR).zip(b.chunks_exact(NR)).take(kc) {
+        for i in 0..MR {
+            for j in 0..NR {
+                acc[i][j] += a[i] * b[j];
+            }
+        }
+    }
+    for (row, acc) in tile.chunks_exact_mut(NR).zip(acc.iter()) {
+        row.copy_from_slice(acc);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    /// 6 x 16 tiles, held in 12 registers of 8 floats.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn microkernel_f32(kc: usize, a: &[f32], b: &[f32], tile: &mut [f32]) {
+        assert!(a.len() >= kc * 6 && b.len() >= kc * 16 && tile.len() >= 6 * 16);
+        let (a, b) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [[_mm256_setzero_ps(); 2]; 6];
+        for k in 0..kc {
+            let b0 = _mm256_loadu_ps(b.add(k * 16));
+            let b1 = _mm256_loadu_ps(b.add(k * 16 + 8));
+            for (i, acc) in acc.iter_mut().enumerate() {
+                let a = _mm256_set1_ps(*a.add(k * 6 + i));
+                acc[0] = _mm256_fmadd_ps(a, b0, acc[0]);
+                acc[1] = _mm256_fmadd_ps(a, b1, acc[1]);
+            }
+        }
+        let tile = tile.as_mut_ptr();
+        for (i, acc) in acc.iter().enumerate() {
+            _mm256_storeu_ps(tile.add(i * 16), acc[0]);
+            _mm256_storeu_ps(tile.add(i * 16 + 8), acc[1]);
+        }
+    }
+
+    /// 6 x 8 tiles, held in 12 registers of 4 doubles.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn microkernel_f64(kc: usize, a: &[f64], b: &[f64], tile: &mut [f64]) {
+        assert!(a.len() >= kc * 6 && b.len() >= kc * 8 && tile.len() >= 6 * 8);
+        let (a, b) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [[_mm256_setzero_pd(); 2]; 6];
+        for k in 0..kc {
+            let b0 = _mm256_loadu_pd(b.add(k * 8));
+            let b1 = _mm256_loadu_pd(b.add(k * 8 + 4));
+            for (i, acc) in acc.iter_mut().enumerate() {
+                let a = _mm256_set1_pd(*a.add(k * 6 + i));
+                acc[0] = _mm256_fmadd_pd(a, b0, acc[0]);
+                acc[1] = _mm256_fmadd_pd(a, b1, acc[1]);
+            }
+        }
+        let tile = tile.as_mut_ptr();
+        for (i, acc) in acc.iter().enumerate() {
+            _mm256_storeu_pd(tile.add(i * 8), acc[0]);
+            _mm256_storeu_pd(tile.add(i * 8 + 4), acc[1]);
+        }
+    }
+
+    pub fn detected() -> bool {
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    /// 6 x 16 tiles, held in 24 registers of 4 floats.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn microkernel_f32(kc: usize, a: &[f32], b: &[f32], tile: &mut [f32]) {
+        assert!(a.len() >= kc * 6 && b.len() >= kc * 16 && tile.len() >= 6 * 16);
+        let (a, b) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [[vdupq_n_f32(0.0); 4]; 6];
+        for k in 0..kc {
+            let b = [
+                vld1q_f32(b.add(k * 16)),
+                vld1q_f32(b.add(k * 16 + 4)),
+                vld1q_f32(b.add(k * 16 + 8)),
+                vld1q_f32(b.add(k * 16 + 12)),
+            ];
+            for (i, acc) in acc.iter_mut().enumerate() {
+                let a = vdupq_n_f32(*a.add(k * 6 + i));
+                for (acc, &b) in acc.iter_mut().zip(b.iter()) {
+                    *acc = vfmaq_f32(*acc, a, b);
+                }
+            }
+        }
+        let tile = tile.as_mut_ptr();
+        for (i, acc) in acc.iter().enumerate() {
+            for (j, &acc) in acc.iter().enumerate() {
+                vst1q_f32(tile.add(i * 16 + j * 4), acc);
+            }
+        }
+    }
+
+    /// 6 x 8 tiles, held in 24 registers of 2 doubles.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn microkernel_f64(kc: usize, a: &[f64], b: &[f64], tile: &mut [f64]) {
+        assert!(a.len() >= kc * 6 && b.len() >= kc * 8 && tile.len() >= 6 * 8);
+        let (a, b) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [[vdupq_n_f64(0.0); 4]; 6];
+        for k in 0..kc {
+            let b = [
+                vld1q_f64(b.add(k * 8)),
+                vld1q_f64(b.add(k * 8 + 2)),
+                vld1q_f64(b.add(k * 8 + 4)),
+                vld1q_f64(b.add(k * 8 + 6)),
+            ];
+            for (i, acc) in acc.iter_mut().enumerate() {
+                let a = vdupq_n_f64(*a.add(k * 6 + i));
+                for (acc, &b) in acc.iter_mut().zip(b.iter()) {
+                    *acc = vfmaq_f64(*acc, a, b);
+                }
+            }
+        }
+        let tile = tile.as_mut_ptr();
+        for (i, acc) in acc.iter().enumerate() {
+            for (j, &acc) in acc.iter().enumerate() {
+                vst1q_f64(tile.add(i * 8 + j * 2), acc);
+            }
+        }
+    }
+
+    pub fn detected() -> bool {
+        std::arch::is_aarch64_feature_detected!("neon")
+    }
+}
+
+impl Gemm for f32 {
+    const MR: usize = 6;
+    const NR: usize = 16;
+
+    fn microkernel() -> MicroKernel<Self> {
+        #[cfg(target_arch = "x86_64")]
+        if avx2::detected() {
+            return |kc, a, b, tile| unsafe { avx2::microkernel_f32(kc, a, b, tile) };
+        }
+        #[cfg(target_arch = "aarch64")]
+        if neon::detected() {
+            return |kc, a, b, tile| unsafe { neon::microkernel_f32(kc, a, b, tile) };
+        }
+        microkernel_generic::<f32, 6, 16>
+    }
+}
+
+impl Gemm for f64 {
+    const MR: usize = 6;
+    const NR: usize = 8;
+
+    fn microkernel() -> MicroKernel<Self> {
+        #[cfg(target_arch = "x86_64")]
+        if avx2::detected() {
+            return |kc, a, b, tile| unsafe { avx2::microkernel_f64(kc, a, b, tile) };
+        }
+        #[cfg(target_arch = "aarch64")]
+        if neon::detected() {
+            return |kc, a, b, tile| unsafe { neon::microkernel_f64(kc, a, b, tile) };
+        }
+        microkernel_generic::<f64, 6, 8>
+    }
+}
+
+impl Gemm for i64 {
+    const MR: usize = 4;
+    const NR: usize = 4;
+
+    fn microkernel() -> MicroKernel<Self> {
+        microkernel_generic::<i64, 4, 4>
+    }
+}
+
+/// Packs rows `rows` and columns `cols` of `a` into panels of MR rows. The
+/// last panel is padded with zeros.
+fn pack_a<T: Gemm>(
+    a: &MatRef<T>,
+    rows: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+    packed: &mut Vec<T>,
+) {
+    packed.clear();
+    for panel in rows.clone().step_by(T::MR) {
+        for k in cols.clone() {
+            for i in panel..panel + T::MR {
+                packed.push(if i < rows.end { a.get(i, k) } else { T::ZERO });
+            }
+        }
+    }
+}
+
+/// Packs rows `rows` and columns `cols` of `b` into panels of NR columns. The
+/// last panel is padded with zeros.
+fn pack_b<T: Gemm>(
+    b: &MatRef<T>,
+    rows: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+    packed: &mut Vec<T>,
+) {
+    packed.clear();
+    for panel in cols.clone().step_by(T::NR) {
+        for k in rows.clone() {
+            for j in panel..panel + T::NR {
+                packed.push(if j < cols.end { b.get(k, j) } else { T::ZERO });
+            }
+        }
+    }
+}
+
+/// Adds `a @ b` to `c`, a contiguous row-major matrix with the rows of `a` and
+/// the columns of `b`. Blocks of rows of `c` are computed in parallel on the
+/// thread pool.
+pub fn gemm<T: Gemm>(a: MatRef<T>, b: MatRef<T>, c: &mut [T]) {
+    let (m, n, p) = (a.rows, a.cols, b.cols);
+    assert!(b.rows == n && c.len() == m * p);
+    if m == 0 || n == 0 || p == 0 {
+        return;
+    }
+    let (mr, nr) = (T::MR, T::NR);
+    let mc = MC_PANELS * mr;
+    let kernel = T::microkernel();
+
+    let mut b_packed = Vec::new();
+    for jc in (0..p).step_by(NC) {
+        let nc = NC.min(p - jc);
+        for pc in (0..n).step_by(KC) {
+            let kc = KC.min(n - pc);
+            pack_b(&b, pc..pc + kc, jc..jc + nc, &mut b_packed);
+            let b_packed = &b_packed;
+            threads::install(|| {
+                c.par_chunks_mut(mc * p).enumerate().for_each_init(
+                    || (Vec::new(), vec![T::ZERO; mr * nr]),
+                    |(a_packed, tile), (block, c)| {
+                        let ic = block * mc;
+                        let mc = c.len() / p;
+                        pack_a(&a, ic..ic + mc, pc..pc + kc, a_packed);
+                        for jr in (0..nc).step_by(nr) {
+                            let b_panel = &b_packed[jr * kc..(jr + nr) * kc];
+                            for ir in (0..mc).step_by(mr) {
+                                let a_panel = &a_packed[ir * kc..(ir + mr) * kc];
+                                kernel(kc, a_panel, b_panel, tile);
+                                /* Only the part of the tile inside c is kept */
+                                let columns = nr.min(nc - jr);
+                                for (i, tile) in tile.chunks_exact(nr).take(mc - ir).enumerate() {
+                                    let start = (ir + i) * p + jc + jr;
+                                    for (c, &t) in c[start..start + columns].iter_mut().zip(tile) {
+                                        *c += t;
+                                    }
+                                }
+                            }
+                        }
+                    },
+                )
+            });
+        }
+    }
+}
+
+/// `a @ b` for every pair of matrices, stacked in a new row-major buffer.
+pub fn batch_gemm<T: Gemm>(a: &[MatRef<T>], b: &[MatRef<T>], m: usize, p: usize) -> Vec<T> {
+    let mut c = vec![T::ZERO; a.len() * m * p];
+    threads::install(|| {
+        c.par_chunks_mut((m * p).max(1))
+            .zip(a.par_iter().zip(b.par_iter()))
+            .for_each(|(c, (a, b))| gemm(*a, *b, c))
+    });
+    return c;
+}
@@ -0,0 +1,4 @@
+/* Low level loops over raw buffers, shared by the operations */
+
+pub mod elementwise;
+pub mod gemm;
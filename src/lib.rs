@@ -1,18 +1,44 @@
 #![feature(mapped_lock_guards)]
+#![feature(float_erf)]
+#![allow(clippy::needless_return)]
 
 use pyo3::prelude::*;
 
 pub mod backward;
+pub mod dtype;
+pub mod engine;
 pub mod eq;
+pub mod errors;
+pub mod forward;
+pub mod function;
+pub mod functional;
+pub mod grad_mode;
+pub mod gradcheck;
+pub mod hooks;
+pub mod kernels;
 pub mod objects;
 pub mod operations;
+pub mod threads;
 pub mod utils;
 
 #[pymodule]
 fn autograd(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<objects::Tensor>()?;
     m.add_class::<objects::Graph>()?;
+    m.add_class::<hooks::RemovableHandle>()?;
+    m.add_class::<dtype::DType>()?;
+    m.add("bool", dtype::DType::Bool)?;
+    m.add("int64", dtype::DType::Int64)?;
+    m.add("float32", dtype::DType::Float32)?;
+    m.add("float64", dtype::DType::Float64)?;
+    backward::register_functions(m)?;
+    forward::register_functions(m)?;
+    function::register_functions(m)?;
+    functional::register_functions(m)?;
+    grad_mode::register_functions(m)?;
+    gradcheck::register_functions(m)?;
+    operations::loss::register_functions(m)?;
+    threads::register_functions(m)?;
+    errors::register_exceptions(m)?;
     Ok(())
 }
-
-pub type DTYPE = f32;
@@ -1,52 +1,100 @@
 use pyo3::prelude::*;
 
-use std::sync::{Arc, RwLock};
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, MappedRwLockReadGuard, RwLock, RwLockReadGuard,
+    },
+};
 
-use crate::{backward::Backward, DTYPE};
+use crate::{
+    backward::Backward,
+    dispatch_all,
+    dtype::{Buffer, DType, Element},
+    errors::{AutogradError, Result},
+    hooks::{NodeHook, TensorHook},
+    operations::add::add,
+};
+
+/* The flat buffer holding the elements of a tensor, shared between views */
+pub type Storage = Arc<RwLock<Buffer>>;
 
 /* Tensor is the main object we manipulate */
 pub struct CoreTensor {
+    /* Element (i, j, ...) is storage[offset + i * strides[0] + j * strides[1] + ...] */
     pub shape: Vec<usize>,
-    pub data: Vec<DTYPE>,
+    pub strides: Vec<usize>,
+    pub offset: usize,
     pub requires_grad: bool,
     pub grad: Option<Tensor>,
     pub graph: Option<Graph>,
+    /* The tangent of the tensor and the dual level it belongs to */
+    pub tangent: Option<(usize, Tensor)>,
+    /* Hooks run on the gradient of the tensor, identified to be removed */
+    pub hooks: Vec<(usize, TensorHook)>,
+    /* Whether the tensor keeps its gradient although it is not a leaf */
+    pub retains_grad: bool,
 }
 
 #[pyclass]
 #[derive(Clone)]
 pub struct Tensor {
     pub core: Arc<RwLock<CoreTensor>>,
+    /* The storage of a tensor is never replaced, so it lives outside of the core lock */
+    pub storage: Storage,
+    /* Counts the writes to the storage, shared with it between views */
+    pub version: Arc<AtomicUsize>,
+    /* The element type of the storage */
+    pub dtype: DType,
 }
 #[pymethods]
 impl Tensor {
+    /// Creates a tensor from a flat list of elements. The elements are
+    /// float32 unless another dtype is given.
     #[new]
-    pub fn new(
+    #[pyo3(signature = (shape, data, requires_grad, grad, graph, dtype=None))]
+    pub fn py_new(
         shape: Vec<usize>,
-        data: Vec<DTYPE>,
+        data: &Bound<'_, PyAny>,
         requires_grad: bool,
         grad: Option<Tensor>,
         graph: Option<Graph>,
-    ) -> Self {
-        if shape.iter().product::<usize>() != data.len() {
-            panic!("Shape and data length do not match");
-        }
-        Tensor {
-            core: Arc::new(RwLock::new(CoreTensor {
-                shape,
-                data,
-                requires_grad,
-                grad: grad,
-                graph: graph,
-            })),
-        }
+        dtype: Option<DType>,
+    ) -> PyResult<Self> {
+        let dtype = dtype.unwrap_or(DType::Float32);
+        Ok(dispatch_all!(dtype, T => Tensor::try_new(
+            shape,
+            data.extract::<Vec<T>>()?,
+            requires_grad,
+            grad,
+            graph,
+        )?))
     }
 
     pub fn get_shape(&self) -> Vec<usize> {
         self.core.read().unwrap().shape.clone()
     }
-    pub fn get_data(&self) -> Vec<DTYPE> {
-        self.core.read().unwrap().data.clone()
+    pub fn get_data(&self, py: Python<'_>) -> PyResult<PyObject> {
+        Ok(dispatch_all!(self.dtype, T => self
+            .get_data_ref::<T>()
+            .to_vec()
+            .into_pyobject(py)?
+            .into_any()
+            .unbind()))
+    }
+    pub fn get_dtype(&self) -> DType {
+        self.dtype
+    }
+    pub fn get_strides(&self) -> Vec<usize> {
+        self.core.read().unwrap().strides.clone()
+    }
+    pub fn get_offset(&self) -> usize {
+        self.core.read().unwrap().offset
+    }
+    pub fn is_contiguous(&self) -> bool {
+        let core = self.core.read().unwrap();
+        is_contiguous(&core.shape, &core.strides)
     }
     pub fn get_requires_grad(&self) -> bool {
         self.core.read().unwrap().requires_grad
@@ -63,27 +111,399 @@ impl Tensor {
     pub fn set_graph(&mut self, graph: Option<Graph>) {
         self.core.write().unwrap().graph = graph;
     }
+
+    /// A tensor sharing the storage of `self`, cut from the graph.
+    pub fn detach(&self) -> Tensor {
+        let core = self.core.read().unwrap();
+        self.new_view(
+            core.shape.clone(),
+            core.strides.clone(),
+            core.offset,
+            false,
+            None,
+        )
+    }
+
+    /// Sets whether the tensor requires grad, in place, and returns it.
+    #[pyo3(signature = (requires_grad=true))]
+    pub fn requires_grad_(&self, requires_grad: bool) -> PyResult<Tensor> {
+        self.set_requires_grad(requires_grad)?;
+        Ok(self.clone())
+    }
+
+    /// Resets the gradient, to None by default or to zeros.
+    #[pyo3(signature = (set_to_none=true))]
+    pub fn zero_grad(&mut self, set_to_none: bool) {
+        let grad = match self.get_grad() {
+            Some(grad) if !set_to_none => Some(dispatch_all!(grad.dtype, T => Tensor::new(
+                grad.get_shape(),
+                vec![T::from_f64(0.0); grad.get_shape().iter().product()],
+                false,
+                None,
+                None,
+            ))),
+            _ => None,
+        };
+        self.set_grad(grad);
+    }
+
+    /// Whether the tensor was created by the user rather than by an operation
+    /// recorded in the graph. Only leaves accumulate gradients.
+    #[getter]
+    pub fn is_leaf(&self) -> bool {
+        let core = self.core.read().unwrap();
+        !core.requires_grad || core.graph.is_none()
+    }
+
+    /// The operation that created the tensor, None for leaves.
+    #[getter]
+    pub fn grad_fn(&self) -> Option<Graph> {
+        if self.is_leaf() {
+            return None;
+        }
+        self.get_graph()
+    }
+
+    #[getter(requires_grad)]
+    pub fn py_requires_grad(&self) -> bool {
+        self.get_requires_grad()
+    }
+
+    #[setter(requires_grad)]
+    pub fn py_set_requires_grad(&self, requires_grad: bool) -> PyResult<()> {
+        Ok(self.set_requires_grad(requires_grad)?)
+    }
+
+    #[getter(grad)]
+    pub fn py_grad(&self) -> Option<Tensor> {
+        self.get_grad()
+    }
+
+    #[setter(grad)]
+    pub fn py_set_grad(&mut self, grad: Option<Tensor>) -> PyResult<()> {
+        if let Some(ref grad) = grad {
+            self.check_grad(grad)?;
+        }
+        self.set_grad(grad);
+        Ok(())
+    }
+}
+
+impl Tensor {
+    /// Creates a tensor, checking that the shape matches the data and the gradient,
+    /// and that only floating point tensors require a gradient.
+    pub fn try_new<T: Element>(
+        shape: Vec<usize>,
+        data: Vec<T>,
+        requires_grad: bool,
+        grad: Option<Tensor>,
+        graph: Option<Graph>,
+    ) -> Result<Self> {
+        if shape.iter().product::<usize>() != data.len() {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Shape {:?} does not match data of length {}",
+                shape,
+                data.len()
+            )));
+        }
+        if requires_grad && !T::DTYPE.is_floating_point() {
+            return Err(AutogradError::DTypeMismatch(format!(
+                "Only floating point tensors can require grad, got {}",
+                T::DTYPE.name()
+            )));
+        }
+        if let Some(ref grad) = grad {
+            if grad.get_shape() != shape {
+                return Err(AutogradError::ShapeMismatch(format!(
+                    "Gradient shape {:?} does not match tensor shape {:?}",
+                    grad.get_shape(),
+                    shape
+                )));
+            }
+            if grad.dtype != T::DTYPE {
+                return Err(AutogradError::DTypeMismatch(format!(
+                    "Gradient dtype {} does not match tensor dtype {}",
+                    grad.dtype.name(),
+                    T::DTYPE.name()
+                )));
+            }
+        }
+        Ok(Tensor {
+            core: Arc::new(RwLock::new(CoreTensor {
+                strides: strides(&shape),
+                shape,
+                offset: 0,
+                requires_grad,
+                grad,
+                graph,
+                tangent: None,
+                hooks: vec![],
+                retains_grad: false,
+            })),
+            storage: Arc::new(RwLock::new(T::into_buffer(data))),
+            version: Arc::new(AtomicUsize::new(0)),
+            dtype: T::DTYPE,
+        })
+    }
+
+    /// Creates a tensor, panicking if the shape does not match the data.
+    /// Operations use it for tensors whose shape is known to be valid.
+    pub fn new<T: Element>(
+        shape: Vec<usize>,
+        data: Vec<T>,
+        requires_grad: bool,
+        grad: Option<Tensor>,
+        graph: Option<Graph>,
+    ) -> Self {
+        Tensor::try_new(shape, data, requires_grad, grad, graph)
+            .expect("Operations create tensors whose shape matches their data")
+    }
+
+    /// Creates a tensor sharing the storage of `self`.
+    pub fn new_view(
+        &self,
+        shape: Vec<usize>,
+        strides: Vec<usize>,
+        offset: usize,
+        requires_grad: bool,
+        graph: Option<Graph>,
+    ) -> Self {
+        Tensor {
+            core: Arc::new(RwLock::new(CoreTensor {
+                shape,
+                strides,
+                offset,
+                requires_grad,
+                grad: None,
+                graph,
+                tangent: None,
+                hooks: vec![],
+                retains_grad: false,
+            })),
+            storage: self.storage.clone(),
+            version: self.version.clone(),
+            dtype: self.dtype,
+        }
+    }
 }
 
 // These methods are not safe to expose to Python
 impl Tensor {
-    pub fn get_data_ref(&'_ self) -> std::sync::MappedRwLockReadGuard<'_, Vec<DTYPE>> {
+    /// Identifies the tensor in the computation graph. Clones share the same id.
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.core) as *const () as usize
+    }
+
+    /// The number of writes to the storage of the tensor, from any view.
+    pub fn version(&self) -> usize {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Records a write to the storage of the tensor.
+    pub fn bump_version(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Only leaves can change whether they require grad, and only floating
+    /// point tensors can require it.
+    pub fn set_requires_grad(&self, requires_grad: bool) -> Result<()> {
+        if !self.is_leaf() {
+            return Err(AutogradError::NotLeaf(
+                "Only leaf tensors can change requires_grad, use detach to cut the graph"
+                    .to_string(),
+            ));
+        }
+        if requires_grad && !self.dtype.is_floating_point() {
+            return Err(AutogradError::DTypeMismatch(format!(
+                "Only floating point tensors can require grad, got {}",
+                self.dtype.name()
+            )));
+        }
+        self.core.write().unwrap().requires_grad = requires_grad;
+        Ok(())
+    }
+
+    /// Checks that `grad` can be the gradient of the tensor.
+    pub fn check_grad(&self, grad: &Tensor) -> Result<()> {
+        if grad.get_shape() != self.get_shape() {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Gradient shape {:?} does not match tensor shape {:?}",
+                grad.get_shape(),
+                self.get_shape()
+            )));
+        }
+        if grad.dtype != self.dtype {
+            return Err(AutogradError::DTypeMismatch(format!(
+                "Gradient dtype {} does not match tensor dtype {}",
+                grad.dtype.name(),
+                self.dtype.name()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Adds `grad` to the gradient of the tensor if it requires one.
+    pub fn accumulate_grad(&mut self, grad: Tensor) -> Result<()> {
+        if !self.get_requires_grad() {
+            return Ok(());
+        }
+        let accumulated = match self.get_grad() {
+            /* With create_graph the gradient keeps its graph, otherwise it is
+             * copied so that it never aliases another tensor */
+            None if grad.get_requires_grad() => grad,
+            None => grad.deep_copy(),
+            Some(current_grad) => add(grad, current_grad)?,
+        };
+        self.set_grad(Some(accumulated));
+        Ok(())
+    }
+
+    /// A contiguous copy of the tensor, with its own storage and no graph.
+    pub fn deep_copy(&self) -> Tensor {
+        dispatch_all!(self.dtype, T => Tensor::new(
+            self.get_shape(),
+            self.get_data_ref::<T>().to_vec(),
+            false,
+            None,
+            None,
+        ))
+    }
+
+    /// The whole storage of the tensor, to be indexed with its strides and
+    /// offset. Panics if `T` is not the element type of the tensor.
+    pub fn get_storage_ref<T: Element>(&'_ self) -> MappedRwLockReadGuard<'_, [T]> {
+        RwLockReadGuard::map(self.storage.read().unwrap(), T::from_buffer)
+    }
+
+    /// The elements of the tensor in row-major order. Contiguous tensors
+    /// borrow their storage, other views are gathered into a new buffer.
+    /// Panics if `T` is not the element type of the tensor.
+    pub fn get_data_ref<T: Element>(&'_ self) -> DataRef<'_, T> {
         let core = self.core.read().unwrap();
-        std::sync::RwLockReadGuard::map(core, |core| &core.data)
+        let storage = RwLockReadGuard::map(self.storage.read().unwrap(), T::from_buffer);
+        if is_contiguous(&core.shape, &core.strides) {
+            let start = core.offset;
+            let end = start + core.shape.iter().product::<usize>();
+            DataRef::Borrowed(MappedRwLockReadGuard::map(storage, |storage| {
+                &storage[start..end]
+            }))
+        } else {
+            DataRef::Owned(
+                strided_index_map(&core.shape, &core.strides, core.offset)
+                    .into_iter()
+                    .map(|i| storage[i])
+                    .collect(),
+            )
+        }
     }
 }
 
-pub fn strides(shape: &Vec<usize>) -> Vec<usize> {
-    let mut strides = vec![1];
-    for i in (0..shape.len() - 1).rev() {
-        strides.push(strides.last().unwrap() * shape[i + 1]);
+pub enum DataRef<'a, T> {
+    Borrowed(MappedRwLockReadGuard<'a, [T]>),
+    Owned(Vec<T>),
+}
+
+impl<T> Deref for DataRef<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            DataRef::Borrowed(data) => data,
+            DataRef::Owned(data) => data,
+        }
+    }
+}
+
+/// Strides of a contiguous tensor of the given shape.
+pub fn strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
     }
-    strides.reverse();
     strides
 }
 
+/// Whether elements laid out with these strides are contiguous in row-major order.
+pub fn is_contiguous(shape: &[usize], strides: &[usize]) -> bool {
+    let mut expected = 1;
+    for (&dim, &stride) in shape.iter().zip(strides.iter()).rev() {
+        if dim != 1 && stride != expected {
+            return false;
+        }
+        expected *= dim;
+    }
+    true
+}
+
+/// For every element of a tensor in row-major order, computes its index in the storage.
+pub fn strided_index_map(shape: &[usize], strides: &[usize], offset: usize) -> Vec<usize> {
+    let ndim = shape.len();
+    let size: usize = shape.iter().product();
+    let mut indices = Vec::with_capacity(size);
+    let mut index = vec![0; ndim];
+    let mut position = offset;
+    for _ in 0..size {
+        indices.push(position);
+        for d in (0..ndim).rev() {
+            index[d] += 1;
+            position += strides[d];
+            if index[d] < shape[d] {
+                break;
+            }
+            position -= strides[d] * shape[d];
+            index[d] = 0;
+        }
+    }
+    indices
+}
+
 /* Holds the computation graph of the tensor */
 
+pub struct Node {
+    /// The name of the operation, kept after it is released.
+    pub name: String,
+    /// The operation and the tensors it saved for backward. It is released by
+    /// the backward pass unless the graph is retained.
+    pub operation: Option<Box<dyn Backward + Send + Sync>>,
+    /// Hooks run on the gradients computed by the operation, identified to be
+    /// removed.
+    pub hooks: Vec<(usize, NodeHook)>,
+    /// The versions of the inputs and output of the operation when it was
+    /// recorded. They must not be written before it runs.
+    pub versions: Vec<(Arc<AtomicUsize>, usize)>,
+}
+
+impl Node {
+    /// Fails if a tensor the operation saved was modified in place since it
+    /// was recorded, as its gradients would be computed from the new values.
+    pub fn check_versions(&self) -> Result<()> {
+        let modified = self
+            .versions
+            .iter()
+            .any(|(version, recorded)| version.load(Ordering::Acquire) != *recorded);
+        if modified {
+            return Err(AutogradError::InPlaceOnGrad(format!(
+                "A tensor needed by {} to compute gradients was modified in place since \
+                 it was computed",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[pyclass]
 #[derive(Clone)]
-pub struct Graph(pub Arc<RwLock<dyn Backward + Send + Sync>>);
+pub struct Graph(pub Arc<RwLock<Node>>);
+
+#[pymethods]
+impl Graph {
+    /// The name of the operation, e.g. `AddOperation`.
+    pub fn name(&self) -> String {
+        self.0.read().unwrap().name.clone()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("<{}>", self.name())
+    }
+}
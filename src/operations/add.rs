@@ -1,26 +1,23 @@
 use crate::{
     backward::Backward,
+    dispatch_numeric,
+    errors::Result,
+    forward::{sum_tangents, Forward},
+    kernels::elementwise::map,
     objects::Tensor,
-    utils::{broadcast_to_same_dim, new_tensor_with_graph},
-    DTYPE,
+    utils::{broadcast_to_same_dim, in_place, new_tensor_with_graph, promote_types, Operand},
 };
 use pyo3::prelude::*;
 use std::ops::Add;
 
-impl Add for Tensor {
-    type Output = Tensor;
+pub fn add(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    let (lhs, rhs) = broadcast_to_same_dim(lhs, rhs)?;
 
-    fn add(self, rhs: Tensor) -> Tensor {
-        let (lhs, rhs) = broadcast_to_same_dim(self, rhs);
+    return dispatch_numeric!(lhs.dtype, T => {
+        let data: Vec<T> = map([&lhs, &rhs], |[a, b]: [T; 2]| a + b);
 
-        let data: Vec<DTYPE> = lhs
-            .get_data_ref()
-            .iter()
-            .zip(rhs.get_data_ref().iter())
-            .map(|(a, b)| a + b)
-            .collect();
-
-        return new_tensor_with_graph(
+        new_tensor_with_graph(
             lhs.get_shape(),
             data,
             lhs.get_requires_grad() || rhs.get_requires_grad(),
@@ -28,7 +25,18 @@ impl Add for Tensor {
                 lhs: lhs.clone(),
                 rhs: rhs.clone(),
             },
-        );
+        )
+    });
+}
+
+/// `+` for the operations, on tensors whose shapes broadcast and that are
+/// not both bool, e.g. a gradient and a tensor saved in the forward pass. It
+/// panics otherwise, so operands coming from the user go through `add`.
+impl Add for Tensor {
+    type Output = Tensor;
+
+    fn add(self, rhs: Tensor) -> Tensor {
+        add(self, rhs).expect("Operands of `+` must broadcast and not both be bool")
     }
 }
 
@@ -38,16 +46,32 @@ pub struct AddOperation {
 }
 
 impl Backward for AddOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-        self.lhs.do_backward(Some(grad.clone()), None);
-        self.rhs.do_backward(Some(grad.clone()), None);
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.lhs.clone(), self.rhs.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(grad.clone()), Some(grad)]
+    }
+}
+
+impl Forward for AddOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        sum_tangents(tangents.to_vec())
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn __add__(&self, other: Tensor) -> Tensor {
-        self.clone() + other
+    pub fn __add__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(add(self.clone(), other.into_tensor(self))?)
+    }
+
+    pub fn __radd__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(add(other.into_tensor(self), self.clone())?)
+    }
+
+    pub fn __iadd__(&self, other: Operand) -> PyResult<()> {
+        Ok(in_place(self, add(self.clone(), other.into_tensor(self))?)?)
     }
 }
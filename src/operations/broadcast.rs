@@ -1,51 +1,118 @@
 use crate::{
     backward::Backward,
-    objects::Tensor,
-    utils::{new_tensor_simple, new_tensor_with_graph},
+    errors::{AutogradError, Result},
+    forward::Forward,
+    objects::{strided_index_map, strides, Tensor},
+    operations::{reduce::sum, view::reshape},
+    utils::new_view_with_graph,
 };
 use pyo3::prelude::*;
 
-pub fn broadcast(t: Tensor, shape: Vec<usize>) -> Tensor {
-    if t.get_shape() == vec![1] {
-        return new_tensor_with_graph(
-            shape.clone(),
-            vec![t.get_data_ref()[0]; shape.iter().product()],
-            t.get_requires_grad(),
-            BroadcastOperation {
-                t: t.clone(),
-                shape: shape,
-            },
-        );
+/// Computes the shape two tensors broadcast to, following NumPy rules:
+/// shapes are aligned on their last dimension, and dimensions must either
+/// match or be 1.
+pub fn broadcast_shapes(lhs: &[usize], rhs: &[usize]) -> Result<Vec<usize>> {
+    let ndim = lhs.len().max(rhs.len());
+    let mut shape = vec![0; ndim];
+    for i in 0..ndim {
+        let l = if i < lhs.len() {
+            lhs[lhs.len() - 1 - i]
+        } else {
+            1
+        };
+        let r = if i < rhs.len() {
+            rhs[rhs.len() - 1 - i]
+        } else {
+            1
+        };
+        if l != r && l != 1 && r != 1 {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Cannot broadcast tensors with shapes {:?} and {:?}",
+                lhs, rhs
+            )));
+        }
+        shape[ndim - 1 - i] = l.max(r);
     }
+    Ok(shape)
+}
 
-    if shape.len() != t.get_shape().len() + 1 {
-        panic!(
-            "Cannot broadcast tensor with shape {:?} to shape {:?}",
-            t.get_shape(),
-            shape
-        );
+/// Strides that read a tensor of shape `shape` and strides `strides` as a
+/// tensor of shape `target`: broadcast dimensions don't move in the source.
+fn broadcast_strides(shape: &[usize], strides: &[usize], target: &[usize]) -> Vec<usize> {
+    let leading = target.len() - shape.len();
+    let mut target_strides = vec![0; target.len()];
+    for i in 0..shape.len() {
+        if shape[i] != 1 {
+            target_strides[leading + i] = strides[i];
+        }
     }
+    target_strides
+}
 
-    for (dim1, dim2) in t.get_shape().iter().zip(shape[1..].iter()) {
-        if *dim1 != *dim2 {
-            panic!(
-                "Cannot broadcast tensor with shape {:?} to shape {:?}",
-                t.get_shape(),
-                shape
-            );
-        }
+/// For every element of a tensor of shape `target`, computes the index of the
+/// element of a tensor of shape `shape` it is broadcast from.
+pub fn broadcast_index_map(shape: &[usize], target: &[usize]) -> Vec<usize> {
+    strided_index_map(
+        target,
+        &broadcast_strides(shape, &strides(shape), target),
+        0,
+    )
+}
+
+/// Broadcasts `t` to `shape` without copying: the result is a view where
+/// broadcast dimensions have a stride of 0.
+pub fn broadcast(t: Tensor, shape: Vec<usize>) -> Result<Tensor> {
+    let t_shape = t.get_shape();
+    if t_shape.len() > shape.len()
+        || t_shape
+            .iter()
+            .rev()
+            .zip(shape.iter().rev())
+            .any(|(dim1, dim2)| *dim1 != *dim2 && *dim1 != 1)
+    {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Cannot broadcast tensor with shape {:?} to shape {:?}",
+            t_shape, shape
+        )));
     }
 
-    let batch_size = shape[0];
-    return new_tensor_with_graph(
+    let strides = broadcast_strides(&t_shape, &t.get_strides(), &shape);
+    return Ok(new_view_with_graph(
+        &t,
         shape.clone(),
-        t.get_data_ref().repeat(batch_size),
-        t.get_requires_grad(),
+        strides,
+        t.get_offset(),
         BroadcastOperation {
             t: t.clone(),
-            shape: shape,
+            shape,
         },
-    );
+    ));
+}
+
+/// Like `broadcast`, but a size of -1 keeps the corresponding dimension of `t`.
+pub fn expand(t: Tensor, sizes: Vec<isize>) -> Result<Tensor> {
+    let t_shape = t.get_shape();
+    if sizes.len() < t_shape.len() {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Cannot expand tensor with shape {:?} to {:?}",
+            t_shape, sizes
+        )));
+    }
+    let leading = sizes.len() - t_shape.len();
+    let mut shape = Vec::with_capacity(sizes.len());
+    for (i, &size) in sizes.iter().enumerate() {
+        if size >= 0 {
+            shape.push(size as usize);
+        } else if size == -1 && i >= leading {
+            shape.push(t_shape[i - leading]);
+        } else {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Cannot expand tensor with shape {:?} to {:?}",
+                t_shape, sizes
+            )));
+        }
+    }
+    broadcast(t, shape)
 }
 
 pub struct BroadcastOperation {
@@ -54,29 +121,43 @@ pub struct BroadcastOperation {
 }
 
 impl Backward for BroadcastOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
 
-        if self.t.get_shape() == vec![1] {
-            return self.t.do_backward(Some(grad.reduce_sum()), None);
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* Sum the gradient over every broadcast dimension */
+        let t_shape = self.t.get_shape();
+        let leading = self.shape.len() - t_shape.len();
+        let dims: Vec<isize> = (0..self.shape.len())
+            .filter(|&d| d < leading || (t_shape[d - leading] == 1 && self.shape[d] != 1))
+            .map(|d| d as isize)
+            .collect();
+        let summed_grad = if dims.is_empty() {
+            grad
         } else {
-            let mut summed_grad = vec![0.0; self.t.get_data_ref().len()];
-            for i in 0..self.shape[0] {
-                for j in 0..self.t.get_data_ref().len() {
-                    summed_grad[j] += grad.get_data_ref()[i * self.t.get_data_ref().len() + j];
-                }
-            }
-            return self.t.do_backward(
-                Some(new_tensor_simple(self.t.get_shape(), summed_grad)),
-                None,
-            );
-        }
+            sum(grad, Some(dims), true).unwrap()
+        };
+        let t_shape = t_shape.iter().map(|&d| d as isize).collect();
+        return vec![Some(reshape(summed_grad, t_shape).unwrap())];
+    }
+}
+
+impl Forward for BroadcastOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        tangents[0]
+            .clone()
+            .map(|t| broadcast(t, self.shape.clone()).unwrap())
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn broadcast(&self, shape: Vec<usize>) -> Tensor {
-        broadcast(self.clone(), shape)
+    pub fn broadcast(&self, shape: Vec<usize>) -> PyResult<Tensor> {
+        Ok(broadcast(self.clone(), shape)?)
+    }
+
+    pub fn expand(&self, sizes: Vec<isize>) -> PyResult<Tensor> {
+        Ok(expand(self.clone(), sizes)?)
     }
 }
@@ -0,0 +1,55 @@
+use crate::{
+    backward::Backward,
+    dispatch_all,
+    dtype::{DType, Element},
+    forward::Forward,
+    kernels::elementwise::map,
+    objects::Tensor,
+    utils::new_tensor_with_graph,
+};
+use pyo3::prelude::*;
+
+/// Converts the elements of `t` to `dtype`. Returns `t` if it already has this type.
+/// The result only requires a gradient if both types are floating point.
+pub fn to(t: Tensor, dtype: DType) -> Tensor {
+    if t.dtype == dtype {
+        return t;
+    }
+    let requires_grad = t.get_requires_grad() && dtype.is_floating_point();
+    return dispatch_all!(t.dtype, S => dispatch_all!(dtype, T => new_tensor_with_graph(
+        t.get_shape(),
+        map::<S, T, 1>([&t], |[x]| x.cast::<T>()),
+        requires_grad,
+        CastOperation { t: t.clone() },
+    )));
+}
+
+pub struct CastOperation {
+    t: Tensor,
+}
+
+impl Backward for CastOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(to(grad, self.t.dtype))]
+    }
+}
+
+impl Forward for CastOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        if !output.dtype.is_floating_point() {
+            return None;
+        }
+        tangents[0].clone().map(|t| to(t, output.dtype))
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn to(&self, dtype: DType) -> Tensor {
+        to(self.clone(), dtype)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    backward::Backward,
+    dispatch_float,
+    dtype::DType,
+    errors::Result,
+    forward::{sum_tangents, Forward},
+    kernels::elementwise::map,
+    objects::Tensor,
+    operations::cast::to,
+    utils::{broadcast_to_same_dim, in_place, new_tensor_with_graph, promote_types, Operand},
+};
+use pyo3::prelude::*;
+use std::ops::Div;
+
+/// True division: integer tensors are divided as float32.
+pub fn div(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    let (lhs, rhs) = if lhs.dtype.is_floating_point() {
+        (lhs, rhs)
+    } else {
+        (to(lhs, DType::Float32), to(rhs, DType::Float32))
+    };
+    let (lhs, rhs) = broadcast_to_same_dim(lhs, rhs)?;
+
+    return dispatch_float!(lhs.dtype, T => {
+        let data: Vec<T> = map([&lhs, &rhs], |[a, b]: [T; 2]| a / b);
+
+        new_tensor_with_graph(
+            lhs.get_shape(),
+            data,
+            lhs.get_requires_grad() || rhs.get_requires_grad(),
+            DivOperation {
+                lhs: lhs.clone(),
+                rhs: rhs.clone(),
+            },
+        )
+    });
+}
+
+/// Panics where `div` fails, see `+`.
+impl Div for Tensor {
+    type Output = Tensor;
+
+    fn div(self, rhs: Tensor) -> Tensor {
+        div(self, rhs).expect("Operands of `/` must broadcast and not both be bool")
+    }
+}
+
+pub struct DivOperation {
+    lhs: Tensor,
+    rhs: Tensor,
+}
+
+impl Backward for DivOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.lhs.clone(), self.rhs.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        /* d(a / b)/db = -a / b^2 = -(a / b) / b */
+        vec![
+            Some(grad.clone() / self.rhs.clone()),
+            Some(-(grad * output) / self.rhs.clone()),
+        ]
+    }
+}
+
+impl Forward for DivOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        sum_tangents(vec![
+            tangents[0].clone().map(|t| t / self.rhs.clone()),
+            tangents[1]
+                .clone()
+                .map(|t| -(t * output.clone()) / self.rhs.clone()),
+        ])
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn __truediv__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(div(self.clone(), other.into_tensor(self))?)
+    }
+
+    pub fn __rtruediv__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(div(other.into_tensor(self), self.clone())?)
+    }
+
+    pub fn __itruediv__(&self, other: Operand) -> PyResult<()> {
+        Ok(in_place(self, div(self.clone(), other.into_tensor(self))?)?)
+    }
+}
@@ -0,0 +1,473 @@
+use crate::{
+    backward::Backward,
+    dispatch_all,
+    dtype::{DType, Element},
+    errors::{AutogradError, Result},
+    forward::Forward,
+    grad_mode::is_grad_enabled,
+    objects::{strided_index_map, strides, Tensor},
+    operations::{
+        broadcast::{broadcast, broadcast_index_map, broadcast_shapes},
+        cast::to,
+        reduce::{scatter, take},
+        view::resolve_slice,
+    },
+    utils::{new_tensor_simple, new_view_with_graph},
+};
+use pyo3::{
+    exceptions::PyTypeError,
+    prelude::*,
+    types::{PyBool, PyEllipsis, PyList, PySlice, PyTuple},
+};
+
+/* Indexing follows NumPy. Integers, slices, ellipsis and None select a view of
+ * the tensor, then integer and bool tensors gather elements of this view. */
+
+/// One element of an index, as accepted from Python.
+pub enum Index {
+    Int(isize),
+    Slice(Option<isize>, Option<isize>, Option<isize>),
+    Ellipsis,
+    NewAxis,
+    Tensor(Tensor),
+}
+
+impl<'py> FromPyObject<'py> for Index {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if ob.is_none() {
+            return Ok(Index::NewAxis);
+        }
+        if ob.is_instance_of::<PyEllipsis>() {
+            return Ok(Index::Ellipsis);
+        }
+        if let Ok(slice) = ob.downcast::<PySlice>() {
+            return Ok(Index::Slice(
+                slice.getattr("start")?.extract()?,
+                slice.getattr("stop")?.extract()?,
+                slice.getattr("step")?.extract()?,
+            ));
+        }
+        if let Ok(t) = ob.extract::<Tensor>() {
+            return Ok(Index::Tensor(t));
+        }
+        if let Ok(list) = ob.downcast::<PyList>() {
+            /* Lists are indices, or masks if they hold bools */
+            if !list.is_empty() {
+                if let Ok(mask) = list.extract::<Vec<bool>>() {
+                    return Ok(Index::Tensor(new_tensor_simple(vec![mask.len()], mask)));
+                }
+            }
+            let indices = list.extract::<Vec<i64>>()?;
+            return Ok(Index::Tensor(new_tensor_simple(
+                vec![indices.len()],
+                indices,
+            )));
+        }
+        if !ob.is_instance_of::<PyBool>() {
+            if let Ok(i) = ob.extract::<isize>() {
+                return Ok(Index::Int(i));
+            }
+        }
+        Err(PyTypeError::new_err(format!(
+            "Invalid index of type {}",
+            ob.get_type().name()?
+        )))
+    }
+}
+
+/// Splits a Python key into the elements of the index.
+fn parse_key(key: &Bound<'_, PyAny>) -> PyResult<Vec<Index>> {
+    match key.downcast::<PyTuple>() {
+        Ok(tuple) => tuple.iter().map(|item| item.extract()).collect(),
+        Err(_) => Ok(vec![key.extract()?]),
+    }
+}
+
+/// An element of an index resolved against the shape of the tensor.
+enum Selection {
+    /// Removes the dimension, keeping the element at this position.
+    Int(usize),
+    /// Keeps `len` elements starting at `start`, every `step`.
+    Slice {
+        start: usize,
+        len: usize,
+        step: usize,
+    },
+    /// Inserts a dimension of size 1.
+    NewAxis,
+    /// Keeps the dimension to gather from it. The indices are broadcast with
+    /// the indices of the other gathered dimensions.
+    Gather {
+        indices: Vec<i64>,
+        shape: Vec<usize>,
+    },
+}
+
+/// Resolves each element of the index against the dimension it applies to.
+fn resolve(shape: &[usize], indices: Vec<Index>) -> Result<Vec<Selection>> {
+    let consumed: usize = indices
+        .iter()
+        .map(|index| match index {
+            Index::Int(_) | Index::Slice(..) => 1,
+            Index::Tensor(t) if t.dtype == DType::Bool => t.get_shape().len(),
+            Index::Tensor(_) => 1,
+            Index::Ellipsis | Index::NewAxis => 0,
+        })
+        .sum();
+    if consumed > shape.len() {
+        return Err(AutogradError::InvalidIndex(format!(
+            "Too many indices for tensor with shape {:?}",
+            shape
+        )));
+    }
+    if indices
+        .iter()
+        .filter(|index| matches!(index, Index::Ellipsis))
+        .count()
+        > 1
+    {
+        return Err(AutogradError::InvalidIndex(
+            "An index can only have a single ellipsis".to_string(),
+        ));
+    }
+
+    let full = |size: usize| Selection::Slice {
+        start: 0,
+        len: size,
+        step: 1,
+    };
+    let mut selections = Vec::with_capacity(shape.len());
+    let mut dim = 0;
+    for index in indices {
+        match index {
+            Index::Int(i) => {
+                let size = shape[dim];
+                let j = if i < 0 { i + size as isize } else { i };
+                if j < 0 || j >= size as isize {
+                    return Err(AutogradError::IndexOutOfRange { index: i, size });
+                }
+                selections.push(Selection::Int(j as usize));
+                dim += 1;
+            }
+            Index::Slice(start, end, step) => {
+                let step = step.unwrap_or(1);
+                if step < 0 {
+                    return Err(AutogradError::InvalidIndex(
+                        "Negative slice steps are not supported, as strides are unsigned. \
+                         Reverse a dimension with an index tensor instead"
+                            .to_string(),
+                    ));
+                }
+                let (start, len) = resolve_slice(shape[dim], start, end, step as usize)?;
+                selections.push(Selection::Slice {
+                    start,
+                    len,
+                    step: step as usize,
+                });
+                dim += 1;
+            }
+            Index::NewAxis => selections.push(Selection::NewAxis),
+            Index::Ellipsis => {
+                for _ in 0..shape.len() - consumed {
+                    selections.push(full(shape[dim]));
+                    dim += 1;
+                }
+            }
+            Index::Tensor(t) => match t.dtype {
+                DType::Int64 => {
+                    selections.push(Selection::Gather {
+                        indices: t.get_data_ref::<i64>().to_vec(),
+                        shape: t.get_shape(),
+                    });
+                    dim += 1;
+                }
+                DType::Bool => {
+                    let mask_shape = t.get_shape();
+                    if mask_shape[..] != shape[dim..dim + mask_shape.len()] {
+                        return Err(AutogradError::InvalidIndex(format!(
+                            "Mask with shape {:?} does not match dimensions {:?} of the tensor",
+                            mask_shape,
+                            &shape[dim..dim + mask_shape.len()]
+                        )));
+                    }
+                    for indices in nonzero(&t) {
+                        selections.push(Selection::Gather {
+                            shape: vec![indices.len()],
+                            indices,
+                        });
+                    }
+                    dim += mask_shape.len();
+                }
+                dtype => {
+                    return Err(AutogradError::DTypeMismatch(format!(
+                        "Tensors used as indices must be int64 or bool, got {}",
+                        dtype.name()
+                    )))
+                }
+            },
+        }
+    }
+    while dim < shape.len() {
+        selections.push(full(shape[dim]));
+        dim += 1;
+    }
+    Ok(selections)
+}
+
+/// For every dimension of a bool mask, the indices of its true elements.
+fn nonzero(mask: &Tensor) -> Vec<Vec<i64>> {
+    let shape = mask.get_shape();
+    let mut indices = vec![Vec::new(); shape.len()];
+    for (mut i, _) in mask
+        .get_data_ref::<bool>()
+        .iter()
+        .enumerate()
+        .filter(|(_, &x)| x)
+    {
+        for d in (0..shape.len()).rev() {
+            indices[d].push((i % shape[d]) as i64);
+            i /= shape[d];
+        }
+    }
+    indices
+}
+
+/// Applies the selections to a layout, returning the shape, strides and offset
+/// of the view. Gathered dimensions are kept whole.
+fn select_layout(
+    selections: &[Selection],
+    shape: &[usize],
+    strides: &[usize],
+    mut offset: usize,
+) -> (Vec<usize>, Vec<usize>, usize) {
+    let mut view_shape = Vec::with_capacity(selections.len());
+    let mut view_strides = Vec::with_capacity(selections.len());
+    let mut dim = 0;
+    for selection in selections {
+        match selection {
+            Selection::Int(i) => {
+                offset += i * strides[dim];
+                dim += 1;
+            }
+            Selection::Slice { start, len, step } => {
+                offset += start * strides[dim];
+                view_shape.push(*len);
+                view_strides.push(strides[dim] * step);
+                dim += 1;
+            }
+            Selection::NewAxis => {
+                view_shape.push(1);
+                view_strides.push(0);
+            }
+            Selection::Gather { .. } => {
+                view_shape.push(shape[dim]);
+                view_strides.push(strides[dim]);
+                dim += 1;
+            }
+        }
+    }
+    if view_shape.is_empty() {
+        /* Scalars have shape [1] */
+        return (vec![1], vec![1], offset);
+    }
+    (view_shape, view_strides, offset)
+}
+
+/// For the gathered dimensions of the view, computes the shape of the result
+/// and the row-major index in the view of each of its elements. Following
+/// NumPy, integers count as gathered indices when there are some, and the
+/// broadcast indices replace the gathered dimensions if those are next to each
+/// other in the index, and come first otherwise.
+fn gather_args(
+    selections: &[Selection],
+    view_shape: &[usize],
+) -> Result<Option<(Vec<usize>, Vec<usize>)>> {
+    let mut gathers = Vec::new();
+    /* The position in the index and the dimension of the view of every
+     * integer and gathered index */
+    let mut advanced = Vec::new();
+    let mut dim = 0;
+    for (position, selection) in selections.iter().enumerate() {
+        match selection {
+            Selection::Int(_) => advanced.push((position, dim)),
+            Selection::Slice { .. } | Selection::NewAxis => dim += 1,
+            Selection::Gather { indices, shape } => {
+                gathers.push((dim, indices, shape));
+                advanced.push((position, dim));
+                dim += 1;
+            }
+        }
+    }
+    if gathers.is_empty() {
+        return Ok(None);
+    }
+
+    let mut index_shape = gathers[0].2.clone();
+    for (_, _, shape) in gathers.iter().skip(1) {
+        index_shape = broadcast_shapes(&index_shape, shape)?;
+    }
+    let view_strides = strides(view_shape);
+    let mut index_offsets = vec![0; index_shape.iter().product()];
+    for (dim, indices, shape) in gathers.iter() {
+        let size = view_shape[*dim];
+        let map = broadcast_index_map(shape, &index_shape);
+        for (offset, j) in index_offsets.iter_mut().zip(map) {
+            let i = indices[j];
+            let k = if i < 0 { i + size as i64 } else { i };
+            if k < 0 || k >= size as i64 {
+                return Err(AutogradError::IndexOutOfRange {
+                    index: i as isize,
+                    size,
+                });
+            }
+            *offset += k as usize * view_strides[*dim];
+        }
+    }
+
+    let gather_dims: Vec<usize> = gathers.iter().map(|(dim, _, _)| *dim).collect();
+    let split = if advanced.windows(2).all(|w| w[1].0 == w[0].0 + 1) {
+        advanced[0].1
+    } else {
+        0
+    };
+    let (before, after): (Vec<usize>, Vec<usize>) = (0..view_shape.len())
+        .filter(|d| !gather_dims.contains(d))
+        .partition(|&d| d < split);
+    let offsets = |dims: &[usize]| {
+        let shape: Vec<usize> = dims.iter().map(|&d| view_shape[d]).collect();
+        let dim_strides: Vec<usize> = dims.iter().map(|&d| view_strides[d]).collect();
+        (strided_index_map(&shape, &dim_strides, 0), shape)
+    };
+    let (before_offsets, before_shape) = offsets(&before);
+    let (after_offsets, after_shape) = offsets(&after);
+
+    let mut args =
+        Vec::with_capacity(before_offsets.len() * index_offsets.len() * after_offsets.len());
+    for &b in before_offsets.iter() {
+        for &i in index_offsets.iter() {
+            for &a in after_offsets.iter() {
+                args.push(b + i + a);
+            }
+        }
+    }
+    let shape = [before_shape, index_shape, after_shape].concat();
+    Ok(Some((shape, args)))
+}
+
+/// Selects elements of `t` like NumPy. The result is a view of `t` unless the
+/// index contains tensors.
+pub fn index(t: Tensor, indices: Vec<Index>) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let selections = resolve(&shape, indices)?;
+    let (view_shape, view_strides, offset) =
+        select_layout(&selections, &shape, &t.get_strides(), t.get_offset());
+    /* The same view of a contiguous tensor gives where the gradient goes */
+    let (_, grad_strides, grad_offset) = select_layout(&selections, &shape, &strides(&shape), 0);
+
+    let view = new_view_with_graph(
+        &t,
+        view_shape,
+        view_strides,
+        offset,
+        IndexOperation {
+            t: t.clone(),
+            strides: grad_strides,
+            offset: grad_offset,
+        },
+    );
+    return match gather_args(&selections, &view.get_shape())? {
+        None => Ok(view),
+        Some((shape, args)) => Ok(take(&view, shape, args)),
+    };
+}
+
+pub struct IndexOperation {
+    t: Tensor,
+    strides: Vec<usize>,
+    offset: usize,
+}
+
+impl Backward for IndexOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* Scatter the gradient in a zero gradient with the shape of the input */
+        let indices = strided_index_map(&grad.get_shape(), &self.strides, self.offset);
+        vec![Some(scatter(&grad, self.t.get_shape(), indices).unwrap())]
+    }
+}
+
+impl Forward for IndexOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let indices = strided_index_map(&output.get_shape(), &self.strides, self.offset);
+        tangents[0]
+            .as_ref()
+            .map(|t| take(t, output.get_shape(), indices))
+    }
+}
+
+/// Writes `value`, broadcast to the shape of `t[indices]`, into the storage of
+/// `t`. Other views of the storage see the new values, and operations that
+/// saved one of them for backward fail if they run afterwards. Tensors that
+/// require grad can only be modified with gradients disabled, e.g. to update
+/// weights.
+pub fn index_put(t: &Tensor, indices: Vec<Index>, value: Tensor) -> Result<()> {
+    if t.get_requires_grad() && is_grad_enabled() {
+        return Err(AutogradError::InPlaceOnGrad(
+            "Cannot assign to a tensor that requires grad".to_string(),
+        ));
+    }
+    let shape = t.get_shape();
+    let expanded = shape
+        .iter()
+        .zip(t.get_strides())
+        .any(|(&size, stride)| size > 1 && stride == 0);
+    if expanded {
+        return Err(AutogradError::InvalidArgument(
+            "Cannot assign to an expanded tensor, several of its elements share the \
+             same memory. Assign to a copy of it instead"
+                .to_string(),
+        ));
+    }
+    let selections = resolve(&shape, indices)?;
+    let (view_shape, view_strides, offset) =
+        select_layout(&selections, &shape, &t.get_strides(), t.get_offset());
+    let positions = strided_index_map(&view_shape, &view_strides, offset);
+    let (shape, positions) = match gather_args(&selections, &view_shape)? {
+        None => (view_shape, positions),
+        Some((shape, args)) => (shape, args.iter().map(|&j| positions[j]).collect()),
+    };
+    let value = broadcast(to(value, t.dtype), shape)?;
+
+    dispatch_all!(t.dtype, T => {
+        /* Copy the value first, it can share the storage of t */
+        let data = value.get_data_ref::<T>().to_vec();
+        let mut storage = t.storage.write().unwrap();
+        let storage = T::from_buffer_mut(&mut storage);
+        for (i, x) in positions.into_iter().zip(data) {
+            storage[i] = x;
+        }
+    });
+    t.bump_version();
+    Ok(())
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn __getitem__(&self, key: &Bound<'_, PyAny>) -> PyResult<Tensor> {
+        Ok(index(self.clone(), parse_key(key)?)?)
+    }
+
+    pub fn __setitem__(&self, key: &Bound<'_, PyAny>, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = if let Ok(t) = value.extract::<Tensor>() {
+            t
+        } else if let Ok(x) = value.extract::<i64>() {
+            new_tensor_simple(vec![1], vec![x])
+        } else {
+            new_tensor_simple(vec![1], vec![value.extract::<f64>()?])
+        };
+        Ok(index_put(self, parse_key(key)?, value)?)
+    }
+}
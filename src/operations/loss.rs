@@ -0,0 +1,466 @@
+use crate::{
+    backward::Backward,
+    dispatch_float,
+    dtype::{DType, Numeric},
+    errors::{AutogradError, Result},
+    forward::Forward,
+    objects::Tensor,
+    operations::{
+        add::add,
+        cast::to,
+        div::div,
+        mul::mul,
+        neg::neg,
+        reduce::{mean, scatter, sum, take},
+        softmax::log_softmax,
+        sub::sub,
+        unary::{abs, exp, softplus, unary, Unary},
+        view::{reshape, unsqueeze},
+    },
+    utils::{new_tensor_simple, new_tensor_with_graph, Operand},
+};
+use pyo3::prelude::*;
+
+/* Loss functions. Most of them are compositions of differentiable operations,
+ * only the selection of the target classes has its own backward operation. */
+
+/// How the elementwise losses are combined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reduction {
+    None,
+    Mean,
+    Sum,
+    /// The sum divided by the size of the first dimension, only for `kl_div`.
+    BatchMean,
+}
+
+impl TryFrom<&str> for Reduction {
+    type Error = AutogradError;
+
+    fn try_from(reduction: &str) -> Result<Self> {
+        match reduction {
+            "none" => Ok(Reduction::None),
+            "mean" => Ok(Reduction::Mean),
+            "sum" => Ok(Reduction::Sum),
+            "batchmean" => Ok(Reduction::BatchMean),
+            _ => Err(AutogradError::InvalidArgument(format!(
+                "Invalid reduction '{}', expected 'none', 'mean' or 'sum'",
+                reduction
+            ))),
+        }
+    }
+}
+
+fn reduce(loss: Tensor, reduction: Reduction) -> Result<Tensor> {
+    match reduction {
+        Reduction::None => Ok(loss),
+        Reduction::Mean => mean(loss, None, false),
+        Reduction::Sum => sum(loss, None, false),
+        Reduction::BatchMean => {
+            let batch_size = loss.get_shape()[0] as f64;
+            div(sum(loss.clone(), None, false)?, scalar(&loss, batch_size))
+        }
+    }
+}
+
+/// A tensor holding `x`, with the type of `t`.
+fn scalar(t: &Tensor, x: f64) -> Tensor {
+    Operand::Float(x).into_tensor(t)
+}
+
+fn check_float(name: &str, t: &Tensor) -> Result<()> {
+    if !t.dtype.is_floating_point() {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "The input of {} must be floating point, got {}",
+            name,
+            t.dtype.name()
+        )));
+    }
+    Ok(())
+}
+
+/* Regression losses */
+
+pub fn mse_loss(input: Tensor, target: Tensor, reduction: Reduction) -> Result<Tensor> {
+    let diff = sub(input, target)?;
+    return reduce(mul(diff.clone(), diff)?, reduction);
+}
+
+pub fn l1_loss(input: Tensor, target: Tensor, reduction: Reduction) -> Result<Tensor> {
+    return reduce(abs(sub(input, target)?)?, reduction);
+}
+
+/// Quadratic for errors below `delta` and linear beyond.
+pub fn huber_loss(
+    input: Tensor,
+    target: Tensor,
+    reduction: Reduction,
+    delta: f64,
+) -> Result<Tensor> {
+    let loss = unary(sub(input, target)?, Unary::Huber { delta })?;
+    return reduce(loss, reduction);
+}
+
+/// The Huber loss divided by `beta`, which tends to the L1 loss as `beta` goes to 0.
+pub fn smooth_l1_loss(
+    input: Tensor,
+    target: Tensor,
+    reduction: Reduction,
+    beta: f64,
+) -> Result<Tensor> {
+    if beta == 0.0 {
+        return l1_loss(input, target, reduction);
+    }
+    let loss = unary(sub(input, target)?, Unary::Huber { delta: beta })?;
+    return reduce(div(loss.clone(), scalar(&loss, beta))?, reduction);
+}
+
+/* Classification losses */
+
+/// The negative log-likelihood of the target classes. `input` holds
+/// log-probabilities with the classes along dimension 1, and `target` the int64
+/// class of each sample. Samples whose target is `ignore_index` don't
+/// contribute, and the mean is weighted by the class weights of the samples.
+pub fn nll_loss(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    ignore_index: i64,
+    reduction: Reduction,
+) -> Result<Tensor> {
+    check_float("nll_loss", &input)?;
+    if target.dtype != DType::Int64 {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "The target of nll_loss must hold int64 class indices, got {}",
+            target.dtype.name()
+        )));
+    }
+    /* A single sample has no batch dimension */
+    let single = input.get_shape().len() == 1;
+    let input = if single { unsqueeze(input, 0)? } else { input };
+    let shape = input.get_shape();
+    let classes = shape[1];
+    let inner: usize = shape[2..].iter().product();
+    let target_shape = [&shape[..1], &shape[2..]].concat();
+    if target.get_shape() != target_shape && !(single && target.get_shape().is_empty()) {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Target shape {:?} does not match input shape {:?}",
+            target.get_shape(),
+            shape
+        )));
+    }
+    if let Some(ref weight) = weight {
+        if weight.get_shape() != vec![classes] {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Expected a weight for each of the {} classes, got shape {:?}",
+                classes,
+                weight.get_shape()
+            )));
+        }
+    }
+
+    /* The position in the input of the log-probability of each target */
+    let targets = target.get_data_ref::<i64>().to_vec();
+    let mut positions = Vec::with_capacity(targets.len());
+    for (j, &k) in targets.iter().enumerate() {
+        if k == ignore_index {
+            positions.push(None);
+        } else if k < 0 || k as usize >= classes {
+            return Err(AutogradError::IndexOutOfRange {
+                index: k as isize,
+                size: classes,
+            });
+        } else {
+            positions.push(Some(
+                ((j / inner) * classes + k as usize) * inner + j % inner,
+            ));
+        }
+    }
+
+    let weights = dispatch_float!(input.dtype, T => {
+        let class_weights = weight.map(|weight| to(weight, input.dtype).get_data_ref::<T>().to_vec());
+        let weights: Vec<T> = targets
+            .iter()
+            .zip(positions.iter())
+            .map(|(&k, position)| match (position, &class_weights) {
+                (None, _) => T::ZERO,
+                (Some(_), None) => T::ONE,
+                (Some(_), Some(class_weights)) => class_weights[k as usize],
+            })
+            .collect();
+        new_tensor_simple(target.get_shape(), weights)
+    })?;
+    let loss = dispatch_float!(input.dtype, T => {
+        let x = input.get_data_ref::<T>();
+        let w = weights.get_data_ref::<T>();
+        let data: Vec<T> = positions
+            .iter()
+            .zip(w.iter())
+            .map(|(position, &w)| position.map_or(T::ZERO, |p| -w * x[p]))
+            .collect();
+        new_tensor_with_graph(
+            target.get_shape(),
+            data,
+            input.get_requires_grad(),
+            NllLossOperation {
+                input: input.clone(),
+                positions,
+                weights: weights.clone(),
+            },
+        )
+    })?;
+
+    return match reduction {
+        Reduction::Mean => div(sum(loss, None, false)?, sum(weights, None, false)?),
+        _ => reduce(loss, reduction),
+    };
+}
+
+pub struct NllLossOperation {
+    input: Tensor,
+    positions: Vec<Option<usize>>,
+    weights: Tensor,
+}
+
+impl Backward for NllLossOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.input.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* Scatter -w * g to the selected log-probabilities, skipping ignored targets */
+        let (targets, positions): (Vec<usize>, Vec<usize>) = self
+            .positions
+            .iter()
+            .enumerate()
+            .filter_map(|(j, position)| position.map(|p| (j, p)))
+            .unzip();
+        let weighted = -(grad * self.weights.clone());
+        let selected = take(&weighted, vec![targets.len()], targets);
+        vec![Some(
+            scatter(&selected, self.input.get_shape(), positions).unwrap(),
+        )]
+    }
+}
+
+impl Forward for NllLossOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        /* Ignored targets have a zero weight, any position does for them */
+        let positions = self
+            .positions
+            .iter()
+            .map(|position| position.unwrap_or(0))
+            .collect();
+        let t = tangents[0].as_ref()?;
+        Some(-(self.weights.clone() * take(t, self.weights.get_shape(), positions)))
+    }
+}
+
+/// Log-softmax over the classes followed by the negative log-likelihood.
+/// `target` holds either the int64 class of each sample, or the probability
+/// of each class with the shape of `input`.
+pub fn cross_entropy(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    ignore_index: i64,
+    reduction: Reduction,
+) -> Result<Tensor> {
+    check_float("cross_entropy", &input)?;
+    let class_dim = if input.get_shape().len() == 1 { 0 } else { 1 };
+    let log_probabilities = log_softmax(input, class_dim)?;
+    if !target.dtype.is_floating_point() {
+        return nll_loss(log_probabilities, target, weight, ignore_index, reduction);
+    }
+
+    /* Probabilities: -sum(weight * target * log_softmax) over the classes */
+    let mut loss = mul(target, log_probabilities)?;
+    if let Some(weight) = weight {
+        let mut weight_shape = vec![1; loss.get_shape().len()];
+        weight_shape[class_dim as usize] = weight.get_shape().iter().product::<usize>();
+        let weight = reshape(weight, weight_shape.iter().map(|&d| d as isize).collect())?;
+        loss = mul(loss, weight)?;
+    }
+    let loss = neg(sum(loss, Some(vec![class_dim]), false)?)?;
+    return reduce(loss, reduction);
+}
+
+/// Binary cross-entropy of `sigmoid(input)`, computed from the logits without
+/// overflowing: `(1 - t) * x + (1 + (p - 1) * t) * log(1 + exp(-x))`.
+pub fn binary_cross_entropy_with_logits(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    pos_weight: Option<Tensor>,
+    reduction: Reduction,
+) -> Result<Tensor> {
+    check_float("binary_cross_entropy_with_logits", &input)?;
+    let one = scalar(&input, 1.0);
+    let log_sigmoid = softplus(neg(input.clone())?, 1.0, 20.0)?;
+    let log_weight = match pos_weight {
+        None => log_sigmoid,
+        Some(pos_weight) => {
+            let scale = mul(sub(pos_weight, one.clone())?, target.clone())?;
+            mul(add(one.clone(), scale)?, log_sigmoid)?
+        }
+    };
+    let mut loss = add(mul(sub(one, target)?, input)?, log_weight)?;
+    if let Some(weight) = weight {
+        loss = mul(loss, weight)?;
+    }
+    return reduce(loss, reduction);
+}
+
+/// The Kullback-Leibler divergence of `input` from `target`, where `input`
+/// holds log-probabilities and `target` probabilities, or log-probabilities
+/// if `log_target` is set.
+pub fn kl_div(
+    input: Tensor,
+    target: Tensor,
+    reduction: Reduction,
+    log_target: bool,
+) -> Result<Tensor> {
+    check_float("kl_div", &input)?;
+    let loss = if log_target {
+        mul(exp(target.clone())?, sub(target, input)?)?
+    } else {
+        sub(unary(target.clone(), Unary::XLogX)?, mul(target, input)?)?
+    };
+    return reduce(loss, reduction);
+}
+
+fn parse_reduction(reduction: &str, batch_mean: bool) -> Result<Reduction> {
+    let parsed = Reduction::try_from(reduction)?;
+    if parsed == Reduction::BatchMean && !batch_mean {
+        return Err(AutogradError::InvalidArgument(
+            "Reduction 'batchmean' is only supported by kl_div".to_string(),
+        ));
+    }
+    Ok(parsed)
+}
+
+#[pyfunction(name = "mse_loss")]
+#[pyo3(signature = (input, target, reduction="mean"))]
+pub fn py_mse_loss(input: Tensor, target: Tensor, reduction: &str) -> PyResult<Tensor> {
+    Ok(mse_loss(input, target, parse_reduction(reduction, false)?)?)
+}
+
+#[pyfunction(name = "l1_loss")]
+#[pyo3(signature = (input, target, reduction="mean"))]
+pub fn py_l1_loss(input: Tensor, target: Tensor, reduction: &str) -> PyResult<Tensor> {
+    Ok(l1_loss(input, target, parse_reduction(reduction, false)?)?)
+}
+
+#[pyfunction(name = "huber_loss")]
+#[pyo3(signature = (input, target, reduction="mean", delta=1.0))]
+pub fn py_huber_loss(
+    input: Tensor,
+    target: Tensor,
+    reduction: &str,
+    delta: f64,
+) -> PyResult<Tensor> {
+    Ok(huber_loss(
+        input,
+        target,
+        parse_reduction(reduction, false)?,
+        delta,
+    )?)
+}
+
+#[pyfunction(name = "smooth_l1_loss")]
+#[pyo3(signature = (input, target, reduction="mean", beta=1.0))]
+pub fn py_smooth_l1_loss(
+    input: Tensor,
+    target: Tensor,
+    reduction: &str,
+    beta: f64,
+) -> PyResult<Tensor> {
+    Ok(smooth_l1_loss(
+        input,
+        target,
+        parse_reduction(reduction, false)?,
+        beta,
+    )?)
+}
+
+#[pyfunction(name = "nll_loss")]
+#[pyo3(signature = (input, target, weight=None, ignore_index=-100, reduction="mean"))]
+pub fn py_nll_loss(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    ignore_index: i64,
+    reduction: &str,
+) -> PyResult<Tensor> {
+    Ok(nll_loss(
+        input,
+        target,
+        weight,
+        ignore_index,
+        parse_reduction(reduction, false)?,
+    )?)
+}
+
+#[pyfunction(name = "cross_entropy")]
+#[pyo3(signature = (input, target, weight=None, ignore_index=-100, reduction="mean"))]
+pub fn py_cross_entropy(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    ignore_index: i64,
+    reduction: &str,
+) -> PyResult<Tensor> {
+    Ok(cross_entropy(
+        input,
+        target,
+        weight,
+        ignore_index,
+        parse_reduction(reduction, false)?,
+    )?)
+}
+
+#[pyfunction(name = "binary_cross_entropy_with_logits")]
+#[pyo3(signature = (input, target, weight=None, reduction="mean", pos_weight=None))]
+pub fn py_binary_cross_entropy_with_logits(
+    input: Tensor,
+    target: Tensor,
+    weight: Option<Tensor>,
+    reduction: &str,
+    pos_weight: Option<Tensor>,
+) -> PyResult<Tensor> {
+    Ok(binary_cross_entropy_with_logits(
+        input,
+        target,
+        weight,
+        pos_weight,
+        parse_reduction(reduction, false)?,
+    )?)
+}
+
+#[pyfunction(name = "kl_div")]
+#[pyo3(signature = (input, target, reduction="mean", log_target=false))]
+pub fn py_kl_div(
+    input: Tensor,
+    target: Tensor,
+    reduction: &str,
+    log_target: bool,
+) -> PyResult<Tensor> {
+    Ok(kl_div(
+        input,
+        target,
+        parse_reduction(reduction, true)?,
+        log_target,
+    )?)
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_mse_loss, m)?)?;
+    m.add_function(wrap_pyfunction!(py_l1_loss, m)?)?;
+    m.add_function(wrap_pyfunction!(py_huber_loss, m)?)?;
+    m.add_function(wrap_pyfunction!(py_smooth_l1_loss, m)?)?;
+    m.add_function(wrap_pyfunction!(py_nll_loss, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cross_entropy, m)?)?;
+    m.add_function(wrap_pyfunction!(py_binary_cross_entropy_with_logits, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kl_div, m)?)?;
+    Ok(())
+}
@@ -1,103 +1,189 @@
 use crate::{
-    backward::Backward, objects::Tensor, operations::broadcast::broadcast,
-    utils::new_tensor_with_graph, DTYPE,
+    backward::Backward,
+    dispatch_numeric,
+    errors::{AutogradError, Result},
+    forward::{sum_tangents, Forward},
+    kernels::gemm::{batch_gemm, MatRef},
+    objects::{strided_index_map, Tensor},
+    operations::{
+        add::add,
+        broadcast::{broadcast, broadcast_shapes},
+        mul::mul,
+        transpose::transpose,
+        view::{squeeze, unsqueeze},
+    },
+    utils::{new_tensor_with_graph, promote_types, Operand},
 };
 use pyo3::prelude::*;
-use rayon::prelude::*;
-use std::thread;
-
-pub fn matul_kernel(
-    lhs: &Vec<DTYPE>,
-    rhs: &Vec<DTYPE>,
-    m: usize,
-    n: usize,
-    p: usize,
-) -> Vec<DTYPE> {
-    let mut data = vec![0.0; m * p];
-    data.par_chunks_mut(p).enumerate().for_each(|(i, row)| {
-        let a_row = &lhs[i * n..i * n + n];
-        for k in 0..n {
-            let a_ik = a_row[k];
-            let b_row = &rhs[k * p..k * p + p];
-            for j in 0..p {
-                row[j] += a_ik * b_row[j];
-            }
-        }
+
+/// The matrices of a stack in its last two dimensions, read in place through
+/// the strides of `t`, so that transposed and broadcast views are not copied.
+fn matrices<'a, T: Copy>(t: &Tensor, storage: &'a [T]) -> Vec<MatRef<'a, T>> {
+    let (shape, strides) = (t.get_shape(), t.get_strides());
+    let ndim = shape.len();
+    strided_index_map(&shape[..ndim - 2], &strides[..ndim - 2], t.get_offset())
+        .into_iter()
+        .map(|offset| MatRef {
+            data: storage,
+            offset,
+            rows: shape[ndim - 2],
+            cols: shape[ndim - 1],
+            row_stride: strides[ndim - 2],
+            col_stride: strides[ndim - 1],
+        })
+        .collect()
+}
+
+/// Matrix product with numpy semantics. A 1D lhs is a row vector and a 1D rhs
+/// a column vector, their added dimension being removed from the result. Other
+/// tensors are stacks of matrices in their last two dimensions, and the leading
+/// batch dimensions are broadcast together.
+pub fn matmul(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    let (lhs_shape, rhs_shape) = (lhs.get_shape(), rhs.get_shape());
+    if lhs_shape.is_empty() || rhs_shape.is_empty() {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Both operands of matrix multiplication need at least 1 dimension. Got shapes: {:?} and {:?}",
+            lhs_shape, rhs_shape
+        )));
+    }
+    let (lhs_vector, rhs_vector) = (lhs_shape.len() == 1, rhs_shape.len() == 1);
+    let rhs_inner = rhs_shape[rhs_shape.len().saturating_sub(2)];
+    if lhs_shape[lhs_shape.len() - 1] != rhs_inner {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Inner dimensions must match for matrix multiplication. Got shapes: {:?} and {:?}",
+            lhs_shape, rhs_shape
+        )));
+    }
+    let lhs = if lhs_vector { unsqueeze(lhs, 0)? } else { lhs };
+    let rhs = if rhs_vector { unsqueeze(rhs, -1)? } else { rhs };
+
+    let mut result = batched_matmul(lhs, rhs)?;
+    if lhs_vector {
+        result = squeeze(result, Some(-2))?;
+    }
+    if rhs_vector {
+        result = squeeze(result, Some(-1))?;
+    }
+    return Ok(result);
+}
+
+/// Product of stacks of matrices with matching inner dimensions, broadcasting
+/// their batch dimensions.
+fn batched_matmul(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs_shape, rhs_shape) = (lhs.get_shape(), rhs.get_shape());
+    let (lhs_batch, lhs_matrix) = lhs_shape.split_at(lhs_shape.len() - 2);
+    let (rhs_batch, rhs_matrix) = rhs_shape.split_at(rhs_shape.len() - 2);
+    let (m, n, p) = (lhs_matrix[0], lhs_matrix[1], rhs_matrix[1]);
+
+    /* The backward of broadcast sums the gradients over the broadcast dimensions */
+    let batch = broadcast_shapes(lhs_batch, rhs_batch)?;
+    let lhs = match lhs_batch == batch {
+        true => lhs,
+        false => broadcast(lhs, [batch.clone(), vec![m, n]].concat())?,
+    };
+    let rhs = match rhs_batch == batch {
+        true => rhs,
+        false => broadcast(rhs, [batch.clone(), vec![n, p]].concat())?,
+    };
+
+    let shape = [batch, vec![m, p]].concat();
+    return dispatch_numeric!(lhs.dtype, T => {
+        let data = {
+            let (lhs_data, rhs_data) = (lhs.get_storage_ref::<T>(), rhs.get_storage_ref::<T>());
+            batch_gemm(&matrices(&lhs, &lhs_data), &matrices(&rhs, &rhs_data), m, p)
+        };
+
+        new_tensor_with_graph(
+            shape,
+            data,
+            lhs.get_requires_grad() || rhs.get_requires_grad(),
+            MatMulOperation {
+                lhs: lhs.clone(),
+                rhs: rhs.clone(),
+            },
+        )
     });
-    return data;
 }
 
-pub fn batch_matmul_kernel(
-    lhs: &Vec<DTYPE>,
-    rhs: &Vec<DTYPE>,
-    m: usize,
-    n: usize,
-    p: usize,
-    batch_size: usize,
-) -> Vec<DTYPE> {
-    let mut data = vec![0.0; batch_size * m * p];
-    data.par_chunks_mut(m * p)
-        .enumerate()
-        .for_each(|(b, chunk)| {
-            let a_batch = &lhs[b * m * n..(b + 1) * m * n];
-            let b_batch = &rhs[b * n * p..(b + 1) * n * p];
-            chunk.copy_from_slice(&matul_kernel(&a_batch.to_vec(), &b_batch.to_vec(), m, n, p));
-        });
-    return data;
+fn check_dims(t: &Tensor, ndim: usize, function: &str) -> Result<()> {
+    if t.get_shape().len() != ndim {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "{} expects {}D tensors, got shape {:?}",
+            function,
+            ndim,
+            t.get_shape()
+        )));
+    }
+    Ok(())
 }
 
-pub fn matmul(lhs: Tensor, rhs: Tensor) -> Tensor {
-    let m: usize;
-    let p: usize;
-    let shape: Vec<usize>;
-    let data: Vec<DTYPE>;
-
-    if lhs.get_shape().len() == 2 && rhs.get_shape().len() == 2 {
-        m = lhs.get_shape()[0];
-        p = rhs.get_shape()[1];
-        let n = lhs.get_shape()[1];
-        if n != rhs.get_shape()[0] {
-            panic!("Inner dimensions must match for matrix multiplication");
-        }
-        shape = vec![m, p];
-        data = matul_kernel(&lhs.get_data_ref(), &rhs.get_data_ref(), m, n, p);
-    } else if lhs.get_shape().len() == 3 && rhs.get_shape().len() == 3 {
-        let batch_size = lhs.get_shape()[0];
-        m = lhs.get_shape()[1];
-        p = rhs.get_shape()[2];
-        let n = lhs.get_shape()[2];
-        if n != rhs.get_shape()[1] {
-            panic!("Inner dimensions must match for batch matrix multiplication");
-        }
-        shape = vec![batch_size, m, p];
-        data = batch_matmul_kernel(
-            &lhs.get_data_ref(),
-            &rhs.get_data_ref(),
-            m,
-            n,
-            p,
-            batch_size,
-        );
-    } else if lhs.get_shape().len() == 3 && rhs.get_shape().len() == 2 {
-        let shape = vec![lhs.get_shape()[0], rhs.get_shape()[0], rhs.get_shape()[1]];
-        return matmul(lhs, broadcast(rhs, shape));
-    } else {
-        panic!(
-            "Matrix multiplication is only defined for 2D or 3D tensors. Got shapes: {:?} and {:?}",
+/// Dot product of two vectors.
+pub fn dot(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    check_dims(&lhs, 1, "dot")?;
+    check_dims(&rhs, 1, "dot")?;
+    matmul(lhs, rhs)
+}
+
+/// Product of a matrix and a vector.
+pub fn mv(mat: Tensor, vec: Tensor) -> Result<Tensor> {
+    check_dims(&mat, 2, "mv")?;
+    check_dims(&vec, 1, "mv")?;
+    matmul(mat, vec)
+}
+
+/// Outer product of two vectors.
+pub fn outer(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    check_dims(&lhs, 1, "outer")?;
+    check_dims(&rhs, 1, "outer")?;
+    mul(unsqueeze(lhs, 1)?, unsqueeze(rhs, 0)?)
+}
+
+/// Product of two stacks of matrices with the same batch size, without
+/// broadcasting.
+pub fn bmm(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    check_dims(&lhs, 3, "bmm")?;
+    check_dims(&rhs, 3, "bmm")?;
+    if lhs.get_shape()[0] != rhs.get_shape()[0] {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Batch sizes must match for bmm. Got shapes: {:?} and {:?}",
             lhs.get_shape(),
             rhs.get_shape()
-        );
-    }
-
-    return new_tensor_with_graph(
-        shape,
-        data,
-        lhs.get_requires_grad() || rhs.get_requires_grad(),
-        MatMulOperation {
-            lhs: lhs.clone(),
-            rhs: rhs.clone(),
-        },
-    );
+        )));
+    }
+    matmul(lhs, rhs)
+}
+
+/// beta * input + alpha * (mat1 @ mat2), with `input` broadcast to the shape
+/// of the product.
+pub fn addmm(input: Tensor, mat1: Tensor, mat2: Tensor, beta: f64, alpha: f64) -> Result<Tensor> {
+    check_dims(&mat1, 2, "addmm")?;
+    check_dims(&mat2, 2, "addmm")?;
+    let mut product = matmul(mat1, mat2)?;
+    if alpha != 1.0 {
+        let alpha = Operand::Float(alpha).into_tensor(&product);
+        product = mul(product, alpha)?;
+    }
+    let shape = product.get_shape();
+    if broadcast_shapes(&input.get_shape(), &shape)? != shape {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Input of shape {:?} cannot be broadcast to the shape {:?} of the product",
+            input.get_shape(),
+            shape
+        )));
+    }
+    /* With beta = 0 the input is ignored, even if it holds nan */
+    if beta == 0.0 {
+        return Ok(product);
+    }
+    let input = match beta {
+        1.0 => input,
+        _ => {
+            let beta = Operand::Float(beta).into_tensor(&input);
+            mul(input, beta)?
+        }
+    };
+    add(input, product)
 }
 
 pub struct MatMulOperation {
@@ -106,30 +192,67 @@ pub struct MatMulOperation {
 }
 
 impl Backward for MatMulOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-
-        let mut l1 = self.lhs.clone();
-        let g1 = grad.clone();
-        let r1 = self.rhs.transpose();
-        let h1 = thread::spawn(move || {
-            l1.do_backward(Some(matmul(g1, r1)), None);
-        });
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.lhs.clone(), self.rhs.clone()]
+    }
 
-        let mut r2 = self.rhs.clone();
-        let l2 = self.lhs.transpose();
-        let h2 = thread::spawn(move || {
-            r2.do_backward(Some(matmul(l2, grad)), None);
-        });
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* The transposes are views, which the kernel reads in place */
+        let rhs = transpose(self.rhs.clone(), -2, -1).unwrap();
+        let lhs = transpose(self.lhs.clone(), -2, -1).unwrap();
+        vec![
+            Some(matmul(grad.clone(), rhs).unwrap()),
+            Some(matmul(lhs, grad).unwrap()),
+        ]
+    }
+}
 
-        h1.join().unwrap();
-        h2.join().unwrap();
+impl Forward for MatMulOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        sum_tangents(vec![
+            tangents[0]
+                .clone()
+                .map(|t| matmul(t, self.rhs.clone()).unwrap()),
+            tangents[1]
+                .clone()
+                .map(|t| matmul(self.lhs.clone(), t).unwrap()),
+        ])
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn __matmul__(&self, other: Tensor) -> Tensor {
-        matmul(self.clone(), other)
+    pub fn __matmul__(&self, other: Tensor) -> PyResult<Tensor> {
+        Ok(matmul(self.clone(), other)?)
+    }
+
+    #[pyo3(name = "matmul")]
+    pub fn py_matmul(&self, other: Tensor) -> PyResult<Tensor> {
+        Ok(matmul(self.clone(), other)?)
+    }
+
+    #[pyo3(name = "dot")]
+    pub fn py_dot(&self, other: Tensor) -> PyResult<Tensor> {
+        Ok(dot(self.clone(), other)?)
+    }
+
+    #[pyo3(name = "mv")]
+    pub fn py_mv(&self, vec: Tensor) -> PyResult<Tensor> {
+        Ok(mv(self.clone(), vec)?)
+    }
+
+    #[pyo3(name = "outer")]
+    pub fn py_outer(&self, other: Tensor) -> PyResult<Tensor> {
+        Ok(outer(self.clone(), other)?)
+    }
+
+    #[pyo3(name = "bmm")]
+    pub fn py_bmm(&self, other: Tensor) -> PyResult<Tensor> {
+        Ok(bmm(self.clone(), other)?)
+    }
+
+    #[pyo3(name = "addmm", signature = (mat1, mat2, beta=1.0, alpha=1.0))]
+    pub fn py_addmm(&self, mat1: Tensor, mat2: Tensor, beta: f64, alpha: f64) -> PyResult<Tensor> {
+        Ok(addmm(self.clone(), mat1, mat2, beta, alpha)?)
     }
 }
@@ -1,10 +1,18 @@
 pub mod add;
 pub mod broadcast;
+pub mod cast;
+pub mod div;
+pub mod index;
+pub mod loss;
 pub mod matmul;
 pub mod mul;
 pub mod neg;
+pub mod pow;
+pub mod reduce;
 pub mod reduce_sum;
 pub mod relu;
 pub mod softmax;
 pub mod sub;
 pub mod transpose;
+pub mod unary;
+pub mod view;
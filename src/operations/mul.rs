@@ -1,26 +1,23 @@
 use crate::{
     backward::Backward,
+    dispatch_numeric,
+    errors::Result,
+    forward::{sum_tangents, Forward},
+    kernels::elementwise::map,
     objects::Tensor,
-    utils::{broadcast_to_same_dim, new_tensor_with_graph},
-    DTYPE,
+    utils::{broadcast_to_same_dim, in_place, new_tensor_with_graph, promote_types, Operand},
 };
 use pyo3::prelude::*;
 use std::ops::Mul;
 
-impl Mul for Tensor {
-    type Output = Tensor;
+pub fn mul(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    let (lhs, rhs) = broadcast_to_same_dim(lhs, rhs)?;
 
-    fn mul(self, rhs: Tensor) -> Tensor {
-        let (lhs, rhs) = broadcast_to_same_dim(self, rhs);
+    return dispatch_numeric!(lhs.dtype, T => {
+        let data: Vec<T> = map([&lhs, &rhs], |[a, b]: [T; 2]| a * b);
 
-        let data: Vec<DTYPE> = lhs
-            .get_data_ref()
-            .iter()
-            .zip(rhs.get_data_ref().iter())
-            .map(|(a, b)| a * b)
-            .collect();
-
-        return new_tensor_with_graph(
+        new_tensor_with_graph(
             lhs.get_shape(),
             data,
             lhs.get_requires_grad() || rhs.get_requires_grad(),
@@ -28,7 +25,16 @@ impl Mul for Tensor {
                 lhs: lhs.clone(),
                 rhs: rhs.clone(),
             },
-        );
+        )
+    });
+}
+
+/// Panics where `mul` fails, see `+`.
+impl Mul for Tensor {
+    type Output = Tensor;
+
+    fn mul(self, rhs: Tensor) -> Tensor {
+        mul(self, rhs).expect("Operands of `*` must broadcast and not both be bool")
     }
 }
 
@@ -38,18 +44,38 @@ pub struct MulOperation {
 }
 
 impl Backward for MulOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-        self.lhs
-            .do_backward(Some(grad.clone() * self.rhs.clone()), None);
-        self.rhs
-            .do_backward(Some(grad.clone() * self.lhs.clone()), None);
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.lhs.clone(), self.rhs.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![
+            Some(grad.clone() * self.rhs.clone()),
+            Some(grad * self.lhs.clone()),
+        ]
+    }
+}
+
+impl Forward for MulOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        sum_tangents(vec![
+            tangents[0].clone().map(|t| t * self.rhs.clone()),
+            tangents[1].clone().map(|t| self.lhs.clone() * t),
+        ])
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn __mul__(&self, other: Tensor) -> Tensor {
-        self.clone() * other
+    pub fn __mul__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(mul(self.clone(), other.into_tensor(self))?)
+    }
+
+    pub fn __rmul__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(mul(other.into_tensor(self), self.clone())?)
+    }
+
+    pub fn __imul__(&self, other: Operand) -> PyResult<()> {
+        Ok(in_place(self, mul(self.clone(), other.into_tensor(self))?)?)
     }
 }
@@ -1,18 +1,29 @@
-use crate::{backward::Backward, objects::Tensor, utils::new_tensor_with_graph, DTYPE};
+use crate::{
+    backward::Backward, dispatch_numeric, errors::Result, forward::Forward,
+    kernels::elementwise::map, objects::Tensor, utils::new_tensor_with_graph,
+};
 use pyo3::prelude::*;
 use std::ops::Neg;
 
+pub fn neg(t: Tensor) -> Result<Tensor> {
+    return dispatch_numeric!(t.dtype, T => {
+        let data: Vec<T> = map([&t], |[x]: [T; 1]| -x);
+        new_tensor_with_graph(
+            t.get_shape(),
+            data,
+            t.get_requires_grad(),
+            NegOperation { t: t.clone() },
+        )
+    });
+}
+
+/// Panics on bool tensors, where `neg` fails. Operands coming from the user
+/// go through `neg`.
 impl Neg for Tensor {
     type Output = Tensor;
 
     fn neg(self) -> Tensor {
-        let data: Vec<DTYPE> = self.get_data_ref().iter().map(|&x| -x).collect();
-        return new_tensor_with_graph(
-            self.get_shape(),
-            data,
-            self.get_requires_grad(),
-            NegOperation { t: self.clone() },
-        );
+        neg(self).expect("The operand of `-` must not be bool")
     }
 }
 
@@ -21,15 +32,24 @@ pub struct NegOperation {
 }
 
 impl Backward for NegOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let neg_grad = -grad.unwrap();
-        self.t.do_backward(Some(neg_grad), None);
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(-grad)]
+    }
+}
+
+impl Forward for NegOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        tangents[0].clone().map(|t| -t)
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn __neg__(&self) -> Tensor {
-        -self.clone()
+    pub fn __neg__(&self) -> PyResult<Tensor> {
+        Ok(neg(self.clone())?)
     }
 }
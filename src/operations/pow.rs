@@ -0,0 +1,151 @@
+use crate::{
+    backward::Backward,
+    dispatch_float,
+    dtype::{DType, Numeric},
+    errors::Result,
+    forward::{sum_tangents, Forward},
+    grad_mode::is_grad_enabled,
+    kernels::elementwise::map,
+    objects::Tensor,
+    operations::{cast::to, unary::log},
+    utils::{
+        broadcast_to_same_dim, in_place, new_tensor_simple, new_tensor_with_graph, promote_types,
+        Operand,
+    },
+};
+use pyo3::prelude::*;
+
+/// Raises the elements of `lhs` to the power of the elements of `rhs`. The
+/// operands are broadcast together, and integer tensors are converted to float32.
+pub fn pow(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    let (lhs, rhs) = if lhs.dtype.is_floating_point() {
+        (lhs, rhs)
+    } else {
+        (to(lhs, DType::Float32), to(rhs, DType::Float32))
+    };
+    let (lhs, rhs) = broadcast_to_same_dim(lhs, rhs)?;
+
+    return dispatch_float!(lhs.dtype, T => {
+        let data: Vec<T> = map([&lhs, &rhs], |[a, b]: [T; 2]| a.powf(b));
+
+        new_tensor_with_graph(
+            lhs.get_shape(),
+            data,
+            lhs.get_requires_grad() || rhs.get_requires_grad(),
+            PowOperation {
+                lhs: lhs.clone(),
+                rhs: rhs.clone(),
+            },
+        )
+    });
+}
+
+pub struct PowOperation {
+    lhs: Tensor,
+    rhs: Tensor,
+}
+
+impl Backward for PowOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.lhs.clone(), self.rhs.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        if is_grad_enabled() {
+            return self.differentiable_backward(grad, output);
+        }
+        dispatch_float!(grad.dtype, T => {
+            let (lhs, rhs) = (&self.lhs, &self.rhs);
+
+            /* d(a^b)/da = b * a^(b - 1), which is 0 where b = 0 */
+            let lhs_grad: Vec<T> = map([lhs, rhs, &grad], |[a, b, g]: [T; 3]| {
+                if b == T::ZERO {
+                    T::ZERO
+                } else {
+                    g * b * a.powf(b - T::ONE)
+                }
+            });
+            /* d(a^b)/db = a^b * ln(a), taken as 0 where a = 0 and b >= 0 */
+            let rhs_grad: Vec<T> = map([lhs, rhs, &grad, &output], |[a, b, g, y]: [T; 4]| {
+                if a == T::ZERO && b >= T::ZERO {
+                    T::ZERO
+                } else {
+                    g * y * a.ln()
+                }
+            });
+
+            vec![
+                Some(new_tensor_simple(self.lhs.get_shape(), lhs_grad)),
+                Some(new_tensor_simple(self.rhs.get_shape(), rhs_grad)),
+            ]
+        })
+        .unwrap()
+    }
+}
+
+impl Forward for PowOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let (lhs_derivative, rhs_derivative) = self.derivatives(output.clone());
+        sum_tangents(vec![
+            tangents[0].clone().map(|t| t * lhs_derivative),
+            tangents[1].clone().map(|t| t * rhs_derivative),
+        ])
+    }
+}
+
+impl PowOperation {
+    /// The same gradients built from differentiable operations, for create_graph.
+    fn differentiable_backward(&self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        let (lhs_derivative, rhs_derivative) = self.derivatives(output);
+        vec![
+            Some(grad.clone() * lhs_derivative),
+            Some(grad * rhs_derivative),
+        ]
+    }
+
+    /// The partial derivatives with respect to both operands, built from
+    /// differentiable operations. The special cases shift the exponent or the
+    /// logarithm by a constant so that they evaluate to 0 instead of
+    /// multiplying 0 by an infinity.
+    fn derivatives(&self, output: Tensor) -> (Tensor, Tensor) {
+        let (lhs, rhs) = (self.lhs.clone(), self.rhs.clone());
+        let (exponent_shift, log_shift) = dispatch_float!(output.dtype, T => {
+            let indicator = |condition: bool| if condition { T::ONE } else { T::ZERO };
+            let exponent_shift: Vec<T> = map([&rhs], |[b]: [T; 1]| indicator(b == T::ZERO));
+            let log_shift: Vec<T> =
+                map([&lhs, &rhs], |[a, b]: [T; 2]| indicator(a == T::ZERO && b >= T::ZERO));
+            (
+                new_tensor_simple(lhs.get_shape(), exponent_shift),
+                new_tensor_simple(lhs.get_shape(), log_shift),
+            )
+        })
+        .unwrap();
+        let one = Operand::Float(1.0).into_tensor(&lhs);
+
+        let power = pow(lhs.clone(), rhs.clone() - one + exponent_shift).unwrap();
+        (rhs * power, output * log(lhs + log_shift).unwrap())
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn pow(&self, exponent: Operand) -> PyResult<Tensor> {
+        Ok(pow(self.clone(), exponent.into_tensor(self))?)
+    }
+
+    pub fn __pow__(&self, exponent: Operand, _modulo: Option<Py<PyAny>>) -> PyResult<Tensor> {
+        self.pow(exponent)
+    }
+
+    pub fn __rpow__(&self, base: Operand, _modulo: Option<Py<PyAny>>) -> PyResult<Tensor> {
+        Ok(pow(base.into_tensor(self), self.clone())?)
+    }
+
+    pub fn __ipow__(&self, exponent: Operand, _modulo: Option<Py<PyAny>>) -> PyResult<()> {
+        Ok(in_place(
+            self,
+            pow(self.clone(), exponent.into_tensor(self))?,
+        )?)
+    }
+}
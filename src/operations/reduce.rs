@@ -0,0 +1,632 @@
+use crate::{
+    backward::Backward,
+    dispatch_all, dispatch_float, dispatch_numeric,
+    dtype::{DType, Element, Numeric},
+    errors::{AutogradError, Result},
+    forward::{sum_tangents, Forward},
+    grad_mode::is_grad_enabled,
+    objects::Tensor,
+    operations::{
+        broadcast::{broadcast, broadcast_index_map},
+        cast::to,
+        mul::mul,
+        transpose::permute,
+        view::reshape,
+    },
+    utils::{new_tensor_simple, new_tensor_with_graph, normalize_dim, Operand},
+};
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+
+/* Reductions along arbitrary dimensions. A reduced tensor is computed with its
+ * reduced dimensions kept as 1, so that each input element maps to an output
+ * element the same way a broadcast maps output elements to input elements. */
+
+/// One or several dimensions, as accepted from Python.
+#[derive(FromPyObject)]
+pub enum Dims {
+    Single(isize),
+    Multiple(Vec<isize>),
+}
+
+impl From<Dims> for Vec<isize> {
+    fn from(dims: Dims) -> Self {
+        match dims {
+            Dims::Single(dim) => vec![dim],
+            Dims::Multiple(dims) => dims,
+        }
+    }
+}
+
+/// Marks the reduced dimensions. No dimensions means reducing over all of them.
+fn reduced_dims(shape: &[usize], dims: Option<Vec<isize>>) -> Result<Vec<bool>> {
+    match dims {
+        None => Ok(vec![true; shape.len()]),
+        Some(dims) => {
+            let mut reduced = vec![false; shape.len()];
+            for dim in dims {
+                let normalized = normalize_dim(dim, shape)?;
+                if reduced[normalized] {
+                    return Err(AutogradError::InvalidArgument(format!(
+                        "Dimension {} appears multiple times in the reduced dimensions",
+                        dim
+                    )));
+                }
+                reduced[normalized] = true;
+            }
+            Ok(reduced)
+        }
+    }
+}
+
+/// Shape of the reduction with the reduced dimensions kept as 1.
+fn kept_shape(shape: &[usize], reduced: &[bool]) -> Vec<usize> {
+    shape
+        .iter()
+        .zip(reduced.iter())
+        .map(|(&dim, &r)| if r { 1 } else { dim })
+        .collect()
+}
+
+/// Shape of the reduction, with or without the reduced dimensions.
+pub fn output_shape(shape: &[usize], reduced: &[bool], keepdim: bool) -> Vec<usize> {
+    if keepdim {
+        return kept_shape(shape, reduced);
+    }
+    let shape: Vec<usize> = shape
+        .iter()
+        .zip(reduced.iter())
+        .filter(|(_, &r)| !r)
+        .map(|(&dim, _)| dim)
+        .collect();
+    if shape.is_empty() {
+        vec![1]
+    } else {
+        shape
+    }
+}
+
+/// Bool tensors are reduced as int64, like in NumPy.
+fn bool_as_int(t: Tensor) -> Tensor {
+    if t.dtype == DType::Bool {
+        return to(t, DType::Int64);
+    }
+    t
+}
+
+/* Sum and mean */
+
+pub fn sum(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool) -> Result<Tensor> {
+    scaled_sum(t, dims, keepdim, false)
+}
+
+pub fn mean(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool) -> Result<Tensor> {
+    scaled_sum(t, dims, keepdim, true)
+}
+
+fn scaled_sum(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool, average: bool) -> Result<Tensor> {
+    let t = bool_as_int(t);
+    if average && !t.dtype.is_floating_point() {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "Mean is only defined for floating point tensors, got {}",
+            t.dtype.name()
+        )));
+    }
+    let shape = t.get_shape();
+    let reduced = reduced_dims(&shape, dims)?;
+    let kept = kept_shape(&shape, &reduced);
+    let indices = broadcast_index_map(&kept, &shape);
+
+    let size: usize = kept.iter().product();
+    let scale = if average {
+        size as f64 / indices.len() as f64
+    } else {
+        1.0
+    };
+
+    return dispatch_numeric!(t.dtype, T => {
+        let mut data = vec![T::ZERO; size];
+        for (&i, &x) in indices.iter().zip(t.get_data_ref::<T>().iter()) {
+            data[i] += x;
+        }
+        if average {
+            let scale = T::from_f64(scale);
+            data.iter_mut().for_each(|x| *x *= scale);
+        }
+
+        new_tensor_with_graph(
+            output_shape(&shape, &reduced, keepdim),
+            data,
+            t.get_requires_grad(),
+            SumOperation {
+                t: t.clone(),
+                kept,
+                scale,
+            },
+        )
+    });
+}
+
+pub struct SumOperation {
+    t: Tensor,
+    kept: Vec<usize>,
+    scale: f64,
+}
+
+impl Backward for SumOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* Every element receives the gradient of the element it was summed into */
+        let kept = self.kept.iter().map(|&d| d as isize).collect();
+        let sum_grad = broadcast(reshape(grad, kept).unwrap(), self.t.get_shape()).unwrap();
+        if self.scale == 1.0 {
+            return vec![Some(sum_grad)];
+        }
+        let scale = Operand::Float(self.scale).into_tensor(&sum_grad);
+        vec![Some(sum_grad * scale)]
+    }
+}
+
+impl Forward for SumOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let tangent = reduce_like(tangents[0].clone()?, &self.kept, output);
+        if self.scale == 1.0 {
+            return Some(tangent);
+        }
+        let scale = Operand::Float(self.scale).into_tensor(&tangent);
+        Some(tangent * scale)
+    }
+}
+
+/// Sums `t` to the shape `kept` of a reduction, then gives it the shape of its
+/// `output`.
+fn reduce_like(t: Tensor, kept: &[usize], output: &Tensor) -> Tensor {
+    let dims = (0..kept.len())
+        .filter(|&d| kept[d] == 1)
+        .map(|d| d as isize)
+        .collect();
+    let shape = output.get_shape().iter().map(|&d| d as isize).collect();
+    reshape(sum(t, Some(dims), true).unwrap(), shape).unwrap()
+}
+
+/* Product */
+
+pub fn prod(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool) -> Result<Tensor> {
+    let t = bool_as_int(t);
+    let shape = t.get_shape();
+    let reduced = reduced_dims(&shape, dims)?;
+    let kept = kept_shape(&shape, &reduced);
+    let indices = broadcast_index_map(&kept, &shape);
+
+    return dispatch_numeric!(t.dtype, T => {
+        let mut data = vec![T::ONE; kept.iter().product()];
+        for (&i, &x) in indices.iter().zip(t.get_data_ref::<T>().iter()) {
+            data[i] *= x;
+        }
+
+        new_tensor_with_graph(
+            output_shape(&shape, &reduced, keepdim),
+            data,
+            t.get_requires_grad(),
+            ProdOperation { t: t.clone(), kept },
+        )
+    });
+}
+
+pub struct ProdOperation {
+    t: Tensor,
+    kept: Vec<usize>,
+}
+
+impl Backward for ProdOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        let kept = self.kept.iter().map(|&d| d as isize).collect();
+        let prod_grad = broadcast(reshape(grad, kept).unwrap(), self.t.get_shape()).unwrap();
+        vec![Some(prod_grad * self.others(output))]
+    }
+}
+
+impl Forward for ProdOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let t = tangents[0].clone()?;
+        Some(reduce_like(
+            self.others(output.clone()) * t,
+            &self.kept,
+            output,
+        ))
+    }
+}
+
+impl ProdOperation {
+    /// The derivative of the product with respect to each element: the product
+    /// of the other elements. With gradients enabled it is prod / x so that it
+    /// can be differentiated again, or `scanned_others` for inputs with zeros.
+    /// Otherwise a kernel computes it without dividing by zero.
+    fn others(&self, output: Tensor) -> Tensor {
+        let has_zeros =
+            dispatch_float!(self.t.dtype, T => self.t.get_data_ref::<T>().contains(&T::ZERO))
+                .unwrap();
+        if is_grad_enabled() && has_zeros {
+            return self.scanned_others().unwrap();
+        }
+        if is_grad_enabled() {
+            let kept = self.kept.iter().map(|&d| d as isize).collect();
+            let output = broadcast(reshape(output, kept).unwrap(), self.t.get_shape()).unwrap();
+            return output / self.t.clone();
+        }
+
+        let indices = broadcast_index_map(&self.kept, &self.t.get_shape());
+        let size: usize = self.kept.iter().product();
+        dispatch_float!(self.t.dtype, T => {
+            let t_data = self.t.get_data_ref::<T>();
+
+            let mut nonzero_prod = vec![T::ONE; size];
+            let mut zeros = vec![0; size];
+            for (&i, &x) in indices.iter().zip(t_data.iter()) {
+                if x == T::ZERO {
+                    zeros[i] += 1;
+                } else {
+                    nonzero_prod[i] *= x;
+                }
+            }
+
+            let others = indices
+                .iter()
+                .zip(t_data.iter())
+                .map(|(&i, &x)| match (zeros[i], x == T::ZERO) {
+                    (0, _) => nonzero_prod[i] / x,
+                    (1, true) => nonzero_prod[i],
+                    _ => T::ZERO,
+                })
+                .collect();
+            new_tensor_simple::<T>(self.t.get_shape(), others)
+        })
+        .unwrap()
+    }
+
+    /// The product of the other elements, differentiable without dividing by
+    /// zero: the product of the elements before each one times the product of
+    /// those after it. The elements of each reduction are laid out in a row,
+    /// and both products are scans along the rows, in linear time.
+    fn scanned_others(&self) -> Result<Tensor> {
+        let shape = self.t.get_shape();
+        let (reduced, kept): (Vec<usize>, Vec<usize>) =
+            (0..shape.len()).partition(|&d| self.kept[d] == 1);
+        let rows: usize = kept.iter().map(|&d| shape[d]).product();
+        let size: usize = reduced.iter().map(|&d| shape[d]).product();
+
+        let order: Vec<usize> = kept.into_iter().chain(reduced).collect();
+        let permuted = permute(self.t.clone(), order.iter().map(|&d| d as isize).collect())?;
+        let permuted_shape = permuted.get_shape();
+        let x = reshape(permuted, vec![rows as isize, size as isize])?;
+
+        /* The scans start from 1 at the first and at the last element */
+        let (first, last) = dispatch_float!(self.t.dtype, T => {
+            let ends = |end: usize| {
+                let data = (0..rows * size)
+                    .map(|i| if i % size == end { T::ONE } else { T::ZERO })
+                    .collect();
+                new_tensor_simple::<T>(vec![rows, size], data)
+            };
+            (ends(0), ends(size - 1))
+        })?;
+        let before = scan(&first, &shift(&x, false)?, false);
+        let after = scan(&last, &x, true);
+        let products = mul(before, after)?;
+
+        let mut inverse = vec![0; order.len()];
+        for (i, &d) in order.iter().enumerate() {
+            inverse[d] = i as isize;
+        }
+        let products = reshape(
+            products,
+            permuted_shape.iter().map(|&d| d as isize).collect(),
+        )?;
+        permute(products, inverse)
+    }
+}
+
+/// Moves the elements of each row of `t`, of shape [rows, size], one place to
+/// the right, or to the left if `left`, filling the place left empty with zero.
+fn shift(t: &Tensor, left: bool) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let (rows, size) = (shape[0], shape[1]);
+    let positions = |start: usize| -> Vec<usize> {
+        (0..rows)
+            .flat_map(|row| (start..start + size - 1).map(move |j| row * size + j))
+            .collect()
+    };
+    let (from, to) = if left { (1, 0) } else { (0, 1) };
+    let moved = take(t, vec![rows * (size - 1)], positions(from));
+    scatter(&moved, shape, positions(to))
+}
+
+/// The linear recurrence y_i = a_i + b_i * y_(i-1) along each row of `a` and
+/// `b`, of shape [rows, size], from the first element, or
+/// y_i = a_i + b_(i+1) * y_(i+1) from the last one if `reverse`. The gradient
+/// of either is a scan in the other direction, so scans can be differentiated
+/// to any order in linear time, whatever the values of `b`.
+fn scan(a: &Tensor, b: &Tensor, reverse: bool) -> Tensor {
+    let shape = a.get_shape();
+    let size = shape[1];
+    dispatch_float!(a.dtype, T => {
+        let data = {
+            let (a_data, b_data) = (a.get_data_ref::<T>(), b.get_data_ref::<T>());
+            let mut data = vec![T::ZERO; a_data.len()];
+            for row in (0..data.len()).step_by(size.max(1)) {
+                if reverse {
+                    data[row + size - 1] = a_data[row + size - 1];
+                    for i in (row..row + size - 1).rev() {
+                        data[i] = a_data[i] + b_data[i + 1] * data[i + 1];
+                    }
+                } else {
+                    data[row] = a_data[row];
+                    for i in row + 1..row + size {
+                        data[i] = a_data[i] + b_data[i] * data[i - 1];
+                    }
+                }
+            }
+            data
+        };
+
+        new_tensor_with_graph(
+            shape,
+            data,
+            a.get_requires_grad() || b.get_requires_grad(),
+            ScanOperation {
+                a: a.clone(),
+                b: b.clone(),
+                reverse,
+            },
+        )
+    })
+    .unwrap()
+}
+
+pub struct ScanOperation {
+    a: Tensor,
+    b: Tensor,
+    reverse: bool,
+}
+
+impl Backward for ScanOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.a.clone(), self.b.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        let a_grad = scan(&grad, &self.b, !self.reverse);
+        let b_grad = match self.reverse {
+            false => a_grad.clone() * shift(&output, false).unwrap(),
+            true => shift(&a_grad, false).unwrap() * output,
+        };
+        vec![Some(a_grad), Some(b_grad)]
+    }
+}
+
+impl Forward for ScanOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let b_term = tangents[1].clone().map(|t| match self.reverse {
+            false => t * shift(output, false).unwrap(),
+            true => shift(&(t * output.clone()), true).unwrap(),
+        });
+        let a = sum_tangents(vec![tangents[0].clone(), b_term])?;
+        Some(scan(&a, &self.b, self.reverse))
+    }
+}
+
+/* Max and min */
+
+/// Selections have no identity element, so they can't reduce over an empty
+/// dimension.
+fn check_not_empty(shape: &[usize], reduced: &[bool]) -> Result<()> {
+    if let Some(dim) = (0..shape.len()).find(|&d| reduced[d] && shape[d] == 0) {
+        return Err(AutogradError::InvalidArgument(format!(
+            "Cannot select along dimension {} of size 0 in a tensor of shape {:?}",
+            dim, shape
+        )));
+    }
+    Ok(())
+}
+
+/// For every element of the reduction, finds the index of the input element
+/// that compares as `better` to the others (the first one in case of ties).
+/// The reduced dimensions must not be empty.
+fn arg_reduce<T: Element>(t: &Tensor, reduced: &[bool], better: Ordering) -> Vec<usize> {
+    let shape = t.get_shape();
+    let kept = kept_shape(&shape, reduced);
+    let indices = broadcast_index_map(&kept, &shape);
+    let t_data = t.get_data_ref::<T>();
+
+    let mut args: Vec<Option<usize>> = vec![None; kept.iter().product()];
+    for (j, &i) in indices.iter().enumerate() {
+        match args[i] {
+            Some(arg) if t_data[j].partial_cmp(&t_data[arg]) != Some(better) => {}
+            _ => args[i] = Some(j),
+        }
+    }
+    args.into_iter().map(|arg| arg.unwrap()).collect()
+}
+
+fn select(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool, better: Ordering) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let reduced = reduced_dims(&shape, dims)?;
+    check_not_empty(&shape, &reduced)?;
+    let args = dispatch_all!(t.dtype, T => arg_reduce::<T>(&t, &reduced, better));
+    return Ok(take(&t, output_shape(&shape, &reduced, keepdim), args));
+}
+
+pub fn max(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool) -> Result<Tensor> {
+    select(t, dims, keepdim, Ordering::Greater)
+}
+
+pub fn min(t: Tensor, dims: Option<Vec<isize>>, keepdim: bool) -> Result<Tensor> {
+    select(t, dims, keepdim, Ordering::Less)
+}
+
+/// Gathers the elements of `t` at the given row-major indices into a tensor of
+/// shape `shape`. Indices can repeat.
+pub fn take(t: &Tensor, shape: Vec<usize>, args: Vec<usize>) -> Tensor {
+    dispatch_all!(t.dtype, T => {
+        let data = {
+            let t_data = t.get_data_ref::<T>();
+            args.iter().map(|&j| t_data[j]).collect::<Vec<T>>()
+        };
+
+        new_tensor_with_graph(
+            shape,
+            data,
+            t.get_requires_grad(),
+            SelectOperation { t: t.clone(), args },
+        )
+    })
+}
+
+/// Routes the gradient of each element of the result to the input element
+/// it was selected from.
+pub struct SelectOperation {
+    t: Tensor,
+    args: Vec<usize>,
+}
+
+impl Backward for SelectOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(
+            scatter(&grad, self.t.get_shape(), self.args.clone()).unwrap(),
+        )]
+    }
+}
+
+impl Forward for SelectOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        tangents[0]
+            .as_ref()
+            .map(|t| take(t, output.get_shape(), self.args.clone()))
+    }
+}
+
+/// Sums the elements of `t` into a zero tensor of shape `shape`, at the given
+/// row-major indices. Indices can repeat. This is the transpose of `take`, and
+/// each one is the backward of the other.
+pub fn scatter(t: &Tensor, shape: Vec<usize>, args: Vec<usize>) -> Result<Tensor> {
+    return dispatch_numeric!(t.dtype, T => {
+        let mut data = vec![T::ZERO; shape.iter().product()];
+        for (&j, &x) in args.iter().zip(t.get_data_ref::<T>().iter()) {
+            data[j] += x;
+        }
+
+        new_tensor_with_graph(
+            shape,
+            data,
+            t.get_requires_grad(),
+            ScatterOperation { t: t.clone(), args },
+        )
+    });
+}
+
+pub struct ScatterOperation {
+    t: Tensor,
+    args: Vec<usize>,
+}
+
+impl Backward for ScatterOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(take(&grad, self.t.get_shape(), self.args.clone()))]
+    }
+}
+
+impl Forward for ScatterOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        tangents[0]
+            .as_ref()
+            .map(|t| scatter(t, output.get_shape(), self.args.clone()).unwrap())
+    }
+}
+
+/* Argmax and argmin */
+
+/// Indices of the selected elements along `dim`, or in the flattened tensor if
+/// `dim` is None. The result is an int64 tensor.
+fn arg_select(t: Tensor, dim: Option<isize>, keepdim: bool, better: Ordering) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let reduced = reduced_dims(&shape, dim.map(|dim| vec![dim]))?;
+    check_not_empty(&shape, &reduced)?;
+    let args = dispatch_all!(t.dtype, T => arg_reduce::<T>(&t, &reduced, better));
+
+    let data: Vec<i64> = match dim {
+        None => args.iter().map(|&j| j as i64).collect(),
+        Some(dim) => {
+            /* Convert flat indices to indices along dim */
+            let dim = normalize_dim(dim, &shape)?;
+            let inner: usize = shape[dim + 1..].iter().product();
+            args.iter()
+                .map(|&j| ((j / inner) % shape[dim]) as i64)
+                .collect()
+        }
+    };
+    Ok(new_tensor_simple(
+        output_shape(&shape, &reduced, keepdim),
+        data,
+    ))
+}
+
+pub fn argmax(t: Tensor, dim: Option<isize>, keepdim: bool) -> Result<Tensor> {
+    arg_select(t, dim, keepdim, Ordering::Greater)
+}
+
+pub fn argmin(t: Tensor, dim: Option<isize>, keepdim: bool) -> Result<Tensor> {
+    arg_select(t, dim, keepdim, Ordering::Less)
+}
+
+#[pymethods]
+impl Tensor {
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn sum(&self, dim: Option<Dims>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(sum(self.clone(), dim.map(Vec::from), keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn mean(&self, dim: Option<Dims>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(mean(self.clone(), dim.map(Vec::from), keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn prod(&self, dim: Option<Dims>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(prod(self.clone(), dim.map(Vec::from), keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn max(&self, dim: Option<Dims>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(max(self.clone(), dim.map(Vec::from), keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn min(&self, dim: Option<Dims>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(min(self.clone(), dim.map(Vec::from), keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn argmax(&self, dim: Option<isize>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(argmax(self.clone(), dim, keepdim)?)
+    }
+
+    #[pyo3(signature = (dim=None, keepdim=false))]
+    pub fn argmin(&self, dim: Option<isize>, keepdim: bool) -> PyResult<Tensor> {
+        Ok(argmin(self.clone(), dim, keepdim)?)
+    }
+}
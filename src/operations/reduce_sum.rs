@@ -1,22 +1,36 @@
 use crate::{
     backward::Backward,
+    dispatch_numeric,
+    dtype::{DType, Numeric},
+    errors::Result,
+    forward::Forward,
     objects::Tensor,
-    utils::{new_tensor_simple, new_tensor_with_graph},
+    operations::{broadcast::broadcast, cast::to},
+    utils::new_tensor_with_graph,
 };
 use pyo3::prelude::*;
 
-pub fn reduce_sum(t: Tensor) -> Tensor {
-    let mut sum = 0.0;
-    for i in t.get_data_ref().iter() {
-        sum += i;
-    }
+/// Sums all the elements of `t`. Bool tensors are summed as int64.
+pub fn reduce_sum(t: Tensor) -> Result<Tensor> {
+    let t = if t.dtype == DType::Bool {
+        to(t, DType::Int64)
+    } else {
+        t
+    };
+
+    return dispatch_numeric!(t.dtype, T => {
+        let mut sum = T::ZERO;
+        for &i in t.get_data_ref::<T>().iter() {
+            sum += i;
+        }
 
-    return new_tensor_with_graph(
-        vec![1],
-        vec![sum],
-        t.get_requires_grad(),
-        ReduceSumOperation { t: t.clone() },
-    );
+        new_tensor_with_graph(
+            vec![1],
+            vec![sum],
+            t.get_requires_grad(),
+            ReduceSumOperation { t: t.clone() },
+        )
+    });
 }
 
 pub struct ReduceSumOperation {
@@ -24,22 +38,24 @@ pub struct ReduceSumOperation {
 }
 
 impl Backward for ReduceSumOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-        let length = self.t.get_data_ref().len();
-        self.t.do_backward(
-            Some(new_tensor_simple(
-                self.t.get_shape(),
-                vec![(*grad.get_data_ref())[0]; length],
-            )),
-            None,
-        );
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(broadcast(grad, self.t.get_shape()).unwrap())]
+    }
+}
+
+impl Forward for ReduceSumOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        tangents[0].clone().map(|t| reduce_sum(t).unwrap())
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn reduce_sum(&self) -> Tensor {
-        reduce_sum(self.clone())
+    pub fn reduce_sum(&self) -> PyResult<Tensor> {
+        Ok(reduce_sum(self.clone())?)
     }
 }
@@ -1,19 +1,26 @@
 use crate::{
     backward::Backward,
+    dispatch_float,
+    dtype::Numeric,
+    errors::Result,
+    forward::Forward,
+    grad_mode::is_grad_enabled,
+    kernels::elementwise::map,
     objects::Tensor,
     utils::{new_tensor_simple, new_tensor_with_graph},
-    DTYPE,
 };
 use pyo3::prelude::*;
 
-pub fn relu(t: Tensor) -> Tensor {
-    let data: Vec<DTYPE> = t.get_data_ref().iter().map(|&x| x.max(0.0)).collect();
-    return new_tensor_with_graph(
-        t.get_shape(),
-        data,
-        t.get_requires_grad(),
-        ReluOperation { t: t.clone() },
-    );
+pub fn relu(t: Tensor) -> Result<Tensor> {
+    return dispatch_float!(t.dtype, T => {
+        let data: Vec<T> = map([&t], |[x]: [T; 1]| if x > T::ZERO { x } else { T::ZERO });
+        new_tensor_with_graph(
+            t.get_shape(),
+            data,
+            t.get_requires_grad(),
+            ReluOperation { t: t.clone() },
+        )
+    });
 }
 
 pub struct ReluOperation {
@@ -21,24 +28,44 @@ pub struct ReluOperation {
 }
 
 impl Backward for ReluOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-        let relu_grad = self
-            .t
-            .get_data_ref()
-            .iter()
-            .map(|&x| if x > 0.0 { 1.0 } else { 0.0 })
-            .zip(grad.get_data_ref().iter())
-            .map(|(a, b)| a * b)
-            .collect::<Vec<DTYPE>>();
-        self.t
-            .do_backward(Some(new_tensor_simple(self.t.get_shape(), relu_grad)), None);
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        if is_grad_enabled() {
+            /* With create_graph, multiply by the derivative as a constant mask */
+            return vec![Some(grad * self.mask())];
+        }
+        dispatch_float!(self.t.dtype, T => {
+            let relu_grad: Vec<T> =
+                map([&self.t, &grad], |[x, g]: [T; 2]| if x > T::ZERO { g } else { T::ZERO });
+            vec![Some(new_tensor_simple(self.t.get_shape(), relu_grad))]
+        })
+        .unwrap()
+    }
+}
+
+impl Forward for ReluOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        tangents[0].clone().map(|t| t * self.mask())
+    }
+}
+
+impl ReluOperation {
+    /// The derivative of relu, 1 where the input is positive and 0 elsewhere.
+    fn mask(&self) -> Tensor {
+        dispatch_float!(self.t.dtype, T => {
+            let mask: Vec<T> = map([&self.t], |[x]: [T; 1]| if x > T::ZERO { T::ONE } else { T::ZERO });
+            new_tensor_simple(self.t.get_shape(), mask)
+        })
+        .unwrap()
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn relu(&self) -> Tensor {
-        relu(self.clone())
+    pub fn relu(&self) -> PyResult<Tensor> {
+        Ok(relu(self.clone())?)
     }
 }
@@ -1,85 +1,295 @@
 use crate::{
     backward::Backward,
+    dispatch_float,
+    dtype::{Float, Numeric},
+    errors::Result,
+    forward::Forward,
+    grad_mode::is_grad_enabled,
     objects::Tensor,
-    utils::{new_tensor_simple, new_tensor_with_graph},
-    DTYPE,
+    operations::{
+        reduce::{output_shape, sum},
+        unary::exp,
+        view::reshape,
+    },
+    utils::{new_tensor_simple, new_tensor_with_graph, normalize_dim},
 };
 use pyo3::prelude::*;
 
-pub fn softmax(t: Tensor) -> Tensor {
-    let shape = t.get_shape();
-    let data = t.get_data_ref();
-    let dim = shape.last().copied().unwrap_or(1);
-    let outer = data.len() / dim;
+/* Normalizations along one dimension. The data is seen as `outer` blocks of
+ * `size` lanes of `inner` contiguous elements: the elements of a lane are
+ * `inner` apart, and the kernels handle one lane at a time. */
 
-    let mut softmax_data = Vec::with_capacity(data.len());
+/// Splits `shape` around `dim` into the number of blocks, the size of `dim`
+/// and the number of lanes per block.
+fn lanes(shape: &[usize], dim: usize) -> (usize, usize, usize) {
+    let outer = shape[..dim].iter().product();
+    let inner = shape[dim + 1..].iter().product();
+    (outer, shape[dim], inner)
+}
 
-    for i in 0..outer {
-        let start = i * dim;
-        let end = start + dim;
-        let slice = &data[start..end];
+/// Calls `f` with the indices of the elements of each lane.
+fn for_each_lane<F: FnMut(usize, &mut dyn Iterator<Item = usize>)>(
+    (outer, size, inner): (usize, usize, usize),
+    mut f: F,
+) {
+    for o in 0..outer {
+        for i in 0..inner {
+            let start = o * size * inner + i;
+            f(o * inner + i, &mut (0..size).map(|k| start + k * inner));
+        }
+    }
+}
 
-        let max_val = slice.iter().cloned().fold(DTYPE::NEG_INFINITY, DTYPE::max);
-        let exp_slice: Vec<DTYPE> = slice.iter().map(|&x| (x - max_val).exp()).collect();
-        let sum_exp: DTYPE = exp_slice.iter().sum();
-        softmax_data.extend(exp_slice.iter().map(|&x| x / sum_exp));
+/// The maximum of a lane, or 0 if it is -inf so that shifting by it is safe.
+fn lane_shift<T: Float>(data: &[T], lane: &[usize]) -> T {
+    let max = lane.iter().map(|&j| data[j]).fold(T::NEG_INFINITY, T::max);
+    if max == T::NEG_INFINITY {
+        T::ZERO
+    } else {
+        max
     }
+}
+
+/// `log(sum(exp(x)))` of each lane, shifted by the maximum for stability.
+fn logsumexp_kernel<T: Float>(data: &[T], lanes: (usize, usize, usize)) -> Vec<T> {
+    let mut result = vec![T::ZERO; lanes.0 * lanes.2];
+    for_each_lane(lanes, |l, lane| {
+        let lane: Vec<usize> = lane.collect();
+        let shift = lane_shift(data, &lane);
+        let sum_exp: T = lane.iter().map(|&j| (data[j] - shift).exp()).sum();
+        result[l] = shift + sum_exp.ln();
+    });
+    result
+}
+
+fn softmax_kernel<T: Float>(data: &[T], lanes: (usize, usize, usize)) -> Vec<T> {
+    let mut softmax_data = vec![T::ZERO; data.len()];
+    for_each_lane(lanes, |_, lane| {
+        let lane: Vec<usize> = lane.collect();
+        let shift = lane_shift(data, &lane);
+        let mut sum_exp = T::ZERO;
+        for &j in lane.iter() {
+            softmax_data[j] = (data[j] - shift).exp();
+            sum_exp += softmax_data[j];
+        }
+        for &j in lane.iter() {
+            softmax_data[j] /= sum_exp;
+        }
+    });
+    softmax_data
+}
+
+fn log_softmax_kernel<T: Float>(data: &[T], lanes: (usize, usize, usize)) -> Vec<T> {
+    let logsumexp = logsumexp_kernel(data, lanes);
+    let mut log_softmax_data = vec![T::ZERO; data.len()];
+    for_each_lane(lanes, |l, lane| {
+        for j in lane {
+            log_softmax_data[j] = data[j] - logsumexp[l];
+        }
+    });
+    log_softmax_data
+}
 
-    new_tensor_with_graph(
-        shape,
-        softmax_data,
+/// Normalizes `t` along `dim` so that the elements of each lane sum to 1.
+pub fn softmax(t: Tensor, dim: isize) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let dim = normalize_dim(dim, &shape)?;
+
+    return dispatch_float!(t.dtype, T => new_tensor_with_graph(
+        shape.clone(),
+        softmax_kernel(&t.get_data_ref::<T>(), lanes(&shape, dim)),
+        t.get_requires_grad(),
+        SoftmaxOperation { t: t.clone(), dim },
+    ));
+}
+
+/// The logarithm of the softmax of `t` along `dim`, computed without
+/// overflowing or taking the logarithm of 0.
+pub fn log_softmax(t: Tensor, dim: isize) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let dim = normalize_dim(dim, &shape)?;
+
+    return dispatch_float!(t.dtype, T => new_tensor_with_graph(
+        shape.clone(),
+        log_softmax_kernel(&t.get_data_ref::<T>(), lanes(&shape, dim)),
         t.get_requires_grad(),
-        SoftmaxOperation { t: t.clone() },
-    )
+        LogSoftmaxOperation { t: t.clone(), dim },
+    ));
 }
 
+/// `log(sum(exp(t)))` along `dim`, computed without overflowing.
+pub fn logsumexp(t: Tensor, dim: isize, keepdim: bool) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let dim = normalize_dim(dim, &shape)?;
+    let reduced: Vec<bool> = (0..shape.len()).map(|d| d == dim).collect();
+
+    return dispatch_float!(t.dtype, T => new_tensor_with_graph(
+        output_shape(&shape, &reduced, keepdim),
+        logsumexp_kernel(&t.get_data_ref::<T>(), lanes(&shape, dim)),
+        t.get_requires_grad(),
+        LogSumExpOperation { t: t.clone(), dim },
+    ));
+}
+
+/* The backward passes only need sums along lanes: the Jacobian of a lane is
+ * never materialized. With create_graph, the same formulas are built from
+ * differentiable operations. */
+
 pub struct SoftmaxOperation {
     t: Tensor,
+    dim: usize,
 }
 
 impl Backward for SoftmaxOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, input: Option<Tensor>) {
-        let grad = grad.unwrap();
-        let input = input.unwrap();
-        let shape = input.get_shape();
-        let dim = shape.last().copied().unwrap_or(1);
-        let outer = input.get_data_ref().len() / dim;
-
-        let mut softmax_grad = Vec::with_capacity(input.get_data_ref().len());
-
-        let input_data = input.get_data_ref();
-        let grad_data = grad.get_data_ref();
-
-        for i in 0..outer {
-            let start = i * dim;
-            let end = start + dim;
-            let input_slice = &input_data[start..end];
-            let grad_slice = &grad_data[start..end];
-
-            let prod_sum: DTYPE = input_slice
-                .iter()
-                .zip(grad_slice.iter())
-                .map(|(x, g)| x * g)
-                .sum();
-
-            softmax_grad.extend(
-                input_slice
-                    .iter()
-                    .zip(grad_slice.iter())
-                    .map(|(x, g)| x * (g - prod_sum)),
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        /* dx = y * (g - sum(y * g)) */
+        if is_grad_enabled() {
+            let prod_sum = sum(
+                output.clone() * grad.clone(),
+                Some(vec![self.dim as isize]),
+                true,
             );
+            return vec![Some(output * (grad - prod_sum.unwrap()))];
         }
+        let shape = self.t.get_shape();
+        dispatch_float!(output.dtype, T => {
+            let (y, g) = (output.get_data_ref::<T>(), grad.get_data_ref::<T>());
+            let mut softmax_grad = vec![T::ZERO; y.len()];
+            for_each_lane(lanes(&shape, self.dim), |_, lane| {
+                let lane: Vec<usize> = lane.collect();
+                let prod_sum: T = lane.iter().map(|&j| y[j] * g[j]).sum();
+                for &j in lane.iter() {
+                    softmax_grad[j] = y[j] * (g[j] - prod_sum);
+                }
+            });
+            vec![Some(new_tensor_simple(shape.clone(), softmax_grad))]
+        })
+        .unwrap()
+    }
+}
 
-        self.t.do_backward(
-            Some(new_tensor_simple(self.t.get_shape(), softmax_grad)),
-            None,
+impl Forward for SoftmaxOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        /* dy = y * (dx - sum(y * dx)) */
+        let t = tangents[0].clone()?;
+        let prod_sum = sum(
+            output.clone() * t.clone(),
+            Some(vec![self.dim as isize]),
+            true,
         );
+        Some(output.clone() * (t - prod_sum.unwrap()))
+    }
+}
+
+pub struct LogSoftmaxOperation {
+    t: Tensor,
+    dim: usize,
+}
+
+impl Backward for LogSoftmaxOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        /* dx = g - softmax(x) * sum(g), with softmax(x) = exp(y) */
+        if is_grad_enabled() {
+            let grad_sum = sum(grad.clone(), Some(vec![self.dim as isize]), true).unwrap();
+            return vec![Some(grad - exp(output).unwrap() * grad_sum)];
+        }
+        let shape = self.t.get_shape();
+        dispatch_float!(output.dtype, T => {
+            let (y, g) = (output.get_data_ref::<T>(), grad.get_data_ref::<T>());
+            let mut log_softmax_grad = vec![T::ZERO; y.len()];
+            for_each_lane(lanes(&shape, self.dim), |_, lane| {
+                let lane: Vec<usize> = lane.collect();
+                let grad_sum: T = lane.iter().map(|&j| g[j]).sum();
+                for &j in lane.iter() {
+                    log_softmax_grad[j] = g[j] - y[j].exp() * grad_sum;
+                }
+            });
+            vec![Some(new_tensor_simple(shape.clone(), log_softmax_grad))]
+        })
+        .unwrap()
+    }
+}
+
+impl Forward for LogSoftmaxOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        /* dy = dx - sum(softmax(x) * dx) */
+        let t = tangents[0].clone()?;
+        let softmax = exp(output.clone()).unwrap();
+        let prod_sum = sum(softmax * t.clone(), Some(vec![self.dim as isize]), true);
+        Some(t - prod_sum.unwrap())
+    }
+}
+
+pub struct LogSumExpOperation {
+    t: Tensor,
+    dim: usize,
+}
+
+impl Backward for LogSumExpOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        /* dx = g * exp(x - y), the softmax of the lane */
+        let shape = self.t.get_shape();
+        if is_grad_enabled() {
+            let mut kept: Vec<isize> = shape.iter().map(|&d| d as isize).collect();
+            kept[self.dim] = 1;
+            let grad = reshape(grad, kept.clone()).unwrap();
+            let output = reshape(output, kept).unwrap();
+            return vec![Some(grad * exp(self.t.clone() - output).unwrap())];
+        }
+        dispatch_float!(output.dtype, T => {
+            let x = self.t.get_data_ref::<T>();
+            let (y, g) = (output.get_data_ref::<T>(), grad.get_data_ref::<T>());
+            let mut logsumexp_grad = vec![T::ZERO; x.len()];
+            for_each_lane(lanes(&shape, self.dim), |l, lane| {
+                for j in lane {
+                    logsumexp_grad[j] = g[l] * (x[j] - y[l]).exp();
+                }
+            });
+            vec![Some(new_tensor_simple(shape.clone(), logsumexp_grad))]
+        })
+        .unwrap()
+    }
+}
+
+impl Forward for LogSumExpOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        /* dy = sum(exp(x - y) * dx), the softmax of the lane times the tangent */
+        let t = tangents[0].clone()?;
+        let mut kept: Vec<isize> = self.t.get_shape().iter().map(|&d| d as isize).collect();
+        kept[self.dim] = 1;
+        let softmax = exp(self.t.clone() - reshape(output.clone(), kept).unwrap()).unwrap();
+        let prod_sum = sum(softmax * t, Some(vec![self.dim as isize]), true).unwrap();
+        let shape = output.get_shape().iter().map(|&d| d as isize).collect();
+        Some(reshape(prod_sum, shape).unwrap())
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn softmax(&self) -> Tensor {
-        softmax(self.clone())
+    #[pyo3(signature = (dim=-1))]
+    pub fn softmax(&self, dim: isize) -> PyResult<Tensor> {
+        Ok(softmax(self.clone(), dim)?)
+    }
+
+    #[pyo3(signature = (dim=-1))]
+    pub fn log_softmax(&self, dim: isize) -> PyResult<Tensor> {
+        Ok(log_softmax(self.clone(), dim)?)
+    }
+
+    #[pyo3(signature = (dim, keepdim=false))]
+    pub fn logsumexp(&self, dim: isize, keepdim: bool) -> PyResult<Tensor> {
+        Ok(logsumexp(self.clone(), dim, keepdim)?)
     }
 }
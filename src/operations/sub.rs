@@ -1,18 +1,37 @@
-use crate::objects::Tensor;
+use crate::{
+    errors::Result,
+    objects::Tensor,
+    operations::{add::add, neg::neg},
+    utils::{in_place, promote_types, Operand},
+};
 use pyo3::prelude::*;
 use std::ops::Sub;
 
+pub fn sub(lhs: Tensor, rhs: Tensor) -> Result<Tensor> {
+    let (lhs, rhs) = promote_types(lhs, rhs)?;
+    return add(lhs, neg(rhs)?);
+}
+
+/// Panics where `sub` fails, see `+`.
 impl Sub for Tensor {
     type Output = Tensor;
 
     fn sub(self, rhs: Tensor) -> Tensor {
-        return self + (-rhs);
+        sub(self, rhs).expect("Operands of `-` must broadcast and not both be bool")
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn __sub__(&self, other: Tensor) -> Tensor {
-        self.clone() - other
+    pub fn __sub__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(sub(self.clone(), other.into_tensor(self))?)
+    }
+
+    pub fn __rsub__(&self, other: Operand) -> PyResult<Tensor> {
+        Ok(sub(other.into_tensor(self), self.clone())?)
+    }
+
+    pub fn __isub__(&self, other: Operand) -> PyResult<()> {
+        Ok(in_place(self, sub(self.clone(), other.into_tensor(self))?)?)
     }
 }
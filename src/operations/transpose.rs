@@ -1,70 +1,92 @@
 use crate::{
     backward::Backward,
-    objects::{strides, Tensor},
-    utils::new_tensor_with_graph,
+    errors::{AutogradError, Result},
+    forward::Forward,
+    objects::Tensor,
+    utils::{new_view_with_graph, normalize_dim},
 };
 use pyo3::prelude::*;
 
-pub fn transpose(t: Tensor) -> Tensor {
+/// Reorders the dimensions of `t` without copying: dimension `i` of the result
+/// is dimension `dims[i]` of `t`.
+pub fn permute(t: Tensor, dims: Vec<isize>) -> Result<Tensor> {
     let shape = t.get_shape();
-    if shape.len() < 2 {
-        panic!("Transpose is only defined for tensors with at least 2 dimensions");
+    let strides = t.get_strides();
+    if dims.len() != shape.len() {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Permutation {:?} does not match tensor with shape {:?}",
+            dims, shape
+        )));
     }
-    let new_shape = {
-        let mut new_shape = shape.clone();
-        new_shape[shape.len() - 1] = shape[shape.len() - 2];
-        new_shape[shape.len() - 2] = shape[shape.len() - 1];
-        new_shape
-    };
-    let size = new_shape.iter().product();
-    let ndim = new_shape.len();
-
-    let mut data = vec![0.0; size];
-    let old_strides = strides(&shape);
-    let new_strides = strides(&new_shape);
-
-    // iterate over the old shape indices
-    for i in 0..size {
-        // compute ndim index in old shape
-        let mut old_index = vec![0; ndim];
-        for j in 0..ndim {
-            old_index[j] = (i / old_strides[j]) % shape[j];
+    let mut permutation = Vec::with_capacity(dims.len());
+    for &dim in dims.iter() {
+        let dim = normalize_dim(dim, &shape)?;
+        if permutation.contains(&dim) {
+            return Err(AutogradError::ShapeMismatch(format!(
+                "Permutation {:?} repeats dimension {}",
+                dims, dim
+            )));
         }
-        let mut new_index = old_index.clone();
-        // swap the last two indices
-        new_index[ndim - 1] = old_index[ndim - 2];
-        new_index[ndim - 2] = old_index[ndim - 1];
-        // compute linear index for the new shape
-        let mut new_linear_index = 0;
-        for j in 0..ndim {
-            new_linear_index += new_index[j] * new_strides[j];
-        }
-        // assign the value
-        data[new_linear_index] = t.get_data_ref()[i];
+        permutation.push(dim);
     }
 
-    return new_tensor_with_graph(
-        new_shape,
-        data,
-        t.get_requires_grad(),
-        TransposeOperation { t: t.clone() },
-    );
+    return Ok(new_view_with_graph(
+        &t,
+        permutation.iter().map(|&d| shape[d]).collect(),
+        permutation.iter().map(|&d| strides[d]).collect(),
+        t.get_offset(),
+        PermuteOperation {
+            t: t.clone(),
+            permutation,
+        },
+    ));
 }
 
-pub struct TransposeOperation {
+/// Swaps two dimensions of `t` without copying.
+pub fn transpose(t: Tensor, dim0: isize, dim1: isize) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let (dim0, dim1) = (normalize_dim(dim0, &shape)?, normalize_dim(dim1, &shape)?);
+    let mut dims: Vec<isize> = (0..shape.len() as isize).collect();
+    dims.swap(dim0, dim1);
+    return permute(t, dims);
+}
+
+pub struct PermuteOperation {
     t: Tensor,
+    permutation: Vec<usize>,
+}
+
+impl Backward for PermuteOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        let mut inverse = vec![0; self.permutation.len()];
+        for (i, &d) in self.permutation.iter().enumerate() {
+            inverse[d] = i as isize;
+        }
+        vec![Some(permute(grad, inverse).unwrap())]
+    }
 }
 
-impl Backward for TransposeOperation {
-    fn do_backward(&mut self, grad: Option<Tensor>, _: Option<Tensor>) {
-        let grad = grad.unwrap();
-        self.t.do_backward(Some(grad.transpose()), None);
+impl Forward for PermuteOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        let permutation = self.permutation.iter().map(|&d| d as isize).collect();
+        tangents[0]
+            .clone()
+            .map(|t| permute(t, permutation).unwrap())
     }
 }
 
 #[pymethods]
 impl Tensor {
-    pub fn transpose(&self) -> Tensor {
-        transpose(self.clone())
+    pub fn permute(&self, dims: Vec<isize>) -> PyResult<Tensor> {
+        Ok(permute(self.clone(), dims)?)
+    }
+
+    #[pyo3(signature = (dim0=-2, dim1=-1))]
+    pub fn transpose(&self, dim0: isize, dim1: isize) -> PyResult<Tensor> {
+        Ok(transpose(self.clone(), dim0, dim1)?)
     }
 }
@@ -0,0 +1,462 @@
+use crate::{
+    backward::Backward,
+    dispatch_float,
+    dtype::{DType, Element, Float, Numeric},
+    errors::Result,
+    forward::Forward,
+    grad_mode::is_grad_enabled,
+    kernels::elementwise::map,
+    objects::Tensor,
+    operations::cast::to,
+    utils::{new_tensor_simple, new_tensor_with_graph, Operand},
+};
+use pyo3::prelude::*;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_2_SQRT_PI};
+
+/* Differentiable elementwise functions. Each function knows its derivative at
+ * an element given the input and output values, so they share a single
+ * backward operation. */
+
+#[derive(Clone, Copy, Debug)]
+pub enum Unary {
+    Exp,
+    Log,
+    Log1p,
+    Sqrt,
+    Rsqrt,
+    Sin,
+    Cos,
+    Tanh,
+    Sigmoid,
+    Abs,
+    Sign,
+    Gelu,
+    Silu,
+    LeakyRelu { negative_slope: f64 },
+    Elu { alpha: f64 },
+    Softplus { beta: f64, threshold: f64 },
+    Clamp { min: Option<f64>, max: Option<f64> },
+    Huber { delta: f64 },
+    XLogX,
+    NormalCdf,
+}
+
+/// The cumulative distribution function of a standard normal.
+fn normal_cdf<T: Float>(x: T) -> T {
+    T::from_f64(0.5) * (T::ONE + (x * T::from_f64(FRAC_1_SQRT_2)).erf())
+}
+
+/// The density of a standard normal.
+fn normal_pdf<T: Float>(x: T) -> T {
+    let half = T::from_f64(0.5);
+    (-half * x * x).exp() * T::from_f64(0.5 * FRAC_2_SQRT_PI * FRAC_1_SQRT_2)
+}
+
+fn sigmoid_of<T: Float>(x: T) -> T {
+    T::ONE / (T::ONE + (-x).exp())
+}
+
+/// The sign of `x`, which is 0 for 0.
+fn sign_of<T: Float>(x: T) -> T {
+    if x > T::ZERO {
+        T::ONE
+    } else if x < T::ZERO {
+        -T::ONE
+    } else {
+        T::ZERO
+    }
+}
+
+impl Unary {
+    fn forward<T: Float>(self, x: T) -> T {
+        match self {
+            Unary::Exp => x.exp(),
+            Unary::Log => x.ln(),
+            Unary::Log1p => x.ln_1p(),
+            Unary::Sqrt => x.sqrt(),
+            Unary::Rsqrt => T::ONE / x.sqrt(),
+            Unary::Sin => x.sin(),
+            Unary::Cos => x.cos(),
+            Unary::Tanh => x.tanh(),
+            Unary::Sigmoid => sigmoid_of(x),
+            Unary::Abs => x.abs(),
+            Unary::Sign => sign_of(x),
+            Unary::Gelu => x * normal_cdf(x),
+            Unary::Silu => x * sigmoid_of(x),
+            Unary::LeakyRelu { negative_slope } => {
+                if x > T::ZERO {
+                    x
+                } else {
+                    x * T::from_f64(negative_slope)
+                }
+            }
+            Unary::Elu { alpha } => {
+                if x > T::ZERO {
+                    x
+                } else {
+                    T::from_f64(alpha) * (x.exp() - T::ONE)
+                }
+            }
+            Unary::Softplus { beta, threshold } => {
+                let (beta, threshold) = (T::from_f64(beta), T::from_f64(threshold));
+                /* Revert to the identity where exp would overflow */
+                if x * beta > threshold {
+                    x
+                } else {
+                    (x * beta).exp().ln_1p() / beta
+                }
+            }
+            Unary::Clamp { min, max } => {
+                let x = min.map_or(x, |min| x.max(T::from_f64(min)));
+                max.map_or(x, |max| x.min(T::from_f64(max)))
+            }
+            Unary::Huber { delta } => {
+                /* Quadratic near 0 and linear beyond delta */
+                let delta = T::from_f64(delta);
+                let half = T::from_f64(0.5);
+                if x.abs() <= delta {
+                    half * x * x
+                } else {
+                    delta * (x.abs() - half * delta)
+                }
+            }
+            Unary::NormalCdf => normal_cdf(x),
+            Unary::XLogX => {
+                /* Extended by continuity at 0 */
+                if x == T::ZERO {
+                    T::ZERO
+                } else {
+                    x * x.ln()
+                }
+            }
+        }
+    }
+
+    /// The derivative of the function at `x`, where it evaluates to `y`.
+    fn derivative<T: Float>(self, x: T, y: T) -> T {
+        let half = T::from_f64(0.5);
+        match self {
+            Unary::Exp => y,
+            Unary::Log => T::ONE / x,
+            Unary::Log1p => T::ONE / (T::ONE + x),
+            Unary::Sqrt => half / y,
+            Unary::Rsqrt => -half * y * y * y,
+            Unary::Sin => x.cos(),
+            Unary::Cos => -x.sin(),
+            Unary::Tanh => T::ONE - y * y,
+            Unary::Sigmoid => y * (T::ONE - y),
+            Unary::Abs => sign_of(x),
+            Unary::Sign => T::ZERO,
+            Unary::Gelu => normal_cdf(x) + x * normal_pdf(x),
+            Unary::Silu => {
+                let s = sigmoid_of(x);
+                s * (T::ONE + x * (T::ONE - s))
+            }
+            Unary::LeakyRelu { negative_slope } => {
+                if x > T::ZERO {
+                    T::ONE
+                } else {
+                    T::from_f64(negative_slope)
+                }
+            }
+            Unary::Elu { alpha } => {
+                if x > T::ZERO {
+                    T::ONE
+                } else {
+                    y + T::from_f64(alpha)
+                }
+            }
+            Unary::Softplus { beta, threshold } => {
+                let (beta, threshold) = (T::from_f64(beta), T::from_f64(threshold));
+                if x * beta > threshold {
+                    T::ONE
+                } else {
+                    sigmoid_of(x * beta)
+                }
+            }
+            Unary::Clamp { min, max } => {
+                let above_min = min.is_none_or(|min| x >= T::from_f64(min));
+                let below_max = max.is_none_or(|max| x <= T::from_f64(max));
+                if above_min && below_max {
+                    T::ONE
+                } else {
+                    T::ZERO
+                }
+            }
+            Unary::Huber { delta } => {
+                let delta = T::from_f64(delta);
+                if x.abs() <= delta {
+                    x
+                } else {
+                    delta * sign_of(x)
+                }
+            }
+            Unary::XLogX => x.ln() + T::ONE,
+            Unary::NormalCdf => normal_pdf(x),
+        }
+    }
+
+    /// The derivative at every element of `x`, built from differentiable
+    /// operations so that create_graph can differentiate it again. Piecewise
+    /// constant parts don't depend on `x` and are computed as constants.
+    fn derivative_tensor(self, x: Tensor, y: Tensor) -> Result<Tensor> {
+        let like = x.clone();
+        let c = |value: f64| Operand::Float(value).into_tensor(&like);
+        Ok(match self {
+            Unary::Exp => y,
+            Unary::Log => c(1.0) / x,
+            Unary::Log1p => c(1.0) / (c(1.0) + x),
+            Unary::Sqrt => c(0.5) / y,
+            Unary::Rsqrt => c(-0.5) * y.clone() * y.clone() * y,
+            Unary::Sin => cos(x)?,
+            Unary::Cos => -sin(x)?,
+            Unary::Tanh => c(1.0) - y.clone() * y,
+            Unary::Sigmoid => y.clone() * (c(1.0) - y),
+            Unary::Gelu => normal_cdf_tensor(x.clone())? + x.clone() * normal_pdf_tensor(x)?,
+            Unary::Silu => {
+                let s = sigmoid(x.clone())?;
+                s.clone() * (c(1.0) + x * (c(1.0) - s))
+            }
+            Unary::Elu { alpha } => {
+                let positive = indicator(&x, |x| x > 0.0);
+                positive.clone() + (c(1.0) - positive) * (y + c(alpha))
+            }
+            Unary::Softplus { beta, threshold } => {
+                let linear = indicator(&x, |x| x * beta > threshold);
+                linear.clone() + (c(1.0) - linear) * sigmoid(x * c(beta))?
+            }
+            Unary::Huber { delta } => clamp(x, Some(-delta), Some(delta))?,
+            Unary::XLogX => log(x)? + c(1.0),
+            Unary::NormalCdf => normal_pdf_tensor(x)?,
+            Unary::Abs | Unary::Sign | Unary::LeakyRelu { .. } | Unary::Clamp { .. } => {
+                dispatch_float!(x.dtype, T => {
+                    let derivative: Vec<T> = map([&x, &y], |[x, y]: [T; 2]| self.derivative(x, y));
+                    new_tensor_simple(x.get_shape(), derivative)
+                })?
+            }
+        })
+    }
+}
+
+/// A constant tensor with 1 where `condition` holds for `x` and 0 elsewhere.
+fn indicator<F: Fn(f64) -> bool + Sync>(x: &Tensor, condition: F) -> Tensor {
+    dispatch_float!(x.dtype, T => {
+        let data: Vec<T> = map([x], |[x]: [T; 1]| if condition(x.to_f64()) { T::ONE } else { T::ZERO });
+        new_tensor_simple(x.get_shape(), data)
+    })
+    .unwrap()
+}
+
+fn normal_cdf_tensor(x: Tensor) -> Result<Tensor> {
+    unary(x, Unary::NormalCdf)
+}
+
+/// `exp(-x^2 / 2) / sqrt(2 * pi)`
+fn normal_pdf_tensor(x: Tensor) -> Result<Tensor> {
+    let like = x.clone();
+    let c = |value: f64| Operand::Float(value).into_tensor(&like);
+    let scale = c(0.5 * FRAC_2_SQRT_PI * FRAC_1_SQRT_2);
+    Ok(exp(c(-0.5) * x.clone() * x)? * scale)
+}
+
+/// Applies `op` to every element of `t`. Integer and bool tensors are
+/// converted to float32 first.
+pub fn unary(t: Tensor, op: Unary) -> Result<Tensor> {
+    let t = if t.dtype.is_floating_point() {
+        t
+    } else {
+        to(t, DType::Float32)
+    };
+
+    return dispatch_float!(t.dtype, T => {
+        let data: Vec<T> = map([&t], |[x]: [T; 1]| op.forward(x));
+        new_tensor_with_graph(
+            t.get_shape(),
+            data,
+            t.get_requires_grad(),
+            UnaryOperation { t: t.clone(), op },
+        )
+    });
+}
+
+pub fn exp(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Exp)
+}
+
+pub fn log(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Log)
+}
+
+pub fn log1p(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Log1p)
+}
+
+pub fn sqrt(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Sqrt)
+}
+
+pub fn rsqrt(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Rsqrt)
+}
+
+pub fn sin(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Sin)
+}
+
+pub fn cos(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Cos)
+}
+
+pub fn tanh(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Tanh)
+}
+
+pub fn sigmoid(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Sigmoid)
+}
+
+pub fn abs(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Abs)
+}
+
+pub fn sign(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Sign)
+}
+
+/// The exact GELU, `x * P(X <= x)` for a standard normal X.
+pub fn gelu(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Gelu)
+}
+
+pub fn silu(t: Tensor) -> Result<Tensor> {
+    unary(t, Unary::Silu)
+}
+
+pub fn leaky_relu(t: Tensor, negative_slope: f64) -> Result<Tensor> {
+    unary(t, Unary::LeakyRelu { negative_slope })
+}
+
+pub fn elu(t: Tensor, alpha: f64) -> Result<Tensor> {
+    unary(t, Unary::Elu { alpha })
+}
+
+/// `log(1 + exp(beta * x)) / beta`, which is `x` where `beta * x > threshold`.
+pub fn softplus(t: Tensor, beta: f64, threshold: f64) -> Result<Tensor> {
+    unary(t, Unary::Softplus { beta, threshold })
+}
+
+/// Clamps the elements of `t` to `[min, max]`. Either bound can be omitted.
+pub fn clamp(t: Tensor, min: Option<f64>, max: Option<f64>) -> Result<Tensor> {
+    unary(t, Unary::Clamp { min, max })
+}
+
+pub struct UnaryOperation {
+    t: Tensor,
+    op: Unary,
+}
+
+impl Backward for UnaryOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, output: Tensor) -> Vec<Option<Tensor>> {
+        if is_grad_enabled() {
+            let derivative = self.op.derivative_tensor(self.t.clone(), output).unwrap();
+            return vec![Some(grad * derivative)];
+        }
+        dispatch_float!(self.t.dtype, T => {
+            let op = self.op;
+            let unary_grad: Vec<T> =
+                map([&self.t, &output, &grad], |[x, y, g]: [T; 3]| g * op.derivative(x, y));
+            vec![Some(new_tensor_simple(self.t.get_shape(), unary_grad))]
+        })
+        .unwrap()
+    }
+}
+
+impl Forward for UnaryOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let derivative = self.op.derivative_tensor(self.t.clone(), output.clone());
+        tangents[0].clone().map(|t| t * derivative.unwrap())
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn exp(&self) -> PyResult<Tensor> {
+        Ok(exp(self.clone())?)
+    }
+
+    pub fn log(&self) -> PyResult<Tensor> {
+        Ok(log(self.clone())?)
+    }
+
+    pub fn log1p(&self) -> PyResult<Tensor> {
+        Ok(log1p(self.clone())?)
+    }
+
+    pub fn sqrt(&self) -> PyResult<Tensor> {
+        Ok(sqrt(self.clone())?)
+    }
+
+    pub fn rsqrt(&self) -> PyResult<Tensor> {
+        Ok(rsqrt(self.clone())?)
+    }
+
+    pub fn sin(&self) -> PyResult<Tensor> {
+        Ok(sin(self.clone())?)
+    }
+
+    pub fn cos(&self) -> PyResult<Tensor> {
+        Ok(cos(self.clone())?)
+    }
+
+    pub fn tanh(&self) -> PyResult<Tensor> {
+        Ok(tanh(self.clone())?)
+    }
+
+    pub fn sigmoid(&self) -> PyResult<Tensor> {
+        Ok(sigmoid(self.clone())?)
+    }
+
+    pub fn abs(&self) -> PyResult<Tensor> {
+        Ok(abs(self.clone())?)
+    }
+
+    pub fn __abs__(&self) -> PyResult<Tensor> {
+        Ok(abs(self.clone())?)
+    }
+
+    pub fn sign(&self) -> PyResult<Tensor> {
+        Ok(sign(self.clone())?)
+    }
+
+    pub fn gelu(&self) -> PyResult<Tensor> {
+        Ok(gelu(self.clone())?)
+    }
+
+    pub fn silu(&self) -> PyResult<Tensor> {
+        Ok(silu(self.clone())?)
+    }
+
+    #[pyo3(signature = (negative_slope=0.01))]
+    pub fn leaky_relu(&self, negative_slope: f64) -> PyResult<Tensor> {
+        Ok(leaky_relu(self.clone(), negative_slope)?)
+    }
+
+    #[pyo3(signature = (alpha=1.0))]
+    pub fn elu(&self, alpha: f64) -> PyResult<Tensor> {
+        Ok(elu(self.clone(), alpha)?)
+    }
+
+    #[pyo3(signature = (beta=1.0, threshold=20.0))]
+    pub fn softplus(&self, beta: f64, threshold: f64) -> PyResult<Tensor> {
+        Ok(softplus(self.clone(), beta, threshold)?)
+    }
+
+    #[pyo3(signature = (min=None, max=None))]
+    pub fn clamp(&self, min: Option<f64>, max: Option<f64>) -> PyResult<Tensor> {
+        Ok(clamp(self.clone(), min, max)?)
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    backward::Backward,
+    dispatch_all,
+    errors::{AutogradError, Result},
+    forward::Forward,
+    objects::{strided_index_map, strides, Tensor},
+    operations::reduce::{scatter, take},
+    utils::{new_tensor_with_graph, new_view_with_graph, normalize_dim},
+};
+use pyo3::prelude::*;
+
+/* Operations returning views of a tensor. They share the storage of their input
+ * and only compute a new shape, strides and offset. */
+
+/// Resolves a shape where one dimension can be -1 for a tensor of `size` elements.
+fn infer_shape(shape: &[isize], size: usize) -> Result<Vec<usize>> {
+    let invalid = || {
+        AutogradError::ShapeMismatch(format!(
+            "Shape {:?} is invalid for a tensor of {} elements",
+            shape, size
+        ))
+    };
+    let mut inferred = None;
+    let mut known = 1;
+    for (i, &dim) in shape.iter().enumerate() {
+        if dim == -1 && inferred.is_none() {
+            inferred = Some(i);
+        } else if dim >= 0 {
+            known *= dim as usize;
+        } else {
+            return Err(invalid());
+        }
+    }
+    let mut resolved: Vec<usize> = shape.iter().map(|&dim| dim.max(0) as usize).collect();
+    match inferred {
+        Some(i) if known != 0 && size.is_multiple_of(known) => resolved[i] = size / known,
+        None if known == size => {}
+        _ => return Err(invalid()),
+    }
+    Ok(resolved)
+}
+
+/// Views `t` with another shape. `t` must be contiguous.
+pub fn view(t: Tensor, shape: Vec<isize>) -> Result<Tensor> {
+    let shape = infer_shape(&shape, t.get_shape().iter().product())?;
+    if !t.is_contiguous() {
+        return Err(AutogradError::ShapeMismatch(format!(
+            "Cannot view tensor with shape {:?} and strides {:?} as {:?}, use reshape instead",
+            t.get_shape(),
+            t.get_strides(),
+            shape
+        )));
+    }
+    return Ok(new_view_with_graph(
+        &t,
+        shape.clone(),
+        strides(&shape),
+        t.get_offset(),
+        ReshapeOperation { t: t.clone() },
+    ));
+}
+
+/// Gives `t` another shape, copying it only if it is not contiguous.
+pub fn reshape(t: Tensor, shape: Vec<isize>) -> Result<Tensor> {
+    if t.is_contiguous() {
+        return view(t, shape);
+    }
+    return view(contiguous(t), shape);
+}
+
+/// Removes dimensions of size 1, either `dim` or all of them.
+pub fn squeeze(t: Tensor, dim: Option<isize>) -> Result<Tensor> {
+    let shape = t.get_shape();
+    let strides = t.get_strides();
+    let dim = dim.map(|dim| normalize_dim(dim, &shape)).transpose()?;
+    let kept: Vec<usize> = (0..shape.len())
+        .filter(|&d| shape[d] != 1 || dim.is_some_and(|dim| dim != d))
+        .collect();
+    let (mut new_shape, mut new_strides): (Vec<usize>, Vec<usize>) =
+        kept.iter().map(|&d| (shape[d], strides[d])).unzip();
+    if new_shape.is_empty() {
+        /* Scalars have shape [1] */
+        new_shape = vec![1];
+        new_strides = vec![1];
+    }
+    return Ok(new_view_with_graph(
+        &t,
+        new_shape,
+        new_strides,
+        t.get_offset(),
+        ReshapeOperation { t: t.clone() },
+    ));
+}
+
+/// Inserts a dimension of size 1 at position `dim`.
+pub fn unsqueeze(t: Tensor, dim: isize) -> Result<Tensor> {
+    let mut shape = t.get_shape();
+    let mut strides = t.get_strides();
+    /* The new dimension can be inserted after the last one */
+    let dim = if dim < 0 {
+        dim + shape.len() as isize + 1
+    } else {
+        dim
+    };
+    if dim < 0 || dim > shape.len() as isize {
+        return Err(AutogradError::DimensionOutOfRange {
+            dim,
+            shape: shape.clone(),
+        });
+    }
+    let dim = dim as usize;
+    let stride = if dim < shape.len() {
+        shape[dim] * strides[dim]
+    } else {
+        1
+    };
+    shape.insert(dim, 1);
+    strides.insert(dim, stride);
+    return Ok(new_view_with_graph(
+        &t,
+        shape,
+        strides,
+        t.get_offset(),
+        ReshapeOperation { t: t.clone() },
+    ));
+}
+
+pub struct ReshapeOperation {
+    t: Tensor,
+}
+
+impl Backward for ReshapeOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        let shape = self.t.get_shape().iter().map(|&d| d as isize).collect();
+        vec![Some(reshape(grad, shape).unwrap())]
+    }
+}
+
+impl Forward for ReshapeOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let shape = output.get_shape().iter().map(|&d| d as isize).collect();
+        tangents[0].clone().map(|t| reshape(t, shape).unwrap())
+    }
+}
+
+/// Resolves the bounds of a slice of a dimension of `size` elements, following
+/// Python conventions: they can be negative and are clamped. Returns the first
+/// selected index and the number of selected elements.
+pub fn resolve_slice(
+    size: usize,
+    start: Option<isize>,
+    end: Option<isize>,
+    step: usize,
+) -> Result<(usize, usize)> {
+    if step == 0 {
+        return Err(AutogradError::ShapeMismatch(
+            "Slice step must be positive".to_string(),
+        ));
+    }
+    let size = size as isize;
+    let resolve = |bound: isize| {
+        let bound = if bound < 0 { bound + size } else { bound };
+        bound.clamp(0, size) as usize
+    };
+    let start = resolve(start.unwrap_or(0));
+    let end = resolve(end.unwrap_or(size)).max(start);
+    Ok((start, (end - start).div_ceil(step)))
+}
+
+/// Selects the elements `start`, `start + step`, ... before `end` along `dim`.
+/// Bounds follow Python conventions: they can be negative and are clamped.
+pub fn slice(
+    t: Tensor,
+    dim: isize,
+    start: Option<isize>,
+    end: Option<isize>,
+    step: usize,
+) -> Result<Tensor> {
+    let mut shape = t.get_shape();
+    let mut strides = t.get_strides();
+    let dim = normalize_dim(dim, &shape)?;
+    let (start, size) = resolve_slice(shape[dim], start, end, step)?;
+
+    let offset = t.get_offset() + start * strides[dim];
+    shape[dim] = size;
+    strides[dim] *= step;
+    return Ok(new_view_with_graph(
+        &t,
+        shape,
+        strides,
+        offset,
+        SliceOperation {
+            t: t.clone(),
+            dim,
+            start,
+            step,
+        },
+    ));
+}
+
+pub struct SliceOperation {
+    t: Tensor,
+    dim: usize,
+    start: usize,
+    step: usize,
+}
+
+impl Backward for SliceOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        /* Scatter the gradient in a zero gradient with the shape of the input */
+        let indices = self.indices(&grad.get_shape());
+        vec![Some(scatter(&grad, self.t.get_shape(), indices).unwrap())]
+    }
+}
+
+impl Forward for SliceOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], output: &Tensor) -> Option<Tensor> {
+        let indices = self.indices(&output.get_shape());
+        tangents[0]
+            .as_ref()
+            .map(|t| take(t, output.get_shape(), indices))
+    }
+}
+
+impl SliceOperation {
+    /// The row-major index in the input of every element of a slice of shape
+    /// `shape`.
+    fn indices(&self, shape: &[usize]) -> Vec<usize> {
+        let mut slice_strides = strides(&self.t.get_shape());
+        let offset = self.start * slice_strides[self.dim];
+        slice_strides[self.dim] *= self.step;
+        strided_index_map(shape, &slice_strides, offset)
+    }
+}
+
+/// Returns `t` if it is contiguous, and a contiguous copy otherwise.
+pub fn contiguous(t: Tensor) -> Tensor {
+    if t.is_contiguous() {
+        return t;
+    }
+    return dispatch_all!(t.dtype, T => new_tensor_with_graph(
+        t.get_shape(),
+        t.get_data_ref::<T>().to_vec(),
+        t.get_requires_grad(),
+        ContiguousOperation { t: t.clone() },
+    ));
+}
+
+pub struct ContiguousOperation {
+    t: Tensor,
+}
+
+impl Backward for ContiguousOperation {
+    fn inputs(&self) -> Vec<Tensor> {
+        vec![self.t.clone()]
+    }
+
+    fn do_backward(&mut self, grad: Tensor, _: Tensor) -> Vec<Option<Tensor>> {
+        vec![Some(grad)]
+    }
+}
+
+impl Forward for ContiguousOperation {
+    fn do_forward(&self, tangents: &[Option<Tensor>], _: &Tensor) -> Option<Tensor> {
+        tangents[0].clone()
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    pub fn view(&self, shape: Vec<isize>) -> PyResult<Tensor> {
+        Ok(view(self.clone(), shape)?)
+    }
+
+    pub fn reshape(&self, shape: Vec<isize>) -> PyResult<Tensor> {
+        Ok(reshape(self.clone(), shape)?)
+    }
+
+    #[pyo3(signature = (dim=None))]
+    pub fn squeeze(&self, dim: Option<isize>) -> PyResult<Tensor> {
+        Ok(squeeze(self.clone(), dim)?)
+    }
+
+    pub fn unsqueeze(&self, dim: isize) -> PyResult<Tensor> {
+        Ok(unsqueeze(self.clone(), dim)?)
+    }
+
+    #[pyo3(signature = (dim, start=None, end=None, step=1))]
+    pub fn slice(
+        &self,
+        dim: isize,
+        start: Option<isize>,
+        end: Option<isize>,
+        step: usize,
+    ) -> PyResult<Tensor> {
+        Ok(slice(self.clone(), dim, start, end, step)?)
+    }
+
+    pub fn contiguous(&self) -> Tensor {
+        contiguous(self.clone())
+    }
+}
@@ -0,0 +1,72 @@
+use pyo3::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::{Arc, RwLock};
+
+use crate::errors::{AutogradError, Result};
+
+/* The pool running the kernels and the independent branches of the backward
+ * pass, so that the number of threads stays bounded however deep the graph is.
+ * Rayon's global pool can't be resized, so this one is replaced by
+ * `set_num_threads`, and work already started finishes on the previous one.
+ * The grad mode is local to each thread: work sent to the pool must set it on
+ * the worker if it records operations. */
+
+static POOL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+
+/// A pool of `num_threads` workers, or one per CPU for 0.
+fn build(num_threads: usize) -> Result<Arc<ThreadPool>> {
+    ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("autograd-{}", i))
+        .build()
+        .map(Arc::new)
+        .map_err(|error| {
+            AutogradError::InvalidArgument(format!("Cannot start the thread pool: {}", error))
+        })
+}
+
+pub fn pool() -> Arc<ThreadPool> {
+    if let Some(pool) = POOL.read().unwrap().as_ref() {
+        return pool.clone();
+    }
+    POOL.write()
+        .unwrap()
+        .get_or_insert_with(|| build(0).unwrap())
+        .clone()
+}
+
+/// Runs `f` on the pool, so that the parallel iterators it uses are scheduled
+/// there. On a worker of the pool, `f` runs directly.
+pub fn install<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    pool().install(f)
+}
+
+pub fn set_num_threads(num_threads: usize) -> Result<()> {
+    if num_threads == 0 {
+        return Err(AutogradError::InvalidArgument(
+            "The number of threads must be positive".to_string(),
+        ));
+    }
+    *POOL.write().unwrap() = Some(build(num_threads)?);
+    Ok(())
+}
+
+pub fn get_num_threads() -> usize {
+    pool().current_num_threads()
+}
+
+#[pyfunction(name = "set_num_threads")]
+pub fn py_set_num_threads(num_threads: usize) -> PyResult<()> {
+    Ok(set_num_threads(num_threads)?)
+}
+
+#[pyfunction(name = "get_num_threads")]
+pub fn py_get_num_threads() -> usize {
+    get_num_threads()
+}
+
+pub fn register_functions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_set_num_threads, m)?)?;
+    m.add_function(wrap_pyfunction!(py_get_num_threads, m)?)?;
+    Ok(())
+}
@@ -1,43 +1,172 @@
+use pyo3::prelude::*;
 use std::sync::{Arc, RwLock};
 
 use crate::{
     backward::Backward,
-    objects::{Graph, Tensor},
-    operations::broadcast::broadcast,
-    DTYPE,
+    dispatch_all,
+    dtype::{DType, Element},
+    errors::{AutogradError, Result},
+    forward::{forward_tangent, Forward},
+    grad_mode::is_grad_enabled,
+    objects::{Graph, Node, Tensor},
+    operations::{
+        broadcast::{broadcast, broadcast_shapes},
+        cast::to,
+        index::index_put,
+    },
 };
 
-pub fn new_graph<T: Backward + Send + Sync + 'static>(op: T) -> Graph {
-    Graph(Arc::new(RwLock::new(op)))
+/// A node for the operation that computed `output`, remembering the versions
+/// of the tensors it saved.
+pub fn new_graph<T: Backward + Send + Sync + 'static>(op: T, output: &Tensor) -> Graph {
+    let versions = op
+        .inputs()
+        .iter()
+        .chain([output])
+        .map(|t| (t.version.clone(), t.version()))
+        .collect();
+    Graph(Arc::new(RwLock::new(Node {
+        name: op.name(),
+        operation: Some(Box::new(op)),
+        hooks: vec![],
+        versions,
+    })))
 }
 
-pub fn new_tensor_with_graph<T: Backward + Send + Sync + 'static>(
+pub fn new_tensor_with_graph<E: Element, T: Forward + Send + Sync + 'static>(
     shape: Vec<usize>,
-    data: Vec<DTYPE>,
+    data: Vec<E>,
     requires_grad: bool,
     node: T,
 ) -> Tensor {
-    Tensor::new(shape, data, requires_grad, None, Some(new_graph(node)))
+    /* The node keeps the inputs alive, so it is only recorded if needed */
+    let requires_grad = requires_grad && is_grad_enabled();
+    let mut tensor = Tensor::new(shape, data, requires_grad, None, None);
+    forward_tangent(&node, &tensor);
+    if requires_grad {
+        let graph = new_graph(node, &tensor);
+        tensor.set_graph(Some(graph));
+    }
+    tensor
 }
 
-pub fn new_tensor_simple(shape: Vec<usize>, data: Vec<DTYPE>) -> Tensor {
+pub fn new_tensor_simple<E: Element>(shape: Vec<usize>, data: Vec<E>) -> Tensor {
     Tensor::new(shape, data, false, None, None)
 }
 
-pub fn broadcast_to_same_dim(lhs: Tensor, rhs: Tensor) -> (Tensor, Tensor) {
-    if lhs.get_shape() != rhs.get_shape() {
-        if lhs.get_shape() == vec![1] {
-            return (broadcast(lhs.clone(), rhs.get_shape()), rhs);
-        } else if rhs.get_shape() == vec![1] {
-            return (lhs.clone(), broadcast(rhs.clone(), lhs.get_shape()));
-        } else if lhs.get_shape().len() == rhs.get_shape().len() + 1 {
-            return (lhs.clone(), broadcast(rhs.clone(), lhs.get_shape()));
-        } else if rhs.get_shape().len() == lhs.get_shape().len() + 1 {
-            return (broadcast(lhs.clone(), rhs.get_shape()), rhs.clone());
-        } else {
-            panic!("broadcast_to_same_dim could not broadcast tensors with different shapes: {:?} and {:?}", lhs.get_shape(), rhs.get_shape());
-        }
+/// Creates a view of `t` sharing its storage.
+pub fn new_view_with_graph<T: Forward + Send + Sync + 'static>(
+    t: &Tensor,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    offset: usize,
+    node: T,
+) -> Tensor {
+    let requires_grad = t.get_requires_grad() && is_grad_enabled();
+    let mut view = t.new_view(shape, strides, offset, requires_grad, None);
+    forward_tangent(&node, &view);
+    if requires_grad {
+        let graph = new_graph(node, &view);
+        view.set_graph(Some(graph));
+    }
+    view
+}
+
+/// Resolves a possibly negative dimension.
+pub fn normalize_dim(dim: isize, shape: &[usize]) -> Result<usize> {
+    let ndim = shape.len() as isize;
+    let normalized = if dim < 0 { dim + ndim } else { dim };
+    if normalized < 0 || normalized >= ndim {
+        return Err(AutogradError::DimensionOutOfRange {
+            dim,
+            shape: shape.to_vec(),
+        });
+    }
+    Ok(normalized as usize)
+}
+
+/// Casts both tensors to their common element type for arithmetic.
+pub fn promote_types(lhs: Tensor, rhs: Tensor) -> Result<(Tensor, Tensor)> {
+    let dtype = lhs.dtype.promote(rhs.dtype);
+    if dtype == DType::Bool {
+        return Err(AutogradError::DTypeMismatch(
+            "Arithmetic is not supported between bool tensors".to_string(),
+        ));
+    }
+    return Ok((to(lhs, dtype), to(rhs, dtype)));
+}
+
+/// Broadcasts both tensors to their common shape, following NumPy rules.
+pub fn broadcast_to_same_dim(lhs: Tensor, rhs: Tensor) -> Result<(Tensor, Tensor)> {
+    let (lhs_shape, rhs_shape) = (lhs.get_shape(), rhs.get_shape());
+    if lhs_shape == rhs_shape {
+        return Ok((lhs, rhs));
+    }
+    let shape = broadcast_shapes(&lhs_shape, &rhs_shape)?;
+    let lhs = if lhs_shape == shape {
+        lhs
+    } else {
+        broadcast(lhs, shape.clone())?
+    };
+    let rhs = if rhs_shape == shape {
+        rhs
     } else {
-        return (lhs, rhs);
+        broadcast(rhs, shape)?
+    };
+    return Ok((lhs, rhs));
+}
+
+/// A tensor or a Python number, as accepted by arithmetic operators.
+#[derive(FromPyObject)]
+pub enum Operand {
+    Tensor(Tensor),
+    Int(i64),
+    Float(f64),
+}
+
+impl Operand {
+    /// Converts the operand to a tensor to combine with `t`. Like in PyTorch,
+    /// numbers take the type of `t` unless it cannot represent them.
+    pub fn into_tensor(self, t: &Tensor) -> Tensor {
+        match self {
+            Operand::Tensor(tensor) => tensor,
+            Operand::Int(x) => {
+                let dtype = match t.dtype {
+                    DType::Bool => DType::Int64,
+                    dtype => dtype,
+                };
+                dispatch_all!(dtype, T => new_tensor_simple(vec![1], vec![x.cast::<T>()]))
+            }
+            Operand::Float(x) => {
+                let dtype = match t.dtype.is_floating_point() {
+                    true => t.dtype,
+                    false => DType::Float32,
+                };
+                dispatch_all!(dtype, T => new_tensor_simple(vec![1], vec![x.cast::<T>()]))
+            }
+        }
+    }
+}
+
+/// Writes `result`, computed from `t` by an operation, into the storage of `t`.
+/// In a dual level, `t` takes the tangent of the result.
+pub fn in_place(t: &Tensor, result: Tensor) -> Result<()> {
+    if result.get_requires_grad() {
+        return Err(AutogradError::InPlaceOnGrad(
+            "In-place operations are not supported on tensors that require grad, \
+             use no_grad to modify them"
+                .to_string(),
+        ));
+    }
+    if result.dtype != t.dtype {
+        return Err(AutogradError::DTypeMismatch(format!(
+            "Result of type {} cannot be written in place into a tensor of type {}",
+            result.dtype.name(),
+            t.dtype.name()
+        )));
     }
+    let tangent = result.get_tangent();
+    index_put(t, vec![], result)?;
+    t.set_tangent(tangent);
+    Ok(())
 }
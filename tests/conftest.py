@@ -0,0 +1,7 @@
+from autograd import Tensor
+
+
+def pair(t):
+    """A torch tensor and an autograd copy of it, both leaves requiring grad."""
+    t = t.detach().requires_grad_()
+    return t, Tensor.from_torch(t, requires_grad=True)
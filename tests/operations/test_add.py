@@ -6,6 +6,7 @@ from autograd import Tensor
 np.random.seed(42)
 torch.manual_seed(42)
 
+batch = 10
 n = 5
 m = 10
 
@@ -27,6 +28,14 @@ def test_add_forward_2():
     assert np.allclose(a + b[None, :], result, atol=1e-6, rtol=1e-6)
 
 
+def test_add_forward_3():
+    a = np.random.randn(batch, 1, m)
+    b = np.random.randn(1, n, m)
+    result = (Tensor.from_numpy(a) + Tensor.from_numpy(b)).to_numpy()
+
+    assert np.allclose(a + b, result, atol=1e-6, rtol=1e-6)
+
+
 def test_add_backward_1():
     shape = (n, m)
 
@@ -69,3 +78,24 @@ def test_add_backward_2():
     # Check gradients
     assert torch.allclose(a1.grad, a2.get_grad().to_torch())
     assert torch.allclose(b1.grad, b2.get_grad().to_torch())
+
+
+def test_add_backward_3():
+    # torch implementation
+    a1 = torch.randn((batch, 1, m), requires_grad=True)
+    b1 = torch.randn((1, n, m), requires_grad=True)
+    c1 = a1 + b1
+    grad1 = torch.rand_like(c1)
+    c1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+
+    c2 = a2 + b2
+    grad2 = Tensor.from_torch(grad1)
+    c2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch())
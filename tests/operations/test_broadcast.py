@@ -1,4 +1,5 @@
 import numpy as np
+import pytest
 import torch
 
 from autograd import Tensor
@@ -27,6 +28,14 @@ def test_broadcast_forward_2():
     assert np.allclose(np.broadcast_to(a, shape), result, atol=1e-6, rtol=1e-6)
 
 
+def test_broadcast_forward_3():
+    shape = (batch, n, m)
+    a = np.random.randn(n, 1)
+    result = (Tensor.from_numpy(a).broadcast(shape)).to_numpy()
+
+    assert np.allclose(np.broadcast_to(a, shape), result, atol=1e-6, rtol=1e-6)
+
+
 def test_broadcast_backward_1():
     shape = (n, m)
 
@@ -63,3 +72,53 @@ def test_broadcast_backward_2():
 
     # Check gradients
     assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_broadcast_backward_3():
+    shape = (batch, n, m)
+
+    # torch implementation
+    a1 = torch.randn(1, n, 1, requires_grad=True)
+    b1 = a1.broadcast_to(shape)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.broadcast(shape)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+@pytest.mark.parametrize(
+    "f",
+    [
+        lambda a, b: a + b,
+        lambda a, b: a * b,
+        lambda a, b: a - b / (b * b + 1),
+        lambda a, b: (a * b).relu(),
+        lambda a, b: (a + b).tanh(),
+    ],
+)
+@pytest.mark.parametrize(
+    "shape1,shape2",
+    [((300, 1), (200, 1)), ((200, 300), (300,)), ((200, 300), (300, 200))],
+)
+def test_broadcast_pointwise_large(f, shape1, shape2):
+    # Large enough to be split in chunks, with broadcast and transposed operands
+    a1 = torch.randn(*shape1, requires_grad=True)
+    b1 = torch.randn(*shape2, requires_grad=True)
+    c1 = f(a1, b1.T if len(shape2) == 2 else b1)
+    c1.backward(torch.ones_like(c1))
+
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+    c2 = f(a2, b2.transpose(0, 1) if len(shape2) == 2 else b2)
+    c2.backward(Tensor.from_torch(torch.ones_like(c1)))
+
+    assert torch.allclose(c1, c2.to_torch(), atol=1e-5)
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch(), atol=1e-3)
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch(), atol=1e-3)
@@ -0,0 +1,152 @@
+import numpy as np
+import pytest
+import torch
+
+from autograd import GradError, Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+batch = 10
+n = 5
+m = 10
+
+
+def test_basic_index_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.allclose(a[2], t[2].to_numpy())
+    assert np.allclose(a[-1, 1:4], t[-1, 1:4].to_numpy())
+    assert np.allclose(a[::3, :, 1::2], t[::3, :, 1::2].to_numpy())
+    assert np.allclose(a[..., 3], t[..., 3].to_numpy())
+    assert np.allclose(a[:, None, 2], t[:, None, 2].to_numpy())
+    assert t[1, 2, 3].get_shape() == [1]
+    assert t[1:3].get_offset() == n * m
+
+
+def test_advanced_index_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+    i = np.array([[0, 4], [2, 2]])
+    j = np.array([1, 3])
+
+    assert np.allclose(a[i], t[Tensor.from_numpy(i)].to_numpy())
+    assert np.allclose(
+        a[:, i, j], t[:, Tensor.from_numpy(i), Tensor.from_numpy(j)].to_numpy()
+    )
+    assert np.allclose(a[[0, 3], :, [1, 2]], t[[0, 3], :, [1, 2]].to_numpy())
+    assert np.allclose(a[1:, [4, 0]], t[1:, [4, 0]].to_numpy())
+
+    # Integers count as advanced indices, so these are separated by a slice
+    assert a[0, :, [0, 1]].shape == (2, n)
+    assert t[0, :, [0, 1]].get_shape() == [2, n]
+    assert np.allclose(a[0, :, [0, 1]], t[0, :, [0, 1]].to_numpy())
+    assert t[:, 0, [0, 1]].get_shape() == [batch, 2]
+    assert np.allclose(a[:, 0, [0, 1]], t[:, 0, [0, 1]].to_numpy())
+
+
+def test_mask_index_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+    mask = a[:, :, 0] > 0
+
+    assert np.allclose(a[mask], t[Tensor.from_numpy(mask)].to_numpy())
+    assert np.allclose(a[a > 0], t[Tensor.from_numpy(a > 0)].to_numpy())
+    rows = [True, False, True, False, True]
+    assert np.allclose(a[:, rows], t[:, rows].to_numpy())
+
+
+def test_index_errors():
+    t = Tensor.from_numpy(np.random.randn(n, m))
+
+    with pytest.raises(IndexError):
+        t[n]
+    with pytest.raises(IndexError):
+        t[0, 0, 0]
+    with pytest.raises(IndexError):
+        t[[0, n]]
+    with pytest.raises(TypeError):
+        t["a"]
+    with pytest.raises(IndexError, match="Negative slice steps"):
+        t[::-1]
+
+
+def test_index_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    b1 = a1[1:8:2, ..., 4] * a1[[0, 0, 3], 2:4, [1, 1, 5]].sum()
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2[1:8:2, ..., 4] * a2[[0, 0, 3], 2:4, [1, 1, 5]].sum()
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_mask_index_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    mask = a1.detach() > 0
+    b1 = a1[mask]
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2[Tensor.from_torch(mask)]
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_setitem():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+    view = t.transpose(0, 1)
+    b = np.random.randn(n, m)
+
+    a[1] = b
+    t[1] = Tensor.from_numpy(b)
+    a[:, 2, ::3] = 0.5
+    t[:, 2, ::3] = 0.5
+    a[[0, 4], 1, [2, 3]] = -1
+    t[[0, 4], 1, [2, 3]] = -1
+    a[a > 1] = 1
+    t[Tensor.from_numpy(a > 1)] = 1
+
+    assert np.allclose(a, t.to_numpy())
+    assert np.allclose(a.transpose((1, 0, 2)), view.to_numpy())
+
+
+def test_setitem_requires_grad():
+    t = Tensor.from_numpy(np.random.randn(n, m), requires_grad=True)
+
+    with pytest.raises(GradError):
+        t[0] = 0.0
+
+
+def test_setitem_saved():
+    a = Tensor.from_numpy(np.random.randn(n, m), requires_grad=True)
+    b = Tensor.from_numpy(np.random.randn(n, m))
+    c = (a * b).sum()
+    b[0] = 0.0
+    with pytest.raises(GradError):
+        c.backward(None)
+
+    # Views share the version of their storage, even detached ones
+    d = a.exp()
+    e = (d * 2).sum()
+    d.detach()[1, 2] = 0.0
+    with pytest.raises(GradError):
+        e.backward(None)
+
+    with pytest.raises(ValueError):
+        Tensor.from_numpy(np.random.randn(m)).expand([n, m])[0] = 1.0
@@ -0,0 +1,192 @@
+import pytest
+import torch
+import torch.nn.functional as F
+
+import autograd
+from autograd import Tensor
+from conftest import pair
+
+torch.manual_seed(42)
+
+batch = 8
+classes = 5
+
+REDUCTIONS = ["mean", "sum", "none"]
+
+
+def check(torch_loss, autograd_loss, inputs):
+    """Compares the losses and the gradients of `inputs`, pairs of tensors."""
+    assert torch.allclose(torch_loss, autograd_loss.to_torch(), atol=1e-6)
+
+    grad = torch.rand_like(torch_loss)
+    torch_loss.backward(grad)
+    autograd_loss.backward(Tensor.from_torch(grad.reshape(autograd_loss.get_shape())))
+    for t, a in inputs:
+        assert torch.allclose(t.grad, a.get_grad().to_torch(), atol=1e-6)
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS)
+@pytest.mark.parametrize(
+    "torch_fn, autograd_fn",
+    [
+        (F.mse_loss, autograd.mse_loss),
+        (F.l1_loss, autograd.l1_loss),
+        (F.huber_loss, autograd.huber_loss),
+        (F.smooth_l1_loss, autograd.smooth_l1_loss),
+    ],
+)
+def test_regression_loss(torch_fn, autograd_fn, reduction):
+    x1, x2 = pair(torch.randn(batch, classes) * 2)
+    y1, y2 = pair(torch.randn(batch, classes))
+
+    check(
+        torch_fn(x1, y1, reduction=reduction),
+        autograd_fn(x2, y2, reduction=reduction),
+        [(x1, x2), (y1, y2)],
+    )
+
+
+def test_huber_delta():
+    x1, x2 = pair(torch.randn(batch, classes) * 2)
+    y = torch.randn(batch, classes)
+
+    check(
+        F.huber_loss(x1, y, delta=0.5),
+        autograd.huber_loss(x2, Tensor.from_torch(y), delta=0.5),
+        [(x1, x2)],
+    )
+    x1, x2 = pair(x1)
+    check(
+        F.smooth_l1_loss(x1, y, beta=0.5),
+        autograd.smooth_l1_loss(x2, Tensor.from_torch(y), beta=0.5),
+        [(x1, x2)],
+    )
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS)
+def test_cross_entropy(reduction):
+    x1, x2 = pair(torch.randn(batch, classes))
+    y = torch.randint(0, classes, (batch,))
+
+    check(
+        F.cross_entropy(x1, y, reduction=reduction),
+        autograd.cross_entropy(x2, Tensor.from_torch(y), reduction=reduction),
+        [(x1, x2)],
+    )
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS)
+def test_cross_entropy_weight_ignore_index(reduction):
+    x1, x2 = pair(torch.randn(batch, classes))
+    y = torch.randint(0, classes, (batch,))
+    y[::3] = -1
+    weight = torch.rand(classes)
+
+    check(
+        F.cross_entropy(x1, y, weight=weight, ignore_index=-1, reduction=reduction),
+        autograd.cross_entropy(
+            x2,
+            Tensor.from_torch(y),
+            weight=Tensor.from_torch(weight),
+            ignore_index=-1,
+            reduction=reduction,
+        ),
+        [(x1, x2)],
+    )
+
+
+def test_cross_entropy_spatial():
+    x1, x2 = pair(torch.randn(batch, classes, 3, 2))
+    y = torch.randint(0, classes, (batch, 3, 2))
+
+    check(
+        F.cross_entropy(x1, y),
+        autograd.cross_entropy(x2, Tensor.from_torch(y)),
+        [(x1, x2)],
+    )
+
+
+def test_cross_entropy_probabilities():
+    x1, x2 = pair(torch.randn(batch, classes))
+    y = torch.rand(batch, classes).softmax(dim=1)
+    weight = torch.rand(classes)
+
+    check(
+        F.cross_entropy(x1, y, weight=weight),
+        autograd.cross_entropy(
+            x2, Tensor.from_torch(y), weight=Tensor.from_torch(weight)
+        ),
+        [(x1, x2)],
+    )
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS)
+def test_nll_loss(reduction):
+    x1, x2 = pair(torch.randn(batch, classes).log_softmax(dim=1))
+    y = torch.randint(0, classes, (batch,))
+    weight = torch.rand(classes)
+
+    check(
+        F.nll_loss(x1, y, weight=weight, reduction=reduction),
+        autograd.nll_loss(
+            x2,
+            Tensor.from_torch(y),
+            weight=Tensor.from_torch(weight),
+            reduction=reduction,
+        ),
+        [(x1, x2)],
+    )
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS)
+def test_binary_cross_entropy_with_logits(reduction):
+    x1, x2 = pair(torch.randn(batch, classes) * 10)
+    y = torch.rand(batch, classes)
+    weight = torch.rand(batch, classes)
+    pos_weight = torch.rand(classes) * 3
+
+    check(
+        F.binary_cross_entropy_with_logits(
+            x1, y, weight=weight, pos_weight=pos_weight, reduction=reduction
+        ),
+        autograd.binary_cross_entropy_with_logits(
+            x2,
+            Tensor.from_torch(y),
+            weight=Tensor.from_torch(weight),
+            pos_weight=Tensor.from_torch(pos_weight),
+            reduction=reduction,
+        ),
+        [(x1, x2)],
+    )
+
+
+@pytest.mark.parametrize("reduction", REDUCTIONS + ["batchmean"])
+@pytest.mark.parametrize("log_target", [False, True])
+def test_kl_div(reduction, log_target):
+    x1, x2 = pair(torch.randn(batch, classes).log_softmax(dim=1))
+    y = torch.randn(batch, classes).softmax(dim=1)
+    if log_target:
+        y = y.log()
+    else:
+        y[0, 0] = 0.0
+    y1, y2 = pair(y)
+
+    check(
+        F.kl_div(x1, y1, reduction=reduction, log_target=log_target),
+        autograd.kl_div(x2, y2, reduction=reduction, log_target=log_target),
+        [(x1, x2)],
+    )
+
+
+def test_loss_errors():
+    x = Tensor.from_torch(torch.randn(batch, classes))
+    y = Tensor.from_torch(torch.randint(0, classes, (batch,)))
+
+    with pytest.raises(ValueError):
+        autograd.cross_entropy(x, y, reduction="average")
+    with pytest.raises(IndexError):
+        autograd.cross_entropy(x, Tensor.from_torch(torch.full((batch,), classes)))
+    with pytest.raises(autograd.ShapeError):
+        autograd.cross_entropy(x, Tensor.from_torch(torch.zeros(batch + 1).long()))
+    with pytest.raises(autograd.DTypeError):
+        autograd.nll_loss(x, Tensor.from_torch(torch.zeros(batch).bool()))
@@ -1,7 +1,9 @@
 import numpy as np
+import pytest
 import torch
 
-from autograd import Tensor
+import autograd
+from autograd import ShapeError, Tensor
 
 np.random.seed(42)
 torch.manual_seed(42)
@@ -78,3 +80,117 @@ def test_matmul_backward_2():
     # Check gradients
     assert torch.allclose(a1.grad, a2.get_grad().to_torch())
     assert torch.allclose(b1.grad, b2.get_grad().to_torch())
+
+
+@pytest.mark.parametrize(
+    "shape1,shape2",
+    [
+        ((m,), (m,)),
+        ((m,), (m, p)),
+        ((n, m), (m,)),
+        ((batch, n, m), (m,)),
+        ((m,), (batch, m, p)),
+        ((n, m), (batch, m, p)),
+        ((2, 1, n, m), (3, m, p)),
+        ((2, 3, n, m), (2, 3, m, p)),
+    ],
+)
+def test_matmul_broadcast(shape1, shape2):
+    # torch implementation
+    a1 = torch.randn(*shape1, requires_grad=True)
+    b1 = torch.randn(*shape2, requires_grad=True)
+    c1 = a1 @ b1
+    grad1 = torch.randn_like(c1)
+    c1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+    c2 = a2 @ b2
+    # Scalars have shape [1] here and [] in torch
+    c2.backward(Tensor.from_torch(grad1.reshape(c2.get_shape())))
+
+    assert torch.allclose(c1.reshape(c2.get_shape()), c2.to_torch(), atol=1e-5)
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch(), atol=1e-5)
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch(), atol=1e-5)
+
+
+@pytest.mark.parametrize(
+    "f,shapes",
+    [
+        (lambda a, b: a.dot(b), [(m,), (m,)]),
+        (lambda a, b: a.mv(b), [(n, m), (m,)]),
+        (lambda a, b: a.outer(b), [(n,), (m,)]),
+        (lambda a, b: a.bmm(b), [(batch, n, m), (batch, m, p)]),
+        (lambda i, a, b: i.addmm(a, b), [(p,), (n, m), (m, p)]),
+        (lambda i, a, b: i.addmm(a, b, beta=0.5, alpha=2), [(n, p), (n, m), (m, p)]),
+    ],
+)
+def test_matmul_helpers(f, shapes):
+    inputs1 = [torch.randn(*shape, requires_grad=True) for shape in shapes]
+    inputs2 = [Tensor.from_torch(t, requires_grad=True) for t in inputs1]
+    c1, c2 = f(*inputs1), f(*inputs2)
+    c1.sum().backward()
+    c2.sum().backward()
+
+    assert torch.allclose(c1.reshape(c2.get_shape()), c2.to_torch(), atol=1e-5)
+    for t1, t2 in zip(inputs1, inputs2):
+        assert torch.allclose(t1.grad, t2.get_grad().to_torch(), atol=1e-5)
+
+
+def test_matmul_errors():
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.ones((n, m))) @ Tensor.from_numpy(np.ones((p, n)))
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.ones(m)).dot(Tensor.from_numpy(np.ones(n)))
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.ones((2, n, m))) @ Tensor.from_numpy(np.ones((3, m, p)))
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.ones((2, n, m))).bmm(Tensor.from_numpy(np.ones((3, m, p))))
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.array(2.0)) @ Tensor.from_numpy(np.ones(n))
+    with pytest.raises(ShapeError):
+        Tensor.from_numpy(np.ones((n, m))) @ Tensor.from_numpy(np.array(1.0))
+
+
+@pytest.mark.parametrize(
+    "shape1,shape2",
+    [
+        ((1, 1), (1, 1)),
+        ((7, 3), (3, 17)),
+        ((97, 300), (300, 33)),
+        ((3, 2), (2, 4100)),
+        ((4, 13, 260), (260, 9)),
+    ],
+)
+@pytest.mark.parametrize(
+    "dtype,torch_dtype",
+    [
+        (autograd.float32, torch.float32),
+        (autograd.float64, torch.float64),
+        (autograd.int64, torch.int64),
+    ],
+)
+def test_matmul_blocks(shape1, shape2, dtype, torch_dtype):
+    # Shapes crossing the tiles and blocks of the kernel
+    a1 = torch.randint(-3, 4, shape1).to(torch_dtype)
+    b1 = torch.randint(-3, 4, shape2).to(torch_dtype)
+    c2 = Tensor.from_torch(a1, dtype=dtype) @ Tensor.from_torch(b1, dtype=dtype)
+
+    assert torch.equal(a1 @ b1, c2.to_torch())
+
+
+def test_matmul_transposed():
+    a1 = torch.randn(m, n, requires_grad=True)
+    b1 = torch.randn(p, m, requires_grad=True)
+    c1 = a1.transpose(0, 1) @ b1.transpose(0, 1)
+    c1.backward(torch.ones_like(c1))
+
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+    c2 = a2.transpose(0, 1) @ b2.transpose(0, 1)
+    c2.backward(Tensor.from_torch(torch.ones_like(c1)))
+
+    assert torch.allclose(c1, c2.to_torch(), atol=1e-5)
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch(), atol=1e-5)
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch(), atol=1e-5)
@@ -0,0 +1,64 @@
+import numpy as np
+import torch
+
+from autograd import Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+n = 5
+m = 10
+
+
+def test_pow_forward_1():
+    a = np.abs(np.random.randn(n, m))
+    b = np.random.randn(n, m)
+    result = (Tensor.from_numpy(a) ** Tensor.from_numpy(b)).to_numpy()
+
+    assert np.allclose(a**b, result)
+
+
+def test_pow_forward_2():
+    a = np.random.randn(n, m)
+    result = (Tensor.from_numpy(a) ** 3).to_numpy()
+
+    assert np.allclose(a**3, result)
+    result = Tensor.from_numpy(np.abs(a)).pow(0.5).to_numpy()
+    assert np.allclose(np.abs(a) ** 0.5, result)
+
+
+def test_pow_backward_1():
+    # torch implementation
+    a1 = torch.rand(n, m, requires_grad=True)
+    b1 = torch.randn(m, requires_grad=True)
+    c1 = a1**b1
+    grad1 = torch.rand_like(c1)
+    c1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+    c2 = a2**b2
+    grad2 = Tensor.from_torch(grad1)
+    c2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch(), atol=1e-5)
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch(), atol=1e-5)
+
+
+def test_pow_backward_2():
+    # torch implementation
+    a1 = torch.randn(n, m, requires_grad=True)
+    b1 = a1**2
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2**2
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
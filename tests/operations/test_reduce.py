@@ -0,0 +1,134 @@
+import numpy as np
+import pytest
+import torch
+
+from autograd import Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+batch = 10
+n = 5
+m = 10
+
+
+def test_sum_forward():
+    a = np.random.randn(batch, n, m)
+    result = Tensor.from_numpy(a).sum(1).to_numpy()
+
+    assert np.allclose(a.sum(axis=1), result, atol=1e-5, rtol=1e-5)
+
+
+def test_sum_forward_keepdim():
+    a = np.random.randn(batch, n, m)
+    result = Tensor.from_numpy(a).sum((0, 2), keepdim=True).to_numpy()
+
+    assert np.allclose(a.sum(axis=(0, 2), keepdims=True), result, atol=1e-5, rtol=1e-5)
+
+
+def test_mean_forward():
+    a = np.random.randn(batch, n, m)
+    result = Tensor.from_numpy(a).mean(-1).to_numpy()
+
+    assert np.allclose(a.mean(axis=-1), result, atol=1e-6, rtol=1e-6)
+
+
+def test_max_min_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.allclose(a.max(axis=1), t.max(1).to_numpy(), atol=1e-6, rtol=1e-6)
+    assert np.allclose(a.min(axis=2), t.min(2).to_numpy(), atol=1e-6, rtol=1e-6)
+
+
+def test_argmax_argmin_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.array_equal(a.argmax(axis=1), t.argmax(1).to_numpy())
+    assert np.array_equal(a.argmin(axis=-1), t.argmin(-1).to_numpy())
+    assert a.argmax() == t.argmax().to_numpy()[0]
+
+
+def test_invalid_reductions():
+    empty = Tensor.from_numpy(np.zeros((n, 0)))
+    for select in [empty.max, empty.min, empty.argmax, empty.argmin]:
+        with pytest.raises(ValueError):
+            select(1)
+    with pytest.raises(ValueError):
+        empty.argmax()
+    assert empty.max(0).get_shape() == [0]
+    with pytest.raises(ValueError):
+        Tensor.from_numpy(np.random.randn(n, m)).sum((1, -1))
+
+
+def test_sum_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    b1 = a1.sum(dim=(0, 2), keepdim=True)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.sum((0, 2), keepdim=True)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_mean_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    b1 = a1.mean(dim=1)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.mean(1)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_max_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    b1 = a1.max(dim=2).values
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.max(2)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_prod_backward():
+    # torch implementation
+    a1 = torch.randn(n, m)
+    a1[0, 0] = 0.0
+    a1[1, 0] = 0.0
+    a1[1, 1] = 0.0
+    a1.requires_grad_(True)
+    b1 = a1.prod(dim=1)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.prod(1)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
@@ -1,6 +1,7 @@
 import numpy as np
 import torch
 
+import autograd
 from autograd import Tensor
 
 np.random.seed(42)
@@ -41,3 +42,40 @@ def test_softmax_backward():
 
     # Check gradients
     assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_softmax_dim_forward():
+    a = torch.randn(batch, n, 3, dtype=torch.float64)
+    t = Tensor.from_torch(a, dtype=autograd.float64)
+
+    for dim in [0, 1, -1]:
+        assert torch.allclose(a.softmax(dim), t.softmax(dim).to_torch())
+        assert torch.allclose(a.log_softmax(dim), t.log_softmax(dim).to_torch())
+        assert torch.allclose(a.logsumexp(dim), t.logsumexp(dim).to_torch())
+    assert t.logsumexp(1, keepdim=True).get_shape() == [batch, 1, 3]
+
+
+def test_log_softmax_stability():
+    a = torch.tensor([[1000.0, 0.0, -1000.0]], dtype=torch.float64)
+    t = Tensor.from_torch(a, dtype=autograd.float64)
+
+    assert torch.allclose(a.log_softmax(1), t.log_softmax(1).to_torch())
+    assert torch.allclose(a.logsumexp(1), t.logsumexp(1).to_torch())
+
+
+def test_softmax_dim_backward():
+    for name in ["softmax", "log_softmax", "logsumexp"]:
+        # torch implementation
+        a1 = torch.randn(batch, n, 3, requires_grad=True)
+        b1 = getattr(a1, name)(1)
+        grad1 = torch.rand_like(b1)
+        b1.backward(grad1)
+
+        # autograd implementation
+        a2 = Tensor.from_torch(a1, requires_grad=True)
+        b2 = getattr(a2, name)(1)
+        grad2 = Tensor.from_torch(grad1)
+        b2.backward(grad2)
+
+        # Check gradients
+        assert torch.allclose(a1.grad, a2.get_grad().to_torch(), atol=1e-6)
@@ -27,6 +27,22 @@ def test_transpose_forward_2():
     assert np.allclose(a.transpose((0, 2, 1)), result, atol=1e-6, rtol=1e-6)
 
 
+def test_transpose_forward_3():
+    shape = (batch, n, m)
+    a = np.random.randn(*shape)
+    result = (Tensor.from_numpy(a).transpose(0, 2)).to_numpy()
+
+    assert np.allclose(a.transpose((2, 1, 0)), result, atol=1e-6, rtol=1e-6)
+
+
+def test_permute_forward():
+    shape = (batch, n, m)
+    a = np.random.randn(*shape)
+    result = (Tensor.from_numpy(a).permute([1, -1, 0])).to_numpy()
+
+    assert np.allclose(a.transpose((1, 2, 0)), result, atol=1e-6, rtol=1e-6)
+
+
 def test_transpose_backward_1():
     shape = (n, m)
 
@@ -63,3 +79,22 @@ def test_transpose_backward_2():
 
     # Check gradients
     assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_permute_backward():
+    shape = (batch, n, m)
+
+    # torch implementation
+    a1 = torch.randn(*shape, requires_grad=True)
+    b1 = a1.permute(2, 0, 1)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.permute([2, 0, 1])
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
@@ -0,0 +1,74 @@
+import numpy as np
+import pytest
+import torch
+import torch.nn.functional as F
+
+import autograd
+from autograd import Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+n = 5
+m = 10
+
+# (autograd method, keyword arguments, torch function, input transform)
+functions = [
+    ("exp", {}, torch.exp, None),
+    ("log", {}, torch.log, torch.abs),
+    ("log1p", {}, torch.log1p, torch.abs),
+    ("sqrt", {}, torch.sqrt, torch.abs),
+    ("rsqrt", {}, torch.rsqrt, torch.abs),
+    ("sin", {}, torch.sin, None),
+    ("cos", {}, torch.cos, None),
+    ("tanh", {}, torch.tanh, None),
+    ("sigmoid", {}, torch.sigmoid, None),
+    ("abs", {}, torch.abs, None),
+    ("sign", {}, torch.sign, None),
+    ("gelu", {}, F.gelu, None),
+    ("silu", {}, F.silu, None),
+    ("leaky_relu", {"negative_slope": 0.1}, lambda x: F.leaky_relu(x, 0.1), None),
+    ("elu", {"alpha": 0.5}, lambda x: F.elu(x, 0.5), None),
+    ("softplus", {"beta": 2.0}, lambda x: F.softplus(x, beta=2.0), None),
+    ("clamp", {"min": -0.5, "max": 0.5}, lambda x: x.clamp(-0.5, 0.5), None),
+]
+
+
+@pytest.mark.parametrize("name, kwargs, torch_function, transform", functions)
+def test_unary_forward(name, kwargs, torch_function, transform):
+    a = torch.randn(n, m, dtype=torch.float64)
+    if transform is not None:
+        a = transform(a) + 0.1
+    result = getattr(Tensor.from_torch(a, dtype=autograd.float64), name)(**kwargs).to_torch()
+
+    assert torch.allclose(torch_function(a), result)
+
+
+@pytest.mark.parametrize("name, kwargs, torch_function, transform", functions)
+def test_unary_backward(name, kwargs, torch_function, transform):
+    a = torch.randn(n, m, dtype=torch.float64)
+    if transform is not None:
+        a = transform(a) + 0.1
+
+    # torch implementation
+    a1 = a.clone().requires_grad_(True)
+    b1 = torch_function(a1)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a, requires_grad=True, dtype=autograd.float64)
+    b2 = getattr(a2, name)(**kwargs)
+    grad2 = Tensor.from_torch(grad1, dtype=autograd.float64)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_integer_input():
+    a = np.arange(n)
+    result = Tensor.from_numpy(a).exp().to_numpy()
+
+    assert result.dtype == np.float32
+    assert np.allclose(np.exp(a), result)
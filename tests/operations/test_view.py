@@ -0,0 +1,107 @@
+import numpy as np
+import pytest
+import torch
+
+from autograd import ShapeError, Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+batch = 10
+n = 5
+m = 10
+
+
+def test_views_share_storage():
+    a = Tensor.from_numpy(np.random.randn(batch, n, m))
+    b = a.transpose(0, 2)
+    c = b.slice(0, 1, None, 2)
+
+    assert not b.is_contiguous()
+    assert b.get_strides() == [1, m, n * m]
+    assert c.get_offset() == 1
+    assert c.get_strides() == [2, m, n * m]
+
+
+def test_reshape_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.allclose(a.reshape(batch, -1), t.view([batch, -1]).to_numpy())
+    assert np.allclose(
+        a.transpose((0, 2, 1)).reshape(-1, n),
+        t.transpose().reshape([-1, n]).to_numpy(),
+    )
+    with pytest.raises(ShapeError):
+        t.transpose().view([-1, n])
+
+
+def test_squeeze_unsqueeze_forward():
+    a = np.random.randn(n, 1, m, 1)
+    t = Tensor.from_numpy(a)
+
+    assert t.squeeze().get_shape() == [n, m]
+    assert t.squeeze(-1).get_shape() == [n, 1, m]
+    assert t.squeeze(0).get_shape() == [n, 1, m, 1]
+    assert t.unsqueeze(0).get_shape() == [1, n, 1, m, 1]
+    assert t.unsqueeze(-1).get_shape() == [n, 1, m, 1, 1]
+    assert np.allclose(a[:, None], t.unsqueeze(1).to_numpy())
+
+
+def test_slice_forward():
+    a = np.random.randn(batch, n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.allclose(a[:, 1:4], t.slice(1, 1, 4).to_numpy())
+    assert np.allclose(a[..., ::3], t.slice(2, step=3).to_numpy())
+    assert np.allclose(a[-3:], t.slice(0, -3).to_numpy())
+
+
+def test_expand_forward():
+    a = np.random.randn(n, 1)
+    result = Tensor.from_numpy(a).expand([batch, -1, m])
+
+    assert result.get_strides() == [0, 1, 0]
+    assert np.allclose(np.broadcast_to(a, (batch, n, m)), result.to_numpy())
+
+
+def test_contiguous_forward():
+    a = np.random.randn(batch, n, m)
+    result = Tensor.from_numpy(a).permute([2, 0, 1]).contiguous()
+
+    assert result.is_contiguous()
+    assert np.allclose(a.transpose((2, 0, 1)), result.to_numpy())
+
+
+def test_view_backward():
+    # torch implementation
+    a1 = torch.randn(batch, n, m, requires_grad=True)
+    b1 = a1.transpose(0, 1)[:, 2:8:2].reshape(n, -1).unsqueeze(0)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.transpose(0, 1).slice(1, 2, 8, 2).reshape([n, -1]).unsqueeze(0)
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+
+
+def test_expand_backward():
+    # torch implementation
+    a1 = torch.randn(n, 1, requires_grad=True)
+    b1 = a1.expand(batch, -1, m) * a1.expand(batch, -1, m)
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = a2.expand([batch, -1, m]) * a2.expand([batch, -1, m])
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
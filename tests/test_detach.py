@@ -0,0 +1,100 @@
+import pytest
+import torch
+
+import autograd
+from autograd import DTypeError, GradError, ShapeError, Tensor
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def test_detach():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = a * 2
+    c = b.detach()
+
+    assert not c.requires_grad
+    assert c.is_leaf
+    assert c.grad_fn is None
+    assert torch.allclose(b.to_torch(), c.to_torch())
+
+    # The detached tensor shares its storage
+    c[0] = 10.0
+    assert b.to_torch()[0] == 10.0
+
+
+def test_detach_backward():
+    a1 = torch.randn(n, requires_grad=True)
+    (a1 * a1.detach()).sum().backward()
+
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    (a2 * a2.detach()).sum().backward(None)
+
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+
+
+def test_is_leaf_grad_fn():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = Tensor.from_torch(torch.randn(n))
+
+    assert a.is_leaf and a.grad_fn is None
+    assert (a + b).grad_fn.name() == "AddOperation"
+    assert not (a + b).is_leaf
+    assert (b + b).is_leaf and (b + b).grad_fn is None
+    with autograd.no_grad():
+        assert (a + b).is_leaf
+
+
+def test_requires_grad_():
+    a = Tensor.from_torch(torch.randn(n))
+    assert a.requires_grad_() is not None
+    assert a.requires_grad
+    a.requires_grad = False
+    assert not a.requires_grad
+
+    with pytest.raises(GradError):
+        (Tensor.from_torch(torch.randn(n), requires_grad=True) * 2).requires_grad_(
+            False
+        )
+    with pytest.raises(DTypeError):
+        Tensor.from_torch(torch.arange(n)).requires_grad_()
+
+
+def test_requires_grad_intermediate():
+    # A tensor computed from constants becomes a leaf that accumulates
+    a = Tensor.from_torch(torch.randn(n))
+    b = Tensor.from_torch(torch.randn(n))
+    c = (a * b).requires_grad_()
+    assert c.is_leaf and c.grad_fn is None
+
+    (c * c).sum().backward()
+    assert torch.allclose(c.grad.to_torch(), 2 * c.to_torch())
+
+
+def test_zero_grad():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    (a * a).sum().backward(None)
+    (a * a).sum().backward(None)
+    assert torch.allclose(a.grad.to_torch(), 4 * a.to_torch())
+
+    a.zero_grad(set_to_none=False)
+    assert torch.equal(a.grad.to_torch(), torch.zeros(n))
+    a.zero_grad()
+    assert a.grad is None
+
+    (a * a).sum().backward(None)
+    assert torch.allclose(a.grad.to_torch(), 2 * a.to_torch())
+
+
+def test_grad_setter():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    a.grad = Tensor.from_torch(torch.ones(n))
+    assert torch.equal(a.grad.to_torch(), torch.ones(n))
+    a.grad = None
+    assert a.grad is None
+
+    with pytest.raises(ShapeError):
+        a.grad = Tensor.from_torch(torch.ones(n + 1))
+    with pytest.raises(DTypeError):
+        a.grad = Tensor.from_torch(torch.ones(n), dtype=autograd.float64)
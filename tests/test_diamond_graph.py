@@ -0,0 +1,55 @@
+import numpy as np
+import torch
+
+from autograd import Function, Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+n = 5
+m = 10
+depth = 40
+
+
+def test_diamond_graph():
+    shape = (n, m)
+
+    # torch implementation
+    a1 = torch.randn(*shape, requires_grad=True)
+    b1 = torch.randn(*shape, requires_grad=True)
+    c1 = a1 * b1
+    for _ in range(depth):
+        c1 = (c1 + c1) * torch.full(shape, 0.5)
+    grad1 = torch.ones_like(c1)
+    c1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = Tensor.from_torch(b1, requires_grad=True)
+    half = Tensor.from_torch(torch.full(shape, 0.5))
+    c2 = a2 * b2
+    for _ in range(depth):
+        c2 = (c2 + c2) * half
+    grad2 = Tensor.from_torch(grad1)
+    c2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch())
+    assert torch.allclose(b1.grad, b2.get_grad().to_torch())
+
+
+def test_branch_without_grad():
+    # A branch sending no gradient must not hold back the leaf it shares
+    class Blocked(Function):
+        @staticmethod
+        def forward(ctx, x):
+            return x * 1
+
+        @staticmethod
+        def backward(ctx, grad):
+            return None
+
+    x = Tensor.from_torch(torch.randn(n, m), requires_grad=True)
+    blocked = Blocked.apply(x * 2)
+    (blocked.sum() + x.sum()).backward()
+    assert torch.equal(x.grad.to_torch(), torch.ones(n, m))
@@ -0,0 +1,117 @@
+import numpy as np
+import pytest
+import torch
+
+import autograd
+from autograd import DTypeError, Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+n = 5
+m = 10
+
+
+def test_dtype_from_numpy():
+    assert Tensor.from_numpy(np.random.randn(n).astype(np.float32)).get_dtype() == autograd.float32
+    assert Tensor.from_numpy(np.random.randn(n)).get_dtype() == autograd.float32
+    assert (
+        Tensor.from_numpy(np.random.randn(n), dtype=autograd.float64).get_dtype()
+        == autograd.float64
+    )
+    assert Tensor.from_numpy(np.arange(n)).get_dtype() == autograd.int64
+    assert Tensor.from_numpy(np.arange(n) > 2).get_dtype() == autograd.bool
+
+
+def test_to_numpy_keeps_dtype():
+    a = np.arange(n * m).reshape(n, m)
+    result = Tensor.from_numpy(a).to_numpy()
+
+    assert result.dtype == np.int64
+    assert np.array_equal(a, result)
+
+
+def test_promotion():
+    a = np.random.randn(n, m).astype(np.float32)
+    b = np.random.randn(n, m)
+    c = np.arange(n * m).reshape(n, m)
+    result = Tensor.from_numpy(a) + Tensor.from_numpy(b, dtype=autograd.float64)
+    assert result.get_dtype() == autograd.float64
+    assert np.allclose(a + b, result.to_numpy(), atol=1e-6, rtol=1e-6)
+
+    result = Tensor.from_numpy(a) * Tensor.from_numpy(c)
+    assert result.get_dtype() == autograd.float32
+    assert np.allclose(a * c, result.to_numpy(), atol=1e-5, rtol=1e-5)
+
+
+def test_integer_arithmetic():
+    a = np.arange(n * m).reshape(n, m)
+    b = np.arange(m)
+    result = (Tensor.from_numpy(a) * Tensor.from_numpy(b) - Tensor.from_numpy(a)).to_numpy()
+
+    assert result.dtype == np.int64
+    assert np.array_equal(a * b - a, result)
+
+
+def test_large_integers():
+    # Above 2**53, int64 values are not exact in float64
+    big = 2**62 + 1
+    a = np.array([big, -big, 3])
+    t = Tensor.from_numpy(a)
+
+    assert np.array_equal(t.to(autograd.int64).to_numpy(), a)
+    assert np.array_equal((t + 1).to_numpy(), a + 1)
+    assert np.array_equal((t - 5).to_numpy(), a - 5)
+    assert (Tensor.from_numpy(np.array([True])) * big).to_numpy()[0] == big
+
+
+def test_to():
+    a = np.random.randn(n, m)
+    result = Tensor.from_numpy(a).to(autograd.int64).to_numpy()
+
+    assert np.array_equal(a.astype(np.int64), result)
+
+
+def test_to_backward():
+    a = np.random.randn(n, m).astype(np.float32)
+    b = np.random.randn(n, m)
+    a_tensor = Tensor.from_numpy(a, requires_grad=True)
+    b_tensor = Tensor.from_numpy(b)
+    (a_tensor.to(autograd.float64) * b_tensor).sum().backward(None)
+
+    assert a_tensor.get_grad().get_dtype() == autograd.float32
+    assert np.allclose(b, a_tensor.get_grad().to_numpy(), atol=1e-6, rtol=1e-6)
+
+
+def test_bool_sum():
+    a = np.random.randn(n, m) > 0
+    result = Tensor.from_numpy(a).sum(1)
+
+    assert result.get_dtype() == autograd.int64
+    assert np.array_equal(a.sum(1), result.to_numpy())
+
+
+def test_argmax_dtype():
+    a = np.random.randn(n, m)
+    result = Tensor.from_numpy(a).argmax(1)
+
+    assert result.get_dtype() == autograd.int64
+    assert np.array_equal(a.argmax(1), result.to_numpy())
+
+
+def test_integer_requires_grad():
+    with pytest.raises(DTypeError):
+        Tensor.from_numpy(np.arange(n), requires_grad=True)
+
+
+def test_integer_mean():
+    with pytest.raises(DTypeError):
+        Tensor.from_numpy(np.arange(n)).mean()
+
+
+def test_star_import():
+    # autograd.bool must not shadow the builtin
+    namespace = {}
+    exec("from autograd import *", namespace)
+    assert "bool" not in namespace
+    assert namespace["float64"] == autograd.float64
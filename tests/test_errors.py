@@ -0,0 +1,49 @@
+import numpy as np
+import pytest
+
+from autograd import GradError, ShapeError, Tensor
+
+np.random.seed(42)
+
+n = 5
+m = 10
+
+
+def test_shape_and_data_mismatch():
+    with pytest.raises(ShapeError):
+        Tensor([n, m], [0.0] * n, requires_grad=False, grad=None, graph=None)
+
+
+def test_broadcast_mismatch():
+    a = Tensor.from_numpy(np.random.randn(n, m))
+    b = Tensor.from_numpy(np.random.randn(n))
+    with pytest.raises(ShapeError):
+        a + b
+    with pytest.raises(ValueError):
+        a * b
+
+
+def test_matmul_mismatch():
+    a = Tensor.from_numpy(np.random.randn(n, m))
+    with pytest.raises(ShapeError):
+        a @ a
+
+
+def test_dimension_out_of_range():
+    a = Tensor.from_numpy(np.random.randn(n, m))
+    with pytest.raises(ShapeError):
+        a.sum(2)
+
+
+def test_non_scalar_backward():
+    a = Tensor.from_numpy(np.random.randn(n, m), requires_grad=True)
+    with pytest.raises(GradError):
+        (a + a).backward(None)
+    with pytest.raises(RuntimeError):
+        (a + a).backward(None)
+
+
+def test_backward_without_grad():
+    a = Tensor.from_numpy(np.random.randn(n, m))
+    with pytest.raises(GradError):
+        a.sum().backward(None)
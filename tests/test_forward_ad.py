@@ -0,0 +1,126 @@
+import pytest
+import torch
+import torch.autograd.forward_ad as TFA
+
+import autograd
+import autograd.forward_ad as FA
+from autograd import DTypeError, GradError, ShapeError, Tensor, cross_entropy
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def torch_jvp(f, x, v, w):
+    with TFA.dual_level():
+        return TFA.unpack_dual(f(TFA.make_dual(x, v), w)).tangent
+
+
+def autograd_jvp(f, x, v, w):
+    with FA.dual_level():
+        dual = FA.make_dual(Tensor.from_torch(x), Tensor.from_torch(v))
+        return FA.unpack_dual(f(dual, Tensor.from_torch(w))).tangent
+
+
+@pytest.mark.parametrize(
+    "f",
+    [
+        lambda x, w: x + w,
+        lambda x, w: x * w - x / (w * w + 1),
+        lambda x, w: -x * 3,
+        lambda x, w: x @ w.transpose(0, 1),
+        lambda x, w: x.transpose(0, 1) @ w,
+        lambda x, w: x.permute([1, 0]).reshape([n * 3]),
+        lambda x, w: x.sum(0, True) + w,
+        lambda x, w: x[1:, ::2] * 2,
+        lambda x, w: x[[0, 2]],
+        lambda x, w: x.reduce_sum(),
+        lambda x, w: x.sum(1),
+        lambda x, w: x.mean(0),
+        lambda x, w: x.prod(1),
+        lambda x, w: x.max(),
+        lambda x, w: x.relu() * w,
+        lambda x, w: x.softmax(1),
+        lambda x, w: x.log_softmax(0),
+        lambda x, w: x.logsumexp(1),
+        lambda x, w: x.exp() + x.sin() * x.tanh(),
+        lambda x, w: x.sigmoid() + x.gelu() + x.silu(),
+        lambda x, w: (x * x + 1).log() + (x * x + 1).sqrt(),
+        lambda x, w: (x * x + 1) ** x,
+        lambda x, w: x**3 + 2**x,
+        lambda x, w: x.contiguous().abs(),
+    ],
+)
+def test_jvp(f):
+    x = torch.rand(3, n) + 0.5
+    v = torch.randn(3, n)
+    w = torch.randn(3, n)
+
+    expected = torch_jvp(f, x, v, w)
+    result = autograd_jvp(f, x, v, w).to_torch()
+    # Scalars have shape [1] here and [] in torch
+    assert torch.allclose(expected.reshape(result.shape), result, atol=1e-5)
+
+
+def test_loss():
+    x, v = torch.randn(3, n), torch.randn(3, n)
+    target = torch.tensor([0, 4, 2])
+
+    with TFA.dual_level():
+        dual = TFA.make_dual(x, v)
+        expected = TFA.unpack_dual(torch.nn.functional.cross_entropy(dual, target))
+    with FA.dual_level():
+        dual = FA.make_dual(Tensor.from_torch(x), Tensor.from_torch(v))
+        result = FA.unpack_dual(cross_entropy(dual, Tensor.from_torch(target)))
+
+    assert torch.allclose(expected.primal, result.primal.to_torch())
+    assert torch.allclose(expected.tangent, result.tangent.to_torch(), atol=1e-5)
+
+
+def test_in_place():
+    x, v = torch.randn(n), torch.randn(n)
+    with FA.dual_level():
+        a = FA.make_dual(Tensor.from_torch(x), Tensor.from_torch(v))
+        b = Tensor.from_torch(torch.ones(n))
+        b *= a
+        assert torch.allclose(FA.unpack_dual(b).tangent.to_torch(), v)
+
+
+def test_unpack_dual():
+    x, v = torch.randn(n), torch.randn(n)
+    a = Tensor.from_torch(x)
+    with FA.dual_level():
+        dual = FA.make_dual(a, Tensor.from_torch(v))
+        primal, tangent = FA.unpack_dual(dual)
+        assert torch.allclose(primal.to_torch(), x)
+        assert torch.allclose(tangent.to_torch(), v)
+        assert FA.unpack_dual(primal).tangent is None
+        assert FA.unpack_dual(a).tangent is None
+        assert FA.unpack_dual(a * 2).tangent is None
+    assert FA.unpack_dual(dual).tangent is None
+
+
+def test_reverse_mode():
+    x = torch.randn(n)
+    a = Tensor.from_torch(x, requires_grad=True)
+    with FA.dual_level():
+        dual = FA.make_dual(a, Tensor.from_torch(torch.randn(n)))
+        (dual * dual).sum().backward()
+    assert torch.allclose(a.grad.to_torch(), 2 * x)
+
+
+def test_errors():
+    a = Tensor.from_torch(torch.randn(n))
+    with pytest.raises(GradError):
+        FA.make_dual(a, a)
+
+    with FA.dual_level():
+        with pytest.raises(GradError):
+            FA.enter_dual_level()
+        with pytest.raises(ShapeError):
+            FA.make_dual(a, Tensor.from_torch(torch.randn(n + 1)))
+        with pytest.raises(DTypeError):
+            FA.make_dual(a, Tensor.from_torch(torch.randn(n), dtype=autograd.float64))
+        with pytest.raises(DTypeError):
+            index = Tensor.from_torch(torch.arange(n))
+            FA.make_dual(index, index)
@@ -0,0 +1,182 @@
+import pytest
+import torch
+
+import autograd
+import autograd.forward_ad as FA
+from autograd import Function, GradError, Tensor
+from conftest import pair
+
+torch.manual_seed(42)
+
+n = 5
+
+
+class TorchExp(torch.autograd.Function):
+    @staticmethod
+    def forward(ctx, x):
+        y = x.exp()
+        ctx.save_for_backward(y)
+        return y
+
+    @staticmethod
+    def backward(ctx, grad):
+        (y,) = ctx.saved_tensors
+        return grad * y
+
+
+class Exp(Function):
+    @staticmethod
+    def forward(ctx, x):
+        y = x.exp()
+        ctx.save_for_backward(y)
+        return y
+
+    @staticmethod
+    def backward(ctx, grad):
+        (y,) = ctx.saved_tensors
+        return grad * y
+
+
+class ScaleAndClip(Function):
+    """Returns x * alpha and x clipped to [-1, 1] with a straight-through grad."""
+
+    @staticmethod
+    def forward(ctx, x, alpha):
+        ctx.alpha = alpha
+        return x * alpha, x.clamp(-1.0, 1.0)
+
+    @staticmethod
+    def backward(ctx, grad_scaled, grad_clipped):
+        return grad_scaled * ctx.alpha + grad_clipped, None
+
+    @staticmethod
+    def jvp(ctx, tangent, _):
+        return tangent * ctx.alpha, tangent
+
+
+def test_function():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = TorchExp.apply(a1), Exp.apply(a2)
+    (b1 * a1).sum().backward()
+    (b2 * a2).sum().backward()
+
+    assert torch.allclose(b1, b2.to_torch())
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+    assert b2.grad_fn.name() == "Exp"
+    assert b2.requires_grad and not b2.is_leaf
+
+
+def test_multiple_outputs():
+    a = Tensor.from_torch(torch.randn(n) * 2, requires_grad=True)
+    scaled, clipped = ScaleAndClip.apply(a, 3.0)
+    (scaled + clipped * 2).sum().backward()
+    assert torch.allclose(a.grad.to_torch(), torch.full((n,), 5.0))
+
+
+def test_multiple_outputs_single_backward():
+    calls = []
+
+    class SinCos(Function):
+        @staticmethod
+        def forward(ctx, x):
+            ctx.save_for_backward(x)
+            return x.sin(), x.cos()
+
+        @staticmethod
+        def backward(ctx, grad_sin, grad_cos):
+            calls.append(1)
+            (x,) = ctx.saved_tensors
+            return grad_sin * x.cos() - grad_cos * x.sin()
+
+    a1, a2 = pair(torch.randn(n))
+    sin, cos = SinCos.apply(a2)
+    (sin * 2 + cos * 3).sum().backward()
+    (a1.sin() * 2 + a1.cos() * 3).sum().backward()
+    assert len(calls) == 1
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+
+    # An output without a gradient gets zeros
+    a2.grad = None
+    SinCos.apply(a2)[1].sum().backward()
+    assert len(calls) == 2
+    assert torch.allclose(a2.grad.to_torch(), -a1.detach().sin())
+
+
+def test_gradcheck():
+    x = Tensor.from_torch(torch.randn(n), requires_grad=True, dtype=autograd.float64)
+    assert autograd.gradcheck(Exp.apply, x)
+    # The saved output is connected to the graph, so backward is differentiable
+    assert autograd.gradgradcheck(Exp.apply, x)
+
+
+def test_forward_ad():
+    x, v = torch.randn(n), torch.randn(n)
+    with FA.dual_level():
+        dual = FA.make_dual(Tensor.from_torch(x), Tensor.from_torch(v))
+        exp = FA.unpack_dual(Exp.apply(dual)).tangent
+        scaled, clipped = ScaleAndClip.apply(dual, 3.0)
+        assert torch.allclose(exp.to_torch(), v * x.exp())
+        assert torch.allclose(FA.unpack_dual(scaled).tangent.to_torch(), v * 3)
+        assert torch.allclose(FA.unpack_dual(clipped).tangent.to_torch(), v)
+
+
+def test_needs_input_grad():
+    class Check(Function):
+        @staticmethod
+        def forward(ctx, x, y, alpha):
+            assert ctx.needs_input_grad == (True, False, False)
+            return x * y * alpha
+
+        @staticmethod
+        def backward(ctx, grad):
+            return grad, None, None
+
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = Tensor.from_torch(torch.randn(n))
+    Check.apply(a, b, 2.0).sum().backward()
+    assert torch.equal(a.grad.to_torch(), torch.ones(n))
+
+
+def test_no_grad():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    with autograd.no_grad():
+        assert not Exp.apply(a).requires_grad
+    assert not Exp.apply(a.detach()).requires_grad
+
+
+def test_errors():
+    class Raises(Function):
+        @staticmethod
+        def forward(ctx, x):
+            return x * 2
+
+        @staticmethod
+        def backward(ctx, grad):
+            raise ValueError("backward failed")
+
+    class TooMany(Raises):
+        @staticmethod
+        def backward(ctx, grad):
+            return grad, grad
+
+    class WrongShape(Raises):
+        @staticmethod
+        def backward(ctx, grad):
+            return Tensor.from_torch(torch.ones(n + 1))
+
+    class NotTensors(Raises):
+        @staticmethod
+        def forward(ctx, x):
+            return 2
+
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    with pytest.raises(ValueError, match="backward failed"):
+        Raises.apply(a).sum().backward()
+    with pytest.raises(GradError):
+        TooMany.apply(a).sum().backward()
+    with pytest.raises(autograd.ShapeError):
+        WrongShape.apply(a).sum().backward()
+    with pytest.raises(GradError):
+        NotTensors.apply(a)
+    with pytest.raises(NotImplementedError):
+        Function.apply(a)
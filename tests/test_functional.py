@@ -0,0 +1,172 @@
+import threading
+
+import pytest
+import torch
+import torch.autograd.functional as TF
+
+import autograd.functional as AF
+from autograd import Function, GradError, Tensor
+
+torch.manual_seed(42)
+
+n = 4
+
+
+def to_torch(result):
+    if isinstance(result, tuple):
+        return tuple(to_torch(r) for r in result)
+    return result.to_torch()
+
+
+def assert_close(expected, result):
+    if isinstance(expected, tuple):
+        assert len(expected) == len(result)
+        for e, r in zip(expected, result):
+            assert_close(e, r)
+    else:
+        # Scalars have shape [1] here and [] in torch
+        if expected.dim() == 0:
+            expected = expected.reshape(1)
+        assert torch.allclose(expected, result, atol=1e-5)
+
+
+def single(x):
+    return (x * x).sin() * 2
+
+
+def multiple(x, y):
+    return (x.exp() @ y.transpose(0, 1)).sum(0), (x * 2).tanh()
+
+
+def scalar(x, y):
+    return ((x * y).sigmoid() * x).sum()
+
+
+def inputs(*shapes):
+    tensors = tuple(torch.randn(shape) for shape in shapes)
+    return tensors, tuple(Tensor.from_torch(t) for t in tensors)
+
+
+@pytest.mark.parametrize("vectorize", [False, True])
+def test_jacobian(vectorize):
+    (x1,), (x2,) = inputs((3, n))
+    assert_close(
+        TF.jacobian(single, x1),
+        to_torch(AF.jacobian(single, x2, vectorize=vectorize)),
+    )
+
+    t, a = inputs((2, n), (3, n))
+    assert_close(
+        TF.jacobian(multiple, t),
+        to_torch(AF.jacobian(multiple, a, vectorize=vectorize)),
+    )
+
+
+@pytest.mark.parametrize("vectorize", [False, True])
+def test_hessian(vectorize):
+    t, a = inputs((2, n), (2, n))
+    assert_close(
+        TF.hessian(scalar, t), to_torch(AF.hessian(scalar, a, vectorize=vectorize))
+    )
+
+    (x1,), (x2,) = inputs((n,))
+    f = lambda x: (x * x * x).prod()  # noqa: E731
+    assert_close(TF.hessian(f, x1), to_torch(AF.hessian(f, x2)))
+
+
+def test_jacobian_vectorize_python():
+    # Rows reaching python run serially on the calling thread
+    threads = set()
+
+    class Square(Function):
+        @staticmethod
+        def forward(ctx, x):
+            ctx.save_for_backward(x)
+            return x * x
+
+        @staticmethod
+        def backward(ctx, grad):
+            threads.add(threading.get_ident())
+            (x,) = ctx.saved_tensors
+            return grad * x * 2
+
+    def hooked(x):
+        y = x * 3
+        y.register_hook(lambda grad: threads.add(threading.get_ident()))
+        return y.exp()
+
+    (x1,), (x2,) = inputs((n,))
+    assert_close(
+        TF.jacobian(lambda x: x * x, x1),
+        to_torch(AF.jacobian(Square.apply, x2, vectorize=True)),
+    )
+    assert_close(
+        TF.jacobian(lambda x: (x * 3).exp(), x1),
+        to_torch(AF.jacobian(hooked, x2, vectorize=True)),
+    )
+    assert threads == {threading.get_ident()}
+
+
+def test_vjp():
+    t, a = inputs((2, n), (3, n))
+    v1 = (torch.randn(3), torch.randn(2, n))
+    v2 = tuple(Tensor.from_torch(v) for v in v1)
+
+    assert_close(TF.vjp(multiple, t, v1), to_torch(AF.vjp(multiple, a, v2)))
+
+
+def test_jvp():
+    t, a = inputs((2, n), (3, n))
+    v1 = (torch.randn(2, n), torch.randn(3, n))
+    v2 = tuple(Tensor.from_torch(v) for v in v1)
+
+    assert_close(TF.jvp(multiple, t, v1), to_torch(AF.jvp(multiple, a, v2)))
+
+
+def test_hvp():
+    t, a = inputs((2, n), (2, n))
+    v1 = (torch.randn(2, n), torch.randn(2, n))
+    v2 = tuple(Tensor.from_torch(v) for v in v1)
+
+    assert_close(TF.hvp(scalar, t, v1), to_torch(AF.hvp(scalar, a, v2)))
+
+
+def test_scalar_v():
+    x1, x2 = torch.tensor([0.3]), Tensor.from_torch(torch.tensor([0.3]))
+    assert_close(TF.jvp(single, x1), to_torch(AF.jvp(single, x2)))
+    assert_close(TF.vjp(single, x1), to_torch(AF.vjp(single, x2)))
+
+    _, (x2,) = inputs((n,))
+    with pytest.raises(ValueError):
+        AF.jvp(single, x2)
+
+
+def test_create_graph():
+    x1 = torch.randn(n, requires_grad=True)
+    x2 = Tensor.from_torch(x1, requires_grad=True)
+    f = lambda x: (x * x * x).sum()  # noqa: E731
+
+    TF.hessian(f, x1, create_graph=True).sum().backward()
+    AF.hessian(f, x2, create_graph=True).sum().backward()
+    assert torch.allclose(x1.grad, x2.grad.to_torch())
+
+    assert AF.hessian(f, x2).grad_fn is None
+
+
+def test_inputs_untouched():
+    x = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    AF.jacobian(single, x)
+    AF.hvp(lambda x: (x * x).sum(), x, x)
+    assert x.grad is None
+
+
+def test_strict():
+    _, (x2, y2) = inputs((n,), (n,))
+
+    assert torch.equal(
+        AF.jacobian(lambda x, y: x * 2, (x2, y2))[1].to_torch(), torch.zeros(n, n)
+    )
+    with pytest.raises(GradError):
+        AF.jacobian(lambda x, y: x * 2, (x2, y2), strict=True)
+    with pytest.raises(ValueError):
+        AF.hessian(single, x2)
@@ -0,0 +1,76 @@
+import pytest
+import torch
+
+import autograd
+from autograd import GradError, Tensor
+from conftest import pair
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def test_grad_leaves_untouched():
+    a1, a2 = pair(torch.randn(n))
+    a2.grad = Tensor.from_torch(torch.ones(n))
+
+    (g,) = autograd.grad((a2 * a2).sum(), a2)
+    assert torch.allclose(g.to_torch(), 2 * a1)
+    assert torch.equal(a2.grad.to_torch(), torch.ones(n))
+
+
+def test_grad_non_leaf():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = a1.exp(), a2.exp()
+    c1, c2 = b1 * a1, b2 * a2
+
+    expected = torch.autograd.grad(c1.sum(), [b1, a1])
+    result = autograd.grad(c2.sum(), [b2, a2])
+    for e, r in zip(expected, result):
+        assert torch.allclose(e, r.to_torch())
+    assert a2.grad is None and b2.grad is None
+
+
+def test_grad_independent_objectives():
+    w1, w2 = pair(torch.randn(n))
+    x = torch.randn(n)
+
+    y1, y2 = (w1 * x).sum(), (w2 * Tensor.from_torch(x)).sum()
+    z1, z2 = (w1 * w1).sum(), (w2 * w2).sum()
+    (gy,) = autograd.grad(y2, w2)
+    (gz,) = autograd.grad(z2, w2)
+
+    assert torch.allclose(gy.to_torch(), torch.autograd.grad(y1, w1)[0])
+    assert torch.allclose(gz.to_torch(), torch.autograd.grad(z1, w1)[0])
+
+
+def test_grad_outputs():
+    a1, a2 = pair(torch.randn(n))
+    v = torch.randn(n)
+
+    (g,) = autograd.grad(a2.sin(), a2, Tensor.from_torch(v))
+    assert torch.allclose(g.to_torch(), torch.autograd.grad(a1.sin(), a1, v)[0])
+
+    with pytest.raises(GradError):
+        autograd.grad(a2.sin(), a2)
+    with pytest.raises(ValueError):
+        autograd.grad([a2.sin().sum()], a2, [None, None])
+
+
+def test_allow_unused():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = Tensor.from_torch(torch.randn(n), requires_grad=True)
+
+    with pytest.raises(GradError):
+        autograd.grad((a * 2).sum(), [a, b])
+    g_a, g_b = autograd.grad((a * 2).sum(), [a, b], allow_unused=True)
+    assert torch.allclose(g_a.to_torch(), torch.full((n,), 2.0))
+    assert g_b is None
+
+
+def test_input_requires_grad():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = Tensor.from_torch(torch.randn(n))
+
+    with pytest.raises(GradError):
+        autograd.grad((a * b).sum(), b)
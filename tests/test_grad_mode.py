@@ -0,0 +1,112 @@
+import threading
+
+import pytest
+import torch
+
+import autograd
+from autograd import GradError, Tensor
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def leaf():
+    return Tensor.from_torch(torch.randn(n), requires_grad=True)
+
+
+def test_no_grad():
+    a = leaf()
+    with autograd.no_grad():
+        assert not autograd.is_grad_enabled()
+        b = (a * 2).exp()[1:]
+        assert not b.get_requires_grad()
+        assert b.get_graph() is None
+    assert autograd.is_grad_enabled()
+    assert (a * 2).get_requires_grad()
+
+
+def test_constants_not_recorded():
+    # Tensors that don't require grad keep no reference to their inputs
+    a = Tensor.from_torch(torch.randn(n))
+    b = (a * 2).exp()
+    assert b.get_graph() is None
+    assert b[1:].get_graph() is None
+
+
+def test_enable_grad():
+    a = leaf()
+    with autograd.no_grad():
+        with autograd.enable_grad():
+            assert autograd.is_grad_enabled()
+            assert (a * 2).get_requires_grad()
+        assert not autograd.is_grad_enabled()
+
+
+def test_decorator():
+    a = leaf()
+
+    @autograd.no_grad()
+    def f(x):
+        return x * 2
+
+    assert not f(a).get_requires_grad()
+    assert not f(a).get_requires_grad()
+    assert autograd.is_grad_enabled()
+
+
+def test_set_grad_enabled():
+    autograd.set_grad_enabled(False)
+    assert not autograd.is_grad_enabled()
+    autograd.set_grad_enabled(True)
+    assert autograd.is_grad_enabled()
+
+    with autograd.set_grad_enabled(False):
+        assert not autograd.is_grad_enabled()
+    assert autograd.is_grad_enabled()
+
+
+def test_inference_mode():
+    a = leaf()
+    with autograd.inference_mode():
+        assert autograd.is_inference_mode_enabled()
+        with autograd.enable_grad():
+            assert not autograd.is_grad_enabled()
+            assert not (a * a).get_requires_grad()
+        with autograd.inference_mode(False):
+            assert (a * 2).get_requires_grad()
+    assert not autograd.is_inference_mode_enabled()
+    assert autograd.is_grad_enabled()
+
+
+def test_exception_restores_mode():
+    with pytest.raises(KeyError):
+        with autograd.no_grad():
+            raise KeyError()
+    assert autograd.is_grad_enabled()
+
+
+def test_thread_local():
+    result = []
+    thread = threading.Thread(target=lambda: result.append(autograd.is_grad_enabled()))
+    with autograd.no_grad():
+        thread.start()
+        thread.join()
+    assert result == [True]
+
+
+def test_in_place_update():
+    a1 = torch.randn(n, requires_grad=True)
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    (a1 * a1).sum().backward()
+    (a2 * a2).sum().backward(None)
+
+    with torch.no_grad():
+        a1 -= 0.1 * a1.grad
+    with autograd.no_grad():
+        a2 -= 0.1 * a2.get_grad()
+
+    assert a2.get_requires_grad()
+    assert torch.allclose(a1, a2.to_torch())
+    with pytest.raises(GradError):
+        a2 -= 0.1 * a2.get_grad()
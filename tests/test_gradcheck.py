@@ -0,0 +1,73 @@
+import pytest
+import torch
+
+import autograd
+from autograd import GradError, Tensor
+
+torch.manual_seed(42)
+
+n = 4
+
+
+def inputs(*shapes):
+    return tuple(
+        Tensor.from_torch(torch.rand(shape) + 0.5, dtype=autograd.float64).requires_grad_()
+        for shape in shapes
+    )
+
+
+functions = [
+    lambda x, w: x * w - x / w,
+    lambda x, w: (x @ w.transpose(0, 1)).tanh(),
+    lambda x, w: ((x * x + 1).log() * w.sqrt()).sum(1),
+    lambda x, w: (x**w, 2**x),
+    lambda x, w: (x.softmax(1) * w, x.log_softmax(0), x.logsumexp(1)),
+    lambda x, w: (x[1:, ::2] * x.prod(), w.mean(0, True)),
+    lambda x, w: (x.sigmoid() + x.gelu() + x.silu() + x.exp() + x.sin()).sum(),
+    lambda x, w: x.permute([1, 0]).reshape([n * 3]).relu() * 2,
+]
+
+
+@pytest.mark.parametrize("f", functions)
+def test_gradcheck(f):
+    assert autograd.gradcheck(f, inputs((3, n), (3, n)))
+
+
+@pytest.mark.parametrize("f", functions)
+def test_gradgradcheck(f):
+    assert autograd.gradgradcheck(f, inputs((3, n), (3, n)))
+
+
+def test_constant_inputs():
+    (x,) = inputs((3, n))
+    w = Tensor.from_torch(torch.randn(3, n), dtype=autograd.float64)
+    target = Tensor.from_torch(torch.tensor([0, 3, 1]))
+    assert autograd.gradcheck(lambda x, t: autograd.cross_entropy(x, t), (x, target))
+    assert autograd.gradgradcheck(lambda x, w: (x * w).tanh(), (x, w))
+
+
+def test_grad_outputs():
+    x, v = inputs((3, n), (3, n))
+    assert autograd.gradgradcheck(lambda x: x.exp() * x, x, v)
+    with pytest.raises(ValueError):
+        autograd.gradgradcheck(lambda x: x.exp() * x, x, (v, v))
+
+
+def test_mismatch():
+    def wrong(x):
+        y = x * 1
+        # Finite differences are computed without gradients
+        if y.requires_grad:
+            y.register_hook(lambda g: g * 2)
+        return y.exp()
+
+    (x,) = inputs((3, n))
+    assert not autograd.gradcheck(wrong, x, raise_exception=False)
+    with pytest.raises(GradError):
+        autograd.gradcheck(wrong, x)
+
+
+def test_no_input_requires_grad():
+    x = Tensor.from_torch(torch.randn(3, n), dtype=autograd.float64)
+    with pytest.raises(GradError):
+        autograd.gradcheck(lambda x: x.exp(), x)
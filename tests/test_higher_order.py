@@ -0,0 +1,92 @@
+import pytest
+import torch
+
+import autograd
+from autograd import Tensor
+from conftest import pair
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def test_second_derivative():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    (g,) = autograd.grad((a * a * a).sum(), a, create_graph=True)
+    assert g.requires_grad
+    assert torch.allclose(g.to_torch(), 3 * a.to_torch() ** 2)
+
+    (gg,) = autograd.grad(g.sum(), a)
+    assert torch.allclose(gg.to_torch(), 6 * a.to_torch())
+    assert a.grad is None
+
+
+def test_backward_create_graph():
+    a1, a2 = pair(torch.randn(n))
+    (a1.exp() * a1.sin()).sum().backward(create_graph=True)
+    (a2.exp() * a2.sin()).sum().backward(create_graph=True)
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+
+    g1, g2 = a1.grad, a2.grad
+    a1.grad, a2.grad = None, None
+    g1.sum().backward()
+    g2.sum().backward()
+    assert torch.allclose(a1.grad, a2.grad.to_torch(), atol=1e-6)
+
+
+@pytest.mark.parametrize(
+    "f",
+    [
+        lambda x, w: (x.tanh() * w).sum(),
+        lambda x, w: (x.sigmoid() * x * w).sum(),
+        lambda x, w: ((x * x + 1).log() * w).sum(),
+        lambda x, w: ((x * x + 1).sqrt() * w).sum(),
+        lambda x, w: ((x * x + 1) ** x * w).sum(),
+        lambda x, w: (x.relu() * x * w).sum(),
+        lambda x, w: (x.abs() * x * w).sum(),
+        lambda x, w: (x.softmax(1) * x * w).sum(),
+        lambda x, w: (x.log_softmax(1) * x * w).sum(),
+        lambda x, w: x.logsumexp(1).sum() * (x * w).sum(),
+        lambda x, w: ((x @ w.transpose(0, 1)) ** 2).sum(),
+        lambda x, w: ((x.sum(1, True) * w) ** 2).sum(),
+        lambda x, w: (x[1:, ::2] ** 3).sum() * w.prod(),
+        lambda x, w: x.prod() * (x * w).sum(),
+        lambda x, w: ((x * w).mean(1) ** 2).sum(),
+    ],
+)
+def test_hessian_vector_product(f):
+    x1, x2 = pair(torch.rand(3, n) + 0.5)
+    w = torch.randn(3, n)
+    v = torch.randn(3, n)
+
+    (g1,) = torch.autograd.grad(f(x1, w), x1, create_graph=True)
+    (hv1,) = torch.autograd.grad(g1, x1, v)
+    (g2,) = autograd.grad(f(x2, Tensor.from_torch(w)), x2, create_graph=True)
+    (hv2,) = autograd.grad(g2, x2, Tensor.from_torch(v))
+
+    assert torch.allclose(g1, g2.to_torch(), atol=1e-5)
+    assert torch.allclose(hv1, hv2.to_torch(), atol=1e-4)
+
+
+def test_prod_with_zeros():
+    x = torch.rand(3, n, dtype=torch.float64) + 0.5
+    x[0, 1] = 0.0
+    x[1, 2] = x[1, 4] = 0.0
+    x[2] = 0.0
+    x = Tensor.from_torch(x, requires_grad=True, dtype=autograd.float64)
+    for dims in [None, [1], [0]]:
+        assert autograd.gradgradcheck(lambda x: x.prod(dims), x)
+
+
+def test_grad_multiple():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = pair(torch.randn(n))
+    u = torch.randn(n)
+
+    expected = torch.autograd.grad([(a1 * b1).sum(), a1 * a1], [a1, b1], [None, u])
+    result = autograd.grad(
+        [(a2 * b2).sum(), a2 * a2], [a2, b2], [None, Tensor.from_torch(u)]
+    )
+    for e, r in zip(expected, result):
+        assert torch.allclose(e, r.to_torch())
+    assert a2.grad is None and b2.grad is None
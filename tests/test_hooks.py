@@ -0,0 +1,111 @@
+import pytest
+import torch
+
+import autograd
+from autograd import GradError, ShapeError, Tensor
+from conftest import pair
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def test_intermediate_grad():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = a1.exp(), a2.exp()
+    c1, c2 = b1 * b1, b2 * b2
+    b1.retain_grad()
+    b2.retain_grad()
+    c1.sum().backward()
+    c2.sum().backward()
+
+    assert b2.retains_grad and not c2.retains_grad
+    assert torch.allclose(b1.grad, b2.grad.to_torch())
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+    assert c2.grad is None
+
+
+def test_tensor_hook():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = a1.sin(), a2.sin()
+    b1.register_hook(lambda g: g.clamp(-0.5, 0.5))
+    b2.register_hook(lambda g: g.clamp(-0.5, 0.5))
+    b1.retain_grad()
+    b2.retain_grad()
+    seen = []
+    a2.register_hook(lambda g: seen.append(g.to_torch()))
+    (b1 * b1 * 3).sum().backward()
+    (b2 * b2 * 3).sum().backward()
+
+    assert torch.allclose(b1.grad, b2.grad.to_torch())
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+    assert len(seen) == 1 and torch.allclose(seen[0], a1.grad)
+
+
+def test_hooks_order_and_remove():
+    a = Tensor.from_torch(torch.ones(n), requires_grad=True)
+    b = a * 1
+    b.register_hook(lambda g: g * 2)
+    handle = b.register_hook(lambda g: g + 1)
+    b.sum().backward(retain_graph=True)
+    assert torch.equal(a.grad.to_torch(), torch.full((n,), 3.0))
+
+    a.grad = None
+    handle.remove()
+    handle.remove()
+    b.sum().backward()
+    assert torch.equal(a.grad.to_torch(), torch.full((n,), 2.0))
+
+
+def test_hook_in_grad():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = a1 * a1, a2 * a2
+    b1.register_hook(lambda g: g * 3)
+    b2.register_hook(lambda g: g * 3)
+
+    (expected,) = torch.autograd.grad(b1.sum(), a1)
+    (result,) = autograd.grad(b2.sum(), a2)
+    assert torch.allclose(expected, result.to_torch())
+
+
+def test_node_hook():
+    a1, a2 = pair(torch.randn(n))
+    b1, b2 = pair(torch.randn(n))
+
+    def hook(grad_inputs, grad_output):
+        assert len(grad_inputs) == 2
+        return [grad_inputs[0] * 0 + 1, None]
+
+    c2 = a2 * b2
+    c2.grad_fn.register_hook(hook)
+    c2.sum().backward()
+
+    assert torch.equal(a2.grad.to_torch(), torch.ones(n))
+    assert b2.grad is None
+
+
+def test_hook_errors():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    with pytest.raises(GradError):
+        Tensor.from_torch(torch.randn(n)).register_hook(lambda g: g)
+    with pytest.raises(GradError):
+        Tensor.from_torch(torch.randn(n)).retain_grad()
+
+    b = a * 2
+    b.register_hook(lambda g: Tensor.from_torch(torch.ones(n + 1)))
+    with pytest.raises(ShapeError):
+        b.sum().backward()
+
+    def fail(g):
+        raise ValueError("fail")
+
+    # The exception raised by the hook reaches the caller unchanged
+    c = a * 2
+    c.register_hook(fail)
+    with pytest.raises(ValueError, match="fail"):
+        c.sum().backward()
+
+    d = a * 2
+    d.grad_fn.register_hook(lambda grad_inputs, grad_output: [None])
+    with pytest.raises(GradError):
+        d.sum().backward()
@@ -0,0 +1,92 @@
+import numpy as np
+import pytest
+import torch
+
+from autograd import DTypeError, GradError, Tensor
+
+np.random.seed(42)
+torch.manual_seed(42)
+
+n = 5
+m = 10
+
+
+def test_scalar_operators_forward():
+    a = np.random.randn(n, m)
+    t = Tensor.from_numpy(a)
+
+    assert np.allclose(a + 2, (t + 2).to_numpy())
+    assert np.allclose(2 + a, (2 + t).to_numpy())
+    assert np.allclose(a - 0.5, (t - 0.5).to_numpy())
+    assert np.allclose(1 - a, (1 - t).to_numpy())
+    assert np.allclose(a * 3, (t * 3).to_numpy())
+    assert np.allclose(0.5 * a, (0.5 * t).to_numpy())
+    assert np.allclose(a / 4, (t / 4).to_numpy())
+    assert np.allclose(1 / a, (1 / t).to_numpy())
+    assert np.allclose(a**2, (t**2).to_numpy())
+    assert np.allclose(2**a, (2**t).to_numpy())
+
+
+def test_scalar_dtype():
+    a = np.random.randn(n, m).astype(np.float32)
+    b = np.arange(n * m).reshape(n, m)
+
+    assert (Tensor.from_numpy(a) * 2.5).to_numpy().dtype == np.float32
+    assert (Tensor.from_numpy(b) * 2).to_numpy().dtype == np.int64
+    assert (Tensor.from_numpy(b) * 2.5).to_numpy().dtype == np.float32
+
+
+def test_scalar_operators_backward():
+    # torch implementation
+    a1 = torch.rand(n, m, requires_grad=True) + 0.5
+    a1.retain_grad()
+    b1 = (1 - a1) * 0.5 + 2 / a1 - 3**a1 + a1**3 / 4
+    grad1 = torch.rand_like(b1)
+    b1.backward(grad1)
+
+    # autograd implementation
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = (1 - a2) * 0.5 + 2 / a2 - 3**a2 + a2**3 / 4
+    grad2 = Tensor.from_torch(grad1)
+    b2.backward(grad2)
+
+    # Check gradients
+    assert torch.allclose(a1.grad, a2.get_grad().to_torch(), rtol=1e-4)
+
+
+def test_in_place_operators():
+    a = np.random.randn(n, m)
+    b = np.random.randn(m)
+    t = Tensor.from_numpy(a)
+    view = t[1]
+
+    a += b
+    t += Tensor.from_numpy(b)
+    a -= 1
+    t -= 1
+    a *= 2
+    t *= 2
+    a /= b
+    t /= Tensor.from_numpy(b)
+    a **= 2
+    t **= 2
+
+    assert np.allclose(a, t.to_numpy())
+    assert np.allclose(a[1], view.to_numpy())
+
+
+def test_in_place_errors():
+    t = Tensor.from_numpy(np.arange(n))
+    with pytest.raises(DTypeError):
+        t += 0.5
+
+    t = Tensor.from_numpy(np.random.randn(n), requires_grad=True)
+    with pytest.raises(GradError):
+        t *= 2
+
+    # The operation saved the tensor before it was modified
+    u = Tensor.from_numpy(np.random.randn(n))
+    loss = (t * u).sum()
+    u += 1
+    with pytest.raises(GradError):
+        loss.backward(None)
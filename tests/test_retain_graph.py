@@ -0,0 +1,53 @@
+import pytest
+import torch
+
+from autograd import GradError, Tensor
+
+torch.manual_seed(42)
+
+n = 5
+
+
+def test_backward_twice_fails():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = (a * a).sum()
+    b.backward()
+
+    with pytest.raises(GradError):
+        b.backward()
+    # Nothing is accumulated by the failed call
+    assert torch.allclose(a.grad.to_torch(), 2 * a.to_torch())
+
+
+def test_shared_graph_freed():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = a * a
+    b.sum().backward()
+
+    with pytest.raises(GradError):
+        (b * 2).sum().backward()
+
+
+def test_retain_graph():
+    a1 = torch.randn(n, requires_grad=True)
+    b1 = (a1 * a1).exp().sum()
+    b1.backward(retain_graph=True)
+    b1.backward()
+
+    a2 = Tensor.from_torch(a1, requires_grad=True)
+    b2 = (a2 * a2).exp().sum()
+    b2.backward(retain_graph=True)
+    b2.backward()
+
+    assert torch.allclose(a1.grad, a2.grad.to_torch())
+    with pytest.raises(GradError):
+        b2.backward()
+
+
+def test_grad_fn_after_release():
+    a = Tensor.from_torch(torch.randn(n), requires_grad=True)
+    b = (a * a).sum()
+    b.backward()
+
+    assert b.grad_fn.name() == "SumOperation"
+    assert not b.is_leaf
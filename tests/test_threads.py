@@ -0,0 +1,80 @@
+import pytest
+import torch
+
+import autograd
+from conftest import pair
+
+torch.manual_seed(42)
+
+n = 8
+branches = 6
+
+
+@pytest.fixture
+def num_threads():
+    previous = autograd.get_num_threads()
+    yield
+    autograd.set_num_threads(previous)
+
+
+def wide(x, ws):
+    # Independent branches, run in parallel by the engine
+    out = None
+    for w in ws:
+        y = (x @ w).tanh() @ w
+        out = y if out is None else out + y
+    return out.sum()
+
+
+def test_set_num_threads(num_threads):
+    autograd.set_num_threads(3)
+    assert autograd.get_num_threads() == 3
+    with pytest.raises(ValueError):
+        autograd.set_num_threads(0)
+
+
+@pytest.mark.parametrize("threads", [1, 4])
+def test_parallel_branches(num_threads, threads):
+    autograd.set_num_threads(threads)
+    x1, x2 = pair(torch.randn(n, n))
+    ws = [pair(torch.randn(n, n)) for _ in range(branches)]
+    wide(x1, [w1 for w1, _ in ws]).backward()
+    wide(x2, [w2 for _, w2 in ws]).backward()
+
+    assert torch.allclose(x1.grad, x2.grad.to_torch(), atol=1e-4)
+    for w1, w2 in ws:
+        assert torch.allclose(w1.grad, w2.grad.to_torch(), atol=1e-4)
+
+
+def test_parallel_create_graph(num_threads):
+    autograd.set_num_threads(4)
+    x1, x2 = pair(torch.randn(n, n))
+    (g1,) = torch.autograd.grad(
+        ((x1 @ x1).sin() + (x1 * x1).cos()).sum(), x1, create_graph=True
+    )
+    (g2,) = autograd.grad(
+        ((x2 @ x2).sin() + (x2 * x2).cos()).sum(), x2, create_graph=True
+    )
+    g1.sum().backward()
+    g2.sum().backward()
+
+    assert torch.allclose(x1.grad, x2.grad.to_torch(), atol=1e-4)
+
+
+def test_parallel_python_hooks(num_threads):
+    # Python hooks run on the calling thread, alongside pooled branches
+    autograd.set_num_threads(4)
+    x1, x2 = pair(torch.randn(n))
+    seen = []
+    out1, out2 = 0, 0
+    for i in range(branches):
+        y1, y2 = (x1 * (i + 1)).exp(), (x2 * (i + 1)).exp()
+        if i % 2:
+            y1.register_hook(lambda g: g * 2)
+            y2.register_hook(lambda g: seen.append(i) or g * 2)
+        out1, out2 = out1 + y1, out2 + y2
+    out1.sum().backward()
+    out2.sum().backward()
+
+    assert len(seen) == branches // 2
+    assert torch.allclose(x1.grad, x2.grad.to_torch(), atol=1e-4)